@@ -1,11 +1,17 @@
 #[path = "commands/auth.rs"]
 pub mod rad_auth;
+#[path = "commands/bundle.rs"]
+pub mod rad_bundle;
 #[path = "commands/checkout.rs"]
 pub mod rad_checkout;
 #[path = "commands/clone.rs"]
 pub mod rad_clone;
 #[path = "commands/edit.rs"]
 pub mod rad_edit;
+#[path = "commands/fork.rs"]
+pub mod rad_fork;
+#[path = "commands/gc.rs"]
+pub mod rad_gc;
 #[path = "commands/help.rs"]
 pub mod rad_help;
 #[path = "commands/init.rs"]
@@ -14,10 +20,14 @@ pub mod rad_init;
 pub mod rad_inspect;
 #[path = "commands/issue.rs"]
 pub mod rad_issue;
+#[path = "commands/label.rs"]
+pub mod rad_label;
 #[path = "commands/ls.rs"]
 pub mod rad_ls;
 #[path = "commands/merge.rs"]
 pub mod rad_merge;
+#[path = "commands/node.rs"]
+pub mod rad_node;
 #[path = "commands/patch.rs"]
 pub mod rad_patch;
 #[path = "commands/path.rs"]
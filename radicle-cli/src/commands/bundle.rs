@@ -0,0 +1,165 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use radicle::git::Oid;
+use radicle::prelude::*;
+use radicle::storage::git::bundle;
+use radicle::storage::{ReadStorage, RefUpdate, WriteStorage};
+
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+pub const HELP: Help = Help {
+    name: "bundle",
+    description: "Export and import projects as offline bundles",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad bundle create <id> <file> [--known <oid>...]
+    rad bundle import <id> <file>
+
+    Bundles hold the references of every remote of a project, including
+    their signed refs and identity branches, and can be carried to a
+    machine without network access.
+
+    Importing a bundle verifies it like a fetch would, and leaves storage
+    untouched if verification fails.
+
+Options
+
+    --known <oid>       Leave out objects reachable from the given commit,
+                        eg. a tip of a previously exported bundle (may be
+                        specified multiple times)
+    --help              Print help
+"#,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Operation {
+    Create { known: Vec<Oid> },
+    Import,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub id: Id,
+    pub path: PathBuf,
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<Operation> = None;
+        let mut id: Option<Id> = None;
+        let mut path: Option<PathBuf> = None;
+        let mut known = Vec::new();
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("known") => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+                    let oid = Oid::from_str(&val).map_err(|_| anyhow!("invalid oid '{}'", val))?;
+
+                    known.push(oid);
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "create" => op = Some(Operation::Create { known: vec![] }),
+                    "import" => op = Some(Operation::Import),
+                    unknown => return Err(anyhow!("unknown operation '{}'", unknown)),
+                },
+                Value(val) if id.is_none() => {
+                    let val = val.to_string_lossy();
+
+                    if let Ok(val) = Id::from_human(&val) {
+                        id = Some(val);
+                    } else {
+                        return Err(anyhow!("invalid ID '{}'", val));
+                    }
+                }
+                Value(val) if path.is_none() => {
+                    path = Some(PathBuf::from(val));
+                }
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+        let op = match op.ok_or_else(|| anyhow!("an operation must be specified"))? {
+            Operation::Create { .. } => Operation::Create { known },
+            Operation::Import if !known.is_empty() => {
+                return Err(anyhow!("`--known` can only be used when creating a bundle"));
+            }
+            Operation::Import => Operation::Import,
+        };
+
+        Ok((
+            Options {
+                id: id.ok_or_else(|| anyhow!("a project ID must be specified"))?,
+                path: path.ok_or_else(|| anyhow!("a bundle file must be specified"))?,
+                op,
+            },
+            vec![],
+        ))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = &profile.storage;
+
+    match options.op {
+        Operation::Create { known } => {
+            if !storage.inventory()?.contains(&options.id) {
+                anyhow::bail!("project {} was not found in storage", options.id);
+            }
+            let repo = storage.repository(options.id)?;
+            bundle::create(&repo, &options.path, known)?;
+
+            term::success!(
+                "Bundle of {} written to {}",
+                term::format::tertiary(options.id),
+                options.path.display()
+            );
+        }
+        Operation::Import => {
+            let exists = storage.inventory()?.contains(&options.id);
+            let repo = storage.repository(options.id)?;
+            let updates = match bundle::import(&repo, &options.path) {
+                Ok(updates) => updates,
+                Err(err) => {
+                    // Don't leave an empty repository behind.
+                    if !exists {
+                        fs::remove_dir_all(repo.path())?;
+                    }
+                    return Err(err.into());
+                }
+            };
+
+            for update in &updates {
+                if matches!(update, RefUpdate::Skipped { .. }) {
+                    continue;
+                }
+                term::indented(&term::format::dim(update));
+            }
+            term::success!(
+                "Bundle {} imported into {}",
+                options.path.display(),
+                term::format::tertiary(options.id)
+            );
+        }
+    }
+
+    Ok(())
+}
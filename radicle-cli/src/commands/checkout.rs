@@ -119,7 +119,7 @@ pub fn execute(options: Options, profile: &Profile) -> anyhow::Result<PathBuf> {
     setup_remotes(
         project::SetupRemote {
             project: id,
-            default_branch: payload.default_branch,
+            default_branch: payload.default_branch().clone(),
             repo: &repo,
             fetch: true,
             tracking: true,
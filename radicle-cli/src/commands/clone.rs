@@ -88,7 +88,7 @@ pub fn clone(id: Id, _interactive: Interactive, ctx: impl term::Context) -> anyh
     node.fetch(&id).context("fetch")?;
 
     // Create a local fork of the project, under our own id.
-    rad::fork(id, &signer, &profile.storage).context("fork")?;
+    rad::fork(id, None, &signer, &profile.storage).context("fork")?;
 
     let doc = profile
         .storage
@@ -104,7 +104,7 @@ pub fn clone(id: Id, _interactive: Interactive, ctx: impl term::Context) -> anyh
         .map(|d| *d.id)
         .filter(|id| id != profile.id())
         .collect::<Vec<_>>();
-    let default_branch = doc.payload.default_branch.clone();
+    let default_branch = doc.payload.default_branch().clone();
 
     // Setup tracking for project delegates.
     setup_remotes(
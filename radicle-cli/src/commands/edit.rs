@@ -3,8 +3,10 @@ use std::str::FromStr;
 
 use anyhow::{anyhow, Context as _};
 
-use radicle::identity::Id;
-use radicle::storage::{ReadStorage, WriteStorage};
+use radicle::cob::proposal::{ProposalId, Proposals, Status};
+use radicle::crypto::PublicKey;
+use radicle::identity::{Did, Id};
+use radicle::storage::{ReadStorage, WriteRepository, WriteStorage};
 
 use crate::terminal as term;
 use crate::terminal::args::{Args, Error, Help};
@@ -16,20 +18,83 @@ pub const HELP: Help = Help {
     usage: r#"
 Usage
 
-    rad edit [<id>] [<option>...]
+    rad edit [<id>] [--title <title>] [--description <text>]
+    rad edit [<id>] --revoke <did> [--threshold <n>] [--title <title>] [--description <text>]
+    rad edit [<id>] --rotate <did> --to <did> [--title <title>] [--description <text>]
+    rad edit [<id>] --list
+    rad edit [<id>] --sign <proposal-id>
+    rad edit [<id>] --commit <proposal-id>
 
     Edits the identity document pointed to by the ID. If it isn't specified,
     the current project is edited.
 
+    If the document has a threshold of one, and you are a delegate, the
+    change is committed directly. Otherwise, a proposal is created, which
+    other delegates can sign. Once enough delegates signed it, the proposal
+    can be committed by any delegate.
+
+    Delegate keys that are lost or compromised can be revoked, or rotated
+    to a new key. Revoked keys can never be delegates again. Revoking a
+    delegate doesn't change the threshold: if it would be higher than the
+    number of remaining delegates, a new threshold must be given.
+
 Options
 
-    --help              Print help
+    --title <title>             Title of the proposal
+    --description <text>        Description of the proposal
+    --revoke <did>              Revoke a delegate
+    --threshold <n>             The new threshold of the document, when revoking
+    --rotate <did>              Replace a delegate's key, and revoke the old key
+    --to <did>                  The new key of the delegate being rotated
+    --list                      List open proposals
+    --sign <proposal-id>        Sign a proposal
+    --commit <proposal-id>      Commit a proposal that reached quorum
+    --help                      Print help
 "#,
 };
 
+/// A change to the identity document.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub enum Change {
+    /// Edit the document payload in an editor.
+    #[default]
+    Edit,
+    /// Revoke a delegate, optionally setting a new threshold.
+    Revoke(PublicKey, Option<usize>),
+    /// Replace a delegate's key.
+    Rotate(PublicKey, PublicKey),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Operation {
+    Update {
+        change: Change,
+        title: Option<String>,
+        description: Option<String>,
+    },
+    List,
+    Sign {
+        proposal: ProposalId,
+    },
+    Commit {
+        proposal: ProposalId,
+    },
+}
+
+impl Default for Operation {
+    fn default() -> Self {
+        Self::Update {
+            change: Change::default(),
+            title: None,
+            description: None,
+        }
+    }
+}
+
 #[derive(Default, Debug, Eq, PartialEq)]
 pub struct Options {
     pub id: Option<Id>,
+    pub op: Operation,
 }
 
 impl Args for Options {
@@ -38,12 +103,50 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut id: Option<Id> = None;
+        let mut op: Option<Operation> = None;
+        let mut title: Option<String> = None;
+        let mut description: Option<String> = None;
+        let mut revoke: Option<PublicKey> = None;
+        let mut rotate: Option<PublicKey> = None;
+        let mut to: Option<PublicKey> = None;
+        let mut threshold: Option<usize> = None;
 
         while let Some(arg) = parser.next()? {
             match arg {
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
+                Long("title") => {
+                    title = Some(parser.value()?.to_string_lossy().into());
+                }
+                Long("description") => {
+                    description = Some(parser.value()?.to_string_lossy().into());
+                }
+                Long("revoke") => {
+                    revoke = Some(key(parser.value()?)?);
+                }
+                Long("rotate") => {
+                    rotate = Some(key(parser.value()?)?);
+                }
+                Long("to") => {
+                    to = Some(key(parser.value()?)?);
+                }
+                Long("threshold") => {
+                    threshold = Some(parser.value()?.parse()?);
+                }
+                Long("list") if op.is_none() => {
+                    op = Some(Operation::List);
+                }
+                Long("sign") if op.is_none() => {
+                    op = Some(Operation::Sign {
+                        proposal: proposal_id(parser.value()?)?,
+                    });
+                }
+                Long("commit") if op.is_none() => {
+                    op = Some(Operation::Commit {
+                        proposal: proposal_id(parser.value()?)?,
+                    });
+                }
                 Value(val) if id.is_none() => {
                     let val = val.to_string_lossy();
 
@@ -56,11 +159,41 @@ impl Args for Options {
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
             }
         }
+        if threshold.is_some() && revoke.is_none() {
+            anyhow::bail!("`--threshold` can only be used with `--revoke`");
+        }
+        let change = match (revoke, rotate, to) {
+            (None, None, None) => Change::Edit,
+            (Some(key), None, None) => Change::Revoke(key, threshold),
+            (None, Some(old), Some(new)) => Change::Rotate(old, new),
+            (None, Some(_), None) => anyhow::bail!("`--rotate` requires a new key, via `--to`"),
+            _ => anyhow::bail!("invalid combination of `--revoke`, `--rotate` and `--to`"),
+        };
+        let op = op.unwrap_or(Operation::Update {
+            change,
+            title,
+            description,
+        });
+
+        Ok((Options { id, op }, vec![]))
+    }
+}
+
+fn key(val: OsString) -> anyhow::Result<PublicKey> {
+    let val = val.to_string_lossy();
 
-        Ok((Options { id }, vec![]))
+    if let Ok(did) = Did::decode(&val) {
+        Ok(*did)
+    } else {
+        PublicKey::from_str(&val).map_err(|_| anyhow!("invalid key or DID '{}'", val))
     }
 }
 
+fn proposal_id(val: OsString) -> anyhow::Result<ProposalId> {
+    let val = val.to_string_lossy();
+    ProposalId::from_str(&val).map_err(|_| anyhow!("invalid proposal id '{}'", val))
+}
+
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
     let signer = term::signer(&profile)?;
@@ -71,29 +204,125 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         .or_else(|| radicle::rad::cwd().ok().map(|(_, id)| id))
         .context("Couldn't get ID from either command line or cwd")?;
 
-    let mut project = storage
+    let project = storage
         .get(signer.public_key(), id)?
         .context("No project with such ID exists")?;
 
     let repo = storage.repository(id)?;
+    let mut proposals = Proposals::open(*signer.public_key(), &repo)?;
+
+    match options.op {
+        Operation::Update {
+            change,
+            title,
+            description,
+        } => {
+            let message = match change {
+                Change::Edit => "Updated payload",
+                Change::Revoke(..) | Change::Rotate(..) => "Update identity document",
+            };
+            let updated = match change {
+                Change::Edit => {
+                    let payload = serde_json::to_string_pretty(&project.payload)?;
+                    let Some(payload) = term::Editor::new().edit(&payload)? else {
+                        return Err(anyhow!("Operation aborted!"));
+                    };
+                    let mut updated = project.clone();
+                    updated.payload = serde_json::from_str(&payload)?;
+                    updated
+                }
+                Change::Revoke(key, threshold) => {
+                    let mut updated = project.clone();
+                    if let Some(threshold) = threshold {
+                        updated.threshold = threshold;
+                    }
+                    updated.revoke(&key)?;
+                    updated.unverified().verified()?
+                }
+                Change::Rotate(old, new) => {
+                    let mut updated = project.clone();
+                    if !updated.rotate(&old, new) {
+                        anyhow::bail!("{} is not a delegate, or {} can't be a delegate", old, new);
+                    }
+                    updated
+                }
+            };
+            if updated == project {
+                term::info!("No changes were made to the identity document");
+                return Ok(());
+            }
+            let is_delegate = project
+                .delegates
+                .iter()
+                .any(|d| *d.id == *signer.public_key());
 
-    let payload = serde_json::to_string_pretty(&project.payload)?;
-    match term::Editor::new().edit(&payload)? {
-        Some(updated_payload) => {
-            project.payload = serde_json::from_str(&updated_payload)?;
-            project.sign(&signer).and_then(|(_, sig)| {
-                project.update(
+            if is_delegate && project.threshold == 1 {
+                let (_, sig) = updated.sign(&signer)?;
+                updated.update(
                     signer.public_key(),
-                    "Updated payload",
+                    title.as_deref().unwrap_or(message),
                     &[(signer.public_key(), sig)],
                     &repo,
-                )
-            })?;
+                )?;
+                repo.sign_refs(&signer)?;
+
+                term::success!("Update successful!");
+            } else {
+                let title = match title {
+                    Some(title) => title,
+                    None => term::text_input("Title", None)?,
+                };
+                let proposal =
+                    proposals.create(title, description.unwrap_or_default(), updated, &signer)?;
+
+                term::success!(
+                    "Proposal {} created, it needs {} delegate signature(s) to be committed",
+                    term::format::cob(proposal.id()),
+                    project.threshold
+                );
+            }
         }
-        None => return Err(anyhow!("Operation aborted!")),
-    }
+        Operation::List => {
+            for result in proposals.all()? {
+                let (id, proposal, _) = result?;
+                if *proposal.status() != Status::Open {
+                    continue;
+                }
+                println!(
+                    "{} {} {}",
+                    term::format::cob(&id),
+                    proposal.title(),
+                    term::format::dim(format!(
+                        "({}/{} signatures)",
+                        proposal.quorum(&project).count(),
+                        project.threshold
+                    ))
+                );
+            }
+        }
+        Operation::Sign { proposal } => {
+            let mut proposal = proposals.get_mut(&proposal)?;
+            proposal.sign(&signer)?;
+
+            term::success!(
+                "Signed proposal {} ({}/{} signatures)",
+                term::format::cob(proposal.id()),
+                proposal.quorum(&project).count(),
+                project.threshold
+            );
+        }
+        Operation::Commit { proposal } => {
+            let mut proposal = proposals.get_mut(&proposal)?;
+            let head = proposal.commit(&signer)?;
+            repo.sign_refs(&signer)?;
 
-    term::success!("Update successful!");
+            term::success!(
+                "Committed proposal {} as {}",
+                term::format::cob(proposal.id()),
+                term::format::oid(head)
+            );
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,111 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _};
+
+use radicle::prelude::*;
+use radicle::rad;
+
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+pub const HELP: Help = Help {
+    name: "fork",
+    description: "Fork a project into your own namespace",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad fork [<id>] [--remote <nid>]
+
+    Forks a project in storage into your own namespace, and signs the
+    resulting refs. If the ID isn't specified, the current project is
+    forked.
+
+    By default, the canonical head of the default branch and the canonical
+    tags are forked. With `--remote`, all branches and tags of the given
+    remote are forked instead, eg. to continue someone else's work.
+
+Options
+
+    --remote <nid>      Fork the branches and tags of the given remote
+    --help              Print help
+"#,
+};
+
+#[derive(Debug)]
+pub struct Options {
+    pub id: Option<Id>,
+    pub remote: Option<NodeId>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut id: Option<Id> = None;
+        let mut remote: Option<NodeId> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("remote") => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+
+                    remote = Some(
+                        NodeId::from_str(&val).map_err(|_| anyhow!("invalid Node ID '{}'", val))?,
+                    );
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if id.is_none() => {
+                    let val = val.to_string_lossy();
+
+                    if let Ok(val) = Id::from_human(&val) {
+                        id = Some(val);
+                    } else {
+                        return Err(anyhow!("invalid ID '{}'", val));
+                    }
+                }
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+
+        Ok((Options { id, remote }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = &profile.storage;
+
+    let id = options
+        .id
+        .or_else(|| rad::cwd().ok().map(|(_, id)| id))
+        .context("Couldn't get ID from either command line or cwd")?;
+
+    let signed = rad::fork(id, options.remote.as_ref(), &signer, storage)?;
+
+    for (name, oid) in signed.refs.iter() {
+        term::indented(&format!(
+            "{} {}",
+            term::format::oid(*oid),
+            term::format::dim(name)
+        ));
+    }
+    match options.remote {
+        Some(remote) => term::success!(
+            "Forked {} from {}",
+            term::format::tertiary(id),
+            term::format::node(&remote)
+        ),
+        None => term::success!("Forked {}", term::format::tertiary(id)),
+    }
+
+    Ok(())
+}
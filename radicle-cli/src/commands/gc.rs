@@ -0,0 +1,173 @@
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use radicle::node;
+use radicle::prelude::*;
+use radicle::storage::git::maintenance;
+
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+pub const HELP: Help = Help {
+    name: "gc",
+    description: "Verify and garbage collect storage",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad gc [<id>] [<option>...]
+
+    Verifies the remotes of every project in storage, or only the given
+    project, and reclaims unreferenced objects.
+
+Options
+
+    --dry-run           Only report what would be done
+    --repair            Remove remotes that fail verification
+    --block <nid>       Remove the given remote (may be specified multiple times)
+    --prune             Remove remotes that aren't tracked, according to the
+                        node's remote tracking policy
+    --no-gc             Don't run garbage collection
+    --help              Print help
+"#,
+};
+
+#[derive(Debug)]
+pub struct Options {
+    pub id: Option<Id>,
+    pub dry_run: bool,
+    pub repair: bool,
+    pub gc: bool,
+    pub blocked: HashSet<NodeId>,
+    pub prune: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut id: Option<Id> = None;
+        let mut dry_run = false;
+        let mut repair = false;
+        let mut gc = true;
+        let mut prune = false;
+        let mut blocked = HashSet::new();
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("dry-run") => dry_run = true,
+                Long("repair") => repair = true,
+                Long("no-gc") => gc = false,
+                Long("prune") => prune = true,
+                Long("block") => {
+                    blocked.insert(node_id(parser.value()?)?);
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if id.is_none() => {
+                    let val = val.to_string_lossy();
+
+                    if let Ok(val) = Id::from_human(&val) {
+                        id = Some(val);
+                    } else {
+                        return Err(anyhow!("invalid ID '{}'", val));
+                    }
+                }
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+
+        Ok((
+            Options {
+                id,
+                dry_run,
+                repair,
+                gc,
+                blocked,
+                prune,
+            },
+            vec![],
+        ))
+    }
+}
+
+fn node_id(val: OsString) -> anyhow::Result<NodeId> {
+    let val = val.to_string_lossy();
+    NodeId::from_str(&val).map_err(|_| anyhow!("invalid Node ID '{}'", val))
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = &profile.storage;
+    let tracking = if options.prune {
+        let config = node::config::Config::load(&profile.paths().node())?;
+        Some(config.remote_tracking)
+    } else {
+        None
+    };
+    let opts = maintenance::Options {
+        local: *profile.id(),
+        dry_run: options.dry_run,
+        repair: options.repair,
+        gc: options.gc,
+        blocked: options.blocked,
+        tracking,
+    };
+
+    let reports = if let Some(id) = options.id {
+        vec![maintenance::repository(&storage.repository(id)?, &opts)?]
+    } else {
+        maintenance::run(storage, &opts)?
+    };
+
+    let removed = if opts.dry_run {
+        "would be removed"
+    } else {
+        "removed"
+    };
+
+    for report in &reports {
+        if report.is_clean() {
+            term::success!("{}", term::format::tertiary(report.id));
+            continue;
+        }
+        term::info!("{}", term::format::tertiary(report.id));
+
+        for (remote, err) in &report.unverified {
+            term::indented(&format!(
+                "{} {} {}",
+                term::format::negative("✗"),
+                term::format::node(remote),
+                term::format::dim(err)
+            ));
+        }
+        for (remote, reason) in &report.removed {
+            term::indented(&format!(
+                "{} {} {}",
+                term::format::yellow("-"),
+                term::format::node(remote),
+                term::format::dim(format!("{removed} ({reason})"))
+            ));
+        }
+        if report.quarantines > 0 {
+            term::indented(&format!(
+                "{} {} abandoned quarantine(s) {}",
+                term::format::yellow("-"),
+                report.quarantines,
+                term::format::dim(removed)
+            ));
+        }
+    }
+    if opts.dry_run {
+        term::tip!("Dry run: storage was not modified.");
+    }
+
+    Ok(())
+}
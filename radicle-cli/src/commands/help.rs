@@ -14,15 +14,20 @@ pub const HELP: Help = Help {
 
 const COMMANDS: &[Help] = &[
     rad_auth::HELP,
+    rad_bundle::HELP,
     rad_checkout::HELP,
     rad_clone::HELP,
     rad_edit::HELP,
+    rad_fork::HELP,
+    rad_gc::HELP,
     rad_help::HELP,
     rad_init::HELP,
     rad_inspect::HELP,
     rad_issue::HELP,
+    rad_label::HELP,
     rad_ls::HELP,
     rad_merge::HELP,
+    rad_node::HELP,
     rad_patch::HELP,
     rad_path::HELP,
     rad_push::HELP,
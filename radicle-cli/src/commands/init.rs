@@ -212,13 +212,13 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
                 term::blank();
             }
 
-            if options.set_upstream || git::branch_remote(&repo, &doc.default_branch).is_err() {
+            if options.set_upstream || git::branch_remote(&repo, doc.default_branch()).is_err() {
                 // Setup eg. `master` -> `rad/master`
                 radicle::git::set_upstream(
                     &repo,
                     &radicle::rad::REMOTE_NAME,
-                    &doc.default_branch,
-                    &radicle::git::refs::workdir::branch(&doc.default_branch),
+                    doc.default_branch(),
+                    &radicle::git::refs::workdir::branch(doc.default_branch()),
                 )?;
             }
 
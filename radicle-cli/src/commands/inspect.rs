@@ -1,4 +1,5 @@
 #![allow(clippy::or_fun_call)]
+use std::collections::BTreeSet;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
@@ -8,6 +9,7 @@ use anyhow::{anyhow, Context as _};
 use chrono::prelude::*;
 use json_color::{Color, Colorizer};
 
+use radicle::crypto::Unverified;
 use radicle::identity::project::{Doc, Untrusted};
 use radicle::identity::Id;
 use radicle::storage::{ReadRepository, ReadStorage, WriteStorage};
@@ -150,12 +152,20 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         let head = Doc::<Untrusted>::head(signer.public_key(), &repo)?;
         let history = repo.revwalk(head)?.collect::<Vec<_>>();
         let revision = history.len() as usize;
+        let mut revoked = BTreeSet::new();
 
         for (counter, oid) in history.into_iter().rev().enumerate() {
             let oid = oid?.into();
             let tip = repo.commit(oid)?;
             let blob = Doc::blob_at(oid, &repo)?;
             let content: serde_json::Value = serde_json::from_slice(blob.content())?;
+            let doc = Doc::<Unverified>::from_json(blob.content())?;
+            let revocations = doc
+                .revoked
+                .difference(&revoked)
+                .map(|did| format!("revoke {}\n", term::format::negative(did)))
+                .collect::<String>();
+            revoked = doc.revoked;
             let timezone = if tip.time().sign() == '+' {
                 #[allow(deprecated)]
                 FixedOffset::east(tip.time().offset_minutes() * 60)
@@ -172,10 +182,11 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             print!(
                 "{}",
                 term::TextBox::new(format!(
-                    "commit {}\nblob   {}\ndate   {}\n\n{}",
+                    "commit {}\nblob   {}\ndate   {}\n{}\n{}",
                     term::format::yellow(oid),
                     term::format::dim(blob.id()),
                     term::format::dim(time),
+                    revocations,
                     colorizer().colorize_json_str(&serde_json::to_string_pretty(&content)?)?,
                 ))
                 .first(counter == 0)
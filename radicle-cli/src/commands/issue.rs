@@ -4,11 +4,16 @@ use std::str::FromStr;
 
 use anyhow::{anyhow, Context as _};
 
+use crate::commands::rad_label;
 use crate::terminal as term;
 use crate::terminal::args::{Args, Error, Help};
 
+use radicle::cob::cache::{self, Cache};
 use radicle::cob::common::{Reaction, Tag};
 use radicle::cob::issue::{CloseReason, IssueId, Issues, Status};
+use radicle::cob::label::Labels;
+use radicle::crypto::PublicKey;
+use radicle::identity::Did;
 use radicle::storage::WriteStorage;
 
 pub const HELP: Help = Help {
@@ -19,11 +24,21 @@ pub const HELP: Help = Help {
 Usage
 
     rad issue new [--title <title>] [--description <text>]
+    rad issue edit <id> [--title <title>] [--description <text>] [--comment]
+    rad issue assign <id> [--add <key>]... [--remove <key>]...
+    rad issue label <id> [--add <label>]... [--remove <label>]...
     rad issue state <id> [--closed | --open | --solved]
     rad issue delete <id>
     rad issue react <id> [--emoji <char>]
     rad issue list
 
+    When editing an issue without specifying a new title or description,
+    an editor is opened. With `--comment`, a comment is selected and
+    edited instead of the description. Previous versions are kept.
+
+    Issues are assigned to, and unassigned from people by their public
+    key or DID. Labels are given by name or id, see `rad label`.
+
 Options
 
     --help      Print help
@@ -39,6 +54,9 @@ pub struct Metadata {
 #[derive(Debug, PartialEq, Eq)]
 pub enum OperationName {
     Create,
+    Edit,
+    Assign,
+    Label,
     State,
     React,
     Delete,
@@ -57,6 +75,22 @@ pub enum Operation {
         title: Option<String>,
         description: Option<String>,
     },
+    Edit {
+        id: IssueId,
+        title: Option<String>,
+        description: Option<String>,
+        comment: bool,
+    },
+    Assign {
+        id: IssueId,
+        add: Vec<PublicKey>,
+        remove: Vec<PublicKey>,
+    },
+    Label {
+        id: IssueId,
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
     State {
         id: IssueId,
         state: Status,
@@ -87,13 +121,20 @@ impl Args for Options {
         let mut reaction: Option<Reaction> = None;
         let mut description: Option<String> = None;
         let mut state: Option<Status> = None;
+        let mut comment = false;
+        let mut add = Vec::new();
+        let mut remove = Vec::new();
+        let mut add_labels = Vec::new();
+        let mut remove_labels = Vec::new();
 
         while let Some(arg) = parser.next()? {
             match arg {
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
-                Long("title") if op == Some(OperationName::Create) => {
+                Long("title")
+                    if op == Some(OperationName::Create) || op == Some(OperationName::Edit) =>
+                {
                     title = Some(parser.value()?.to_string_lossy().into());
                 }
                 Long("closed") if op == Some(OperationName::State) => {
@@ -115,11 +156,31 @@ impl Args for Options {
                             Some(Reaction::from_str(emoji).map_err(|_| anyhow!("invalid emoji"))?);
                     }
                 }
-                Long("description") if op == Some(OperationName::Create) => {
+                Long("description")
+                    if op == Some(OperationName::Create) || op == Some(OperationName::Edit) =>
+                {
                     description = Some(parser.value()?.to_string_lossy().into());
                 }
+                Long("comment") if op == Some(OperationName::Edit) => {
+                    comment = true;
+                }
+                Long("add") if op == Some(OperationName::Assign) => {
+                    add.push(key(parser.value()?)?);
+                }
+                Long("remove") if op == Some(OperationName::Assign) => {
+                    remove.push(key(parser.value()?)?);
+                }
+                Long("add") if op == Some(OperationName::Label) => {
+                    add_labels.push(parser.value()?.to_string_lossy().into());
+                }
+                Long("remove") if op == Some(OperationName::Label) => {
+                    remove_labels.push(parser.value()?.to_string_lossy().into());
+                }
                 Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
                     "n" | "new" => op = Some(OperationName::Create),
+                    "e" | "edit" => op = Some(OperationName::Edit),
+                    "a" | "assign" => op = Some(OperationName::Assign),
+                    "label" => op = Some(OperationName::Label),
                     "s" | "state" => op = Some(OperationName::State),
                     "d" | "delete" => op = Some(OperationName::Delete),
                     "l" | "list" => op = Some(OperationName::List),
@@ -145,6 +206,27 @@ impl Args for Options {
 
         let op = match op.unwrap_or_default() {
             OperationName::Create => Operation::Create { title, description },
+            OperationName::Edit => {
+                if comment && description.is_some() {
+                    anyhow::bail!("`--comment` and `--description` can't be used together");
+                }
+                Operation::Edit {
+                    id: id.ok_or_else(|| anyhow!("an issue id must be provided"))?,
+                    title,
+                    description,
+                    comment,
+                }
+            }
+            OperationName::Assign => Operation::Assign {
+                id: id.ok_or_else(|| anyhow!("an issue id must be provided"))?,
+                add,
+                remove,
+            },
+            OperationName::Label => Operation::Label {
+                id: id.ok_or_else(|| anyhow!("an issue id must be provided"))?,
+                add: add_labels,
+                remove: remove_labels,
+            },
             OperationName::State => Operation::State {
                 id: id.ok_or_else(|| anyhow!("an issue id must be provided"))?,
                 state: state.ok_or_else(|| anyhow!("a state operation must be provided"))?,
@@ -163,6 +245,16 @@ impl Args for Options {
     }
 }
 
+fn key(val: OsString) -> anyhow::Result<PublicKey> {
+    let val = val.to_string_lossy();
+
+    if let Ok(did) = Did::decode(&val) {
+        Ok(*did)
+    } else {
+        PublicKey::from_str(&val).map_err(|_| anyhow!("invalid key or DID '{}'", val))
+    }
+}
+
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
     let signer = term::signer(&profile)?;
@@ -178,13 +270,69 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         } => {
             issues.create(title, description, &[], &signer)?;
         }
+        Operation::Edit {
+            id,
+            title,
+            description,
+            comment,
+        } => {
+            let mut issue = issues.get_mut(&id)?;
+            let retitled = title.is_some();
+
+            if let Some(title) = title {
+                issue.edit(title, &signer)?;
+            }
+            if comment {
+                let Some(comment_id) =
+                    term::comment_select(&issue, "Which comment do you want to edit?")
+                else {
+                    return Ok(());
+                };
+                let body = issue
+                    .comments()
+                    .find(|(id, _)| **id == comment_id)
+                    .map(|(_, c)| c.body.clone())
+                    .unwrap_or_default();
+
+                if let Some(body) = term::Editor::new().edit(&body)? {
+                    issue.edit_comment(comment_id, body.trim(), &signer)?;
+                }
+            } else if let Some(description) = description {
+                issue.edit_description(description, &signer)?;
+            } else if !retitled {
+                let description = issue.description().unwrap_or_default().to_owned();
+
+                if let Some(description) = term::Editor::new().edit(&description)? {
+                    issue.edit_description(description.trim(), &signer)?;
+                }
+            }
+        }
+        Operation::Assign { id, add, remove } => {
+            let mut issue = issues.get_mut(&id)?;
+            issue.assign(add, remove, &signer)?;
+        }
+        Operation::Label { id, add, remove } => {
+            let labels = Labels::open(*signer.public_key(), &repo)?;
+            let add = add
+                .iter()
+                .map(|l| rad_label::resolve(&labels, l))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let remove = remove
+                .iter()
+                .map(|l| rad_label::resolve(&labels, l))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let mut issue = issues.get_mut(&id)?;
+            issue.label(add, remove, &signer)?;
+        }
         Operation::State { id, state } => {
             let mut issue = issues.get_mut(&id)?;
             issue.lifecycle(state, &signer)?;
         }
         Operation::React { id, reaction } => {
             if let Ok(mut issue) = issues.get_mut(&id) {
-                let comment_id = term::comment_select(&issue).unwrap();
+                let comment_id =
+                    term::comment_select(&issue, "Which comment do you want to react to?").unwrap();
                 issue.react(comment_id, reaction, &signer)?;
             }
         }
@@ -233,9 +381,18 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             }
         }
         Operation::List => {
-            for result in issues.all()? {
-                let (id, issue, _) = result?;
-                println!("{} {}", id, issue.title());
+            let labels = Labels::open(*signer.public_key(), &repo)?;
+
+            let mut cache = Cache::open(&repo)?;
+            cache.update(&repo)?;
+
+            for entry in cache.issues(&cache::Query::default())? {
+                println!(
+                    "{} {} {}",
+                    entry.id,
+                    entry.title,
+                    rad_label::format(&labels, &entry.labels)
+                );
             }
         }
         Operation::Delete { id } => {
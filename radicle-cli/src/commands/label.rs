@@ -0,0 +1,223 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use radicle::cob::common::Color;
+use radicle::cob::label::{self, LabelId, Labels};
+use radicle::storage::WriteStorage;
+
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+pub const HELP: Help = Help {
+    name: "label",
+    description: "Manage labels",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad label new <name> [--description <text>] [--color <#rrggbb>]
+    rad label edit <label> [--name <name>] [--description <text>] [--color <#rrggbb>]
+    rad label delete <label>
+    rad label list
+
+    Labels are shared by the issues and patches of a project. They can be
+    referred to by name or by id.
+
+Options
+
+    --help      Print help
+"#,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationName {
+    Create,
+    Edit,
+    Delete,
+    List,
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    Create {
+        name: String,
+        description: Option<String>,
+        color: Option<Color>,
+    },
+    Edit {
+        label: String,
+        name: Option<String>,
+        description: Option<String>,
+        color: Option<Color>,
+    },
+    Delete {
+        label: String,
+    },
+    List,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<OperationName> = None;
+        let mut label: Option<String> = None;
+        let mut name: Option<String> = None;
+        let mut description: Option<String> = None;
+        let mut color: Option<Color> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("name") if op == Some(OperationName::Edit) => {
+                    name = Some(parser.value()?.to_string_lossy().into());
+                }
+                Long("description")
+                    if op == Some(OperationName::Create) || op == Some(OperationName::Edit) =>
+                {
+                    description = Some(parser.value()?.to_string_lossy().into());
+                }
+                Long("color")
+                    if op == Some(OperationName::Create) || op == Some(OperationName::Edit) =>
+                {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+
+                    color = Some(
+                        Color::from_str(&val).map_err(|_| anyhow!("invalid color '{}'", val))?,
+                    );
+                }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "n" | "new" => op = Some(OperationName::Create),
+                    "e" | "edit" => op = Some(OperationName::Edit),
+                    "d" | "delete" => op = Some(OperationName::Delete),
+                    "l" | "list" => op = Some(OperationName::List),
+
+                    unknown => anyhow::bail!("unknown operation '{}'", unknown),
+                },
+                Value(val) if label.is_none() && op != Some(OperationName::List) => {
+                    label = Some(val.to_string_lossy().into());
+                }
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+
+        let op = match op.unwrap_or(OperationName::List) {
+            OperationName::Create => Operation::Create {
+                name: label.ok_or_else(|| anyhow!("a label name must be provided"))?,
+                description,
+                color,
+            },
+            OperationName::Edit => Operation::Edit {
+                label: label.ok_or_else(|| anyhow!("a label must be provided"))?,
+                name,
+                description,
+                color,
+            },
+            OperationName::Delete => Operation::Delete {
+                label: label.ok_or_else(|| anyhow!("a label to delete must be provided"))?,
+            },
+            OperationName::List => Operation::List,
+        };
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = &profile.storage;
+    let (_, id) = radicle::rad::cwd()?;
+    let repo = storage.repository(id)?;
+    let mut labels = Labels::open(*signer.public_key(), &repo)?;
+
+    match options.op {
+        Operation::Create {
+            name,
+            description,
+            color,
+        } => {
+            if labels.find(&name)?.is_some() {
+                anyhow::bail!("label '{}' already exists", name);
+            }
+            let label = labels.create(
+                name,
+                description.unwrap_or_default(),
+                color.unwrap_or(*label::DEFAULT_COLOR),
+                &signer,
+            )?;
+            term::success!(
+                "Label {} created with id {}",
+                term::format::label(&label),
+                term::format::tertiary(label.id)
+            );
+        }
+        Operation::Edit {
+            label,
+            name,
+            description,
+            color,
+        } => {
+            let id = resolve(&labels, &label)?;
+            let mut label = labels.get_mut(&id)?;
+            let name = name.unwrap_or_else(|| label.name().to_owned());
+            let description = description.unwrap_or_else(|| label.description().to_owned());
+            let color = color.unwrap_or(*label.color());
+
+            label.edit(name, description, color, &signer)?;
+            term::success!("Label {} updated", term::format::label(&label));
+        }
+        Operation::Delete { label } => {
+            let id = resolve(&labels, &label)?;
+            labels.remove(&id)?;
+        }
+        Operation::List => {
+            for result in labels.all()? {
+                let (id, label, _) = result?;
+                println!(
+                    "{} {} {}",
+                    id,
+                    term::format::label(&label),
+                    term::format::dim(label.description())
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve a label given by name or id.
+pub fn resolve(labels: &Labels, label: &str) -> anyhow::Result<LabelId> {
+    if let Ok(id) = LabelId::from_str(label) {
+        if labels.get(&id)?.is_some() {
+            return Ok(id);
+        }
+    }
+    labels
+        .find(label)?
+        .map(|(id, _)| id)
+        .ok_or_else(|| anyhow!("label '{}' was not found", label))
+}
+
+/// Format the given labels, in their own colors. Labels that can't be found are left out.
+pub fn format<'a>(labels: &Labels, ids: impl IntoIterator<Item = &'a LabelId>) -> String {
+    ids.into_iter()
+        .filter_map(|id| labels.get(id).ok().flatten())
+        .map(|label| term::format::label(&label))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
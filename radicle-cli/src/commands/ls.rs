@@ -56,7 +56,7 @@ pub fn run(_options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             term::format::bold(payload.name),
             term::format::tertiary(id),
             term::format::secondary(head),
-            term::format::italic(payload.description),
+            term::format::italic(payload.description()),
         ]);
     });
     table.render();
@@ -7,8 +7,10 @@ use anyhow::{anyhow, Context};
 
 use crate::terminal as term;
 use crate::terminal::args::{Args, Error, Help};
+use radicle::cob::issue::Issues;
 use radicle::cob::patch::RevisionIx;
-use radicle::cob::patch::{Patch, PatchId, Patches};
+use radicle::cob::patch::{MergeTarget, Patch, PatchId, Patches};
+use radicle::cob::xref;
 use radicle::git;
 use radicle::prelude::*;
 use radicle::rad;
@@ -24,6 +26,9 @@ Usage
 
     To specify a patch to merge, use the fully qualified patch id.
 
+    Open issues that the merged revision closes, with eg. `Fixes <issue-id>`
+    in the patch description or commit messages, are marked as solved.
+
 Options
 
     -i, --interactive         Ask for confirmations
@@ -136,7 +141,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
     let signer = term::signer(&profile)?;
     let repository = profile.storage.repository(id)?;
-    let _project = repository
+    let project = repository
         .project_of(profile.id())
         .context(format!("couldn't load project {} from local state", id))?;
     let repository = profile.storage.repository(id)?;
@@ -161,6 +166,19 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let head_oid = head
         .target()
         .ok_or_else(|| anyhow!("cannot merge into detatched head; aborting"))?;
+
+    // Patches are merged into the branch they target.
+    let target = match patch.target() {
+        MergeTarget::Delegates => project.default_branch().clone(),
+        MergeTarget::Branch(branch) => branch.clone(),
+    };
+    if branch != target.as_str() {
+        return Err(Error::WithHint {
+            err: anyhow!("patch targets {}, but {} is checked out", target, branch),
+            hint: "Checkout the patch's target branch to merge it.",
+        }
+        .into());
+    }
     let revision_ix = options.revision.unwrap_or_else(|| patch.version());
     let (revision_id, revision) = patch
         .revisions()
@@ -270,6 +288,15 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     // TODO: Don't allow merging the same revision twice?
     patch.merge(*revision_id, head_oid.into(), &signer)?;
 
+    // Solve the issues closed by the patch, eg. with `Fixes <issue>`.
+    let mut issues = Issues::open(*profile.id(), &repository)?;
+    for issue in xref::solve(&repository, &mut issues, &patch, &signer)? {
+        term::success!(
+            "Issue {} marked as solved",
+            term::format::tertiary(term::format::cob(&issue))
+        );
+    }
+
     term::success!(
         "Patch state updated, use {} to publish",
         term::format::secondary("`rad push`")
@@ -0,0 +1,87 @@
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use radicle::node::{Alias, Features, Handle as _, NodeUpdate};
+
+use crate::terminal as term;
+use crate::terminal::args::{Args, Error, Help};
+
+pub const HELP: Help = Help {
+    name: "node",
+    description: "Update the information announced by your node",
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad node [<option>...]
+
+    Updates are announced to the network, and kept for the next runs of the node.
+
+Options
+
+    --alias <alias>              Set the node alias
+    --seed                       Advertize the node as a seed
+    --no-seed                    Stop advertizing the node as a seed
+    --external-address <addr>    Set the node's external address, eg. `203.0.113.1:8776`.
+                                 May be specified multiple times
+    --help                       Print help
+"#,
+};
+
+#[derive(Debug)]
+pub struct Options {
+    pub update: NodeUpdate,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut update = NodeUpdate::default();
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("alias") => {
+                    let value: String = parser.value()?.to_string_lossy().into();
+                    update.alias = Some(Alias::try_from(value)?);
+                }
+                Long("seed") => {
+                    update.features = Some(Features::SEED);
+                }
+                Long("no-seed") => {
+                    update.features = Some(Features::NONE);
+                }
+                Long("external-address") => {
+                    let value: String = parser.value()?.to_string_lossy().into();
+                    update
+                        .external_addresses
+                        .get_or_insert_with(Vec::new)
+                        .push(value);
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+        if update == NodeUpdate::default() {
+            return Err(anyhow!("nothing to update; see `rad node --help`"));
+        }
+
+        Ok((Options { update }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let node = radicle::node::connect(profile.node())?;
+
+    if node.update_node(&options.update)? {
+        term::success!("Node announcement updated");
+    } else {
+        term::info!("Node announcement is already up to date");
+    }
+    Ok(())
+}
@@ -2,15 +2,20 @@
 mod common;
 #[path = "patch/create.rs"]
 mod create;
+#[path = "patch/diff.rs"]
+mod diff;
+#[path = "patch/lifecycle.rs"]
+mod lifecycle;
 #[path = "patch/list.rs"]
 mod list;
 
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use std::str::FromStr;
 
 use anyhow::anyhow;
 
-use radicle::cob::patch::PatchId;
+use radicle::cob::patch::{PatchId, RevisionIx};
+use radicle::git::RefString;
 use radicle::prelude::*;
 
 use crate::terminal as term;
@@ -25,18 +30,41 @@ pub const HELP: Help = Help {
 Usage
 
     rad patch [<option>...]
+    rad patch ready <id>
+    rad patch archive <id>
+    rad patch reopen <id>
+    rad patch diff <id> [--from <n>] [--to <n>]
 
 Create options
 
     -u, --update [<id>]        Update an existing patch (default: no)
+        --draft                Create the patch as a draft (default: false)
+        --target <branch>      Branch the patch is intended for (default: the default branch)
         --[no-]sync            Sync patch to seed (default: sync)
         --[no-]push            Push patch head to storage (default: true)
     -m, --message [<string>]   Provide a comment message to the patch or revision (default: prompt)
         --no-message           Leave the patch or revision comment message blank
 
+Diff options
+
+        --from <n>             Revision to compare from (default: the previous revision)
+        --to <n>               Revision to compare to (default: the latest revision)
+
+List options
+
+        --drafts               Only list draft patches
+        --archived             Only list archived patches
+        --all                  List patches of any status
+
+Commands
+
+    ready <id>                 Mark a draft patch as ready for review
+    archive <id>               Archive a proposed or draft patch
+    reopen <id>                Reopen an archived patch
+
 Options
 
-    -l, --list                 List all patches (default: false)
+    -l, --list                 List proposed patches (default: false)
         --help                 Print help
 "#,
 };
@@ -54,9 +82,32 @@ impl Default for Update {
     }
 }
 
+/// A patch status change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lifecycle {
+    Ready,
+    Archive,
+    Reopen,
+}
+
+/// Revisions of a patch to compare.
+#[derive(Debug)]
+pub struct Diff {
+    pub id: PatchId,
+    pub from: Option<RevisionIx>,
+    pub to: Option<RevisionIx>,
+}
+
 #[derive(Default, Debug)]
 pub struct Options {
     pub list: bool,
+    pub lifecycle: Option<(PatchId, Lifecycle)>,
+    pub diff: Option<Diff>,
+    pub draft: bool,
+    pub drafts: bool,
+    pub target: Option<RefString>,
+    pub archived: bool,
+    pub all: bool,
     pub verbose: bool,
     pub sync: bool,
     pub push: bool,
@@ -70,6 +121,17 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut list = false;
+        let mut lifecycle = None;
+        let mut lifecycle_id = None;
+        let mut diff = false;
+        let mut diff_id = None;
+        let mut from = None;
+        let mut to = None;
+        let mut draft = false;
+        let mut drafts = false;
+        let mut target = None;
+        let mut archived = false;
+        let mut all = false;
         let mut verbose = false;
         let mut sync = true;
         let mut message = Comment::default();
@@ -84,13 +146,7 @@ impl Args for Options {
                 }
                 Long("update") | Short('u') => {
                     if let Ok(val) = parser.value() {
-                        let val = val
-                            .to_str()
-                            .ok_or_else(|| anyhow!("patch id specified is not UTF-8"))?;
-                        let id = PatchId::from_str(val)
-                            .map_err(|_| anyhow!("invalid patch id '{}'", val))?;
-
-                        update = Update::Patch(id);
+                        update = Update::Patch(patch_id(&val)?);
                     } else {
                         update = Update::Any;
                     }
@@ -121,6 +177,52 @@ impl Args for Options {
                 Long("no-push") => {
                     push = false;
                 }
+                Long("from") if diff => {
+                    from = Some(parser.value()?.parse()?);
+                }
+                Long("to") if diff => {
+                    to = Some(parser.value()?.parse()?);
+                }
+                Value(val) if !diff && lifecycle.is_none() && val == "diff" => {
+                    diff = true;
+                }
+                Value(val) if !diff && lifecycle.is_none() && val == "ready" => {
+                    lifecycle = Some(Lifecycle::Ready);
+                }
+                Value(val) if !diff && lifecycle.is_none() && val == "archive" => {
+                    lifecycle = Some(Lifecycle::Archive);
+                }
+                Value(val) if !diff && lifecycle.is_none() && val == "reopen" => {
+                    lifecycle = Some(Lifecycle::Reopen);
+                }
+                Value(val) if diff && diff_id.is_none() => {
+                    diff_id = Some(patch_id(&val)?);
+                }
+                Value(val) if lifecycle.is_some() && lifecycle_id.is_none() => {
+                    lifecycle_id = Some(patch_id(&val)?);
+                }
+                Long("target") => {
+                    let val = parser.value()?;
+                    let val = val
+                        .to_str()
+                        .ok_or_else(|| anyhow!("target branch specified is not UTF-8"))?;
+                    let branch = RefString::try_from(val)
+                        .map_err(|_| anyhow!("invalid target branch '{}'", val))?;
+
+                    target = Some(branch);
+                }
+                Long("draft") => {
+                    draft = true;
+                }
+                Long("drafts") => {
+                    drafts = true;
+                }
+                Long("archived") => {
+                    archived = true;
+                }
+                Long("all") => {
+                    all = true;
+                }
 
                 // Common.
                 Long("verbose") | Short('v') => {
@@ -133,9 +235,29 @@ impl Args for Options {
             }
         }
 
+        let diff = if diff {
+            let id = diff_id.ok_or_else(|| anyhow!("a patch id must be specified"))?;
+            Some(Diff { id, from, to })
+        } else {
+            None
+        };
+        let lifecycle = if let Some(lifecycle) = lifecycle {
+            let id = lifecycle_id.ok_or_else(|| anyhow!("a patch id must be specified"))?;
+            Some((id, lifecycle))
+        } else {
+            None
+        };
+
         Ok((
             Options {
                 list,
+                lifecycle,
+                diff,
+                draft,
+                drafts,
+                target,
+                archived,
+                all,
                 sync,
                 message,
                 push,
@@ -154,10 +276,22 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
     let storage = profile.storage.repository(id)?;
 
-    if options.list {
+    if let Some(diff) = options.diff {
+        diff::run(&storage, &profile, diff)?;
+    } else if let Some((id, lifecycle)) = options.lifecycle {
+        lifecycle::run(&storage, &profile, &id, lifecycle)?;
+    } else if options.list {
         list::run(&storage, &profile, Some(workdir), options)?;
     } else {
         create::run(&storage, &profile, &workdir, options)?;
     }
     Ok(())
 }
+
+fn patch_id(val: &OsStr) -> anyhow::Result<PatchId> {
+    let val = val
+        .to_str()
+        .ok_or_else(|| anyhow!("patch id specified is not UTF-8"))?;
+
+    PatchId::from_str(val).map_err(|_| anyhow!("invalid patch id '{}'", val))
+}
@@ -41,7 +41,10 @@ pub fn find_merge_targets(
 }
 
 /// Return the [`Oid`] of the merge target.
-pub fn patch_merge_target_oid(target: MergeTarget, repository: &Repository) -> anyhow::Result<Oid> {
+pub fn patch_merge_target_oid(
+    target: &MergeTarget,
+    repository: &Repository,
+) -> anyhow::Result<Oid> {
     match target {
         MergeTarget::Delegates => {
             if let Ok((_, target)) = repository.head() {
@@ -53,6 +56,17 @@ pub fn patch_merge_target_oid(target: MergeTarget, repository: &Repository) -> a
                 );
             }
         }
+        MergeTarget::Branch(branch) => {
+            if let Ok(target) = target.head(repository) {
+                Ok(*target)
+            } else {
+                anyhow::bail!(
+                    "failed to determine head of branch {} for project {}",
+                    branch,
+                    repository.id,
+                );
+            }
+        }
     }
 }
 
@@ -114,11 +128,15 @@ pub fn find_unmerged_with_base(
     patches: &Patches,
     workdir: &git::raw::Repository,
 ) -> anyhow::Result<Vec<(PatchId, Patch, Clock)>> {
-    // My patches.
-    let proposed: Vec<_> = patches.proposed_by(patches.public_key())?.collect();
+    // My patches, including drafts.
+    let mine: Vec<_> = patches
+        .proposed()?
+        .chain(patches.drafts()?)
+        .filter(|(_, p, _)| p.author().id() == patches.public_key())
+        .collect();
     let mut matches = Vec::new();
 
-    for (id, patch, clock) in proposed {
+    for (id, patch, clock) in mine {
         let (_, rev) = patch.latest().unwrap();
 
         if !rev.merges.is_empty() {
@@ -91,8 +91,11 @@ pub fn run(
     // Determine the merge target for this patch. This can ben any tracked remote's "default"
     // branch, as well as your own (eg. `rad/master`).
     let mut spinner = term::spinner("Analyzing remotes...");
-    let targets =
-        common::find_merge_targets(&head_oid, project.default_branch.as_refstr(), storage)?;
+    let target_branch = options
+        .target
+        .clone()
+        .unwrap_or_else(|| project.default_branch().clone());
+    let targets = common::find_merge_targets(&head_oid, target_branch.as_refstr(), storage)?;
 
     // eg. `refs/namespaces/<peer>/refs/heads/master`
     let (target_peer, target_oid) = match targets.not_merged.as_slice() {
@@ -177,7 +180,7 @@ pub fn run(
     term::info!(
         "{}/{} ({}) <- {}/{} ({})",
         term::format::dim(target_peer.id),
-        term::format::highlight(&project.default_branch.to_string()),
+        term::format::highlight(&target_branch.to_string()),
         term::format::secondary(&term::format::oid(*target_oid)),
         term::format::dim(term::format::node(patches.public_key())),
         term::format::highlight(&head_branch.to_string()),
@@ -233,18 +236,41 @@ pub fn run(
         anyhow::bail!("patch proposal aborted by user");
     }
 
-    let patch = patches.create(
-        title,
-        &description,
-        MergeTarget::default(),
-        base_oid,
-        head_oid,
-        &[],
-        &signer,
-    )?;
+    let target = match options.target {
+        Some(branch) => MergeTarget::Branch(branch),
+        None => MergeTarget::default(),
+    };
+    let patch = if options.draft {
+        patches.draft(
+            title,
+            &description,
+            target,
+            base_oid,
+            head_oid,
+            &[],
+            &signer,
+        )?
+    } else {
+        patches.create(
+            title,
+            &description,
+            target,
+            base_oid,
+            head_oid,
+            &[],
+            &signer,
+        )?
+    };
 
     term::blank();
-    term::success!("Patch {} created 🌱", term::format::highlight(patch.id));
+    if options.draft {
+        term::success!(
+            "Draft patch {} created 🌱",
+            term::format::highlight(patch.id)
+        );
+    } else {
+        term::success!("Patch {} created 🌱", term::format::highlight(patch.id));
+    }
 
     if options.sync {
         // TODO
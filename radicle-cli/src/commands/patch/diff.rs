@@ -0,0 +1,98 @@
+use anyhow::anyhow;
+
+use radicle::cob::patch::{Patches, RangeDiffEntry};
+use radicle::git;
+use radicle::prelude::*;
+use radicle::profile::Profile;
+use radicle::storage::git::Repository;
+
+use crate::terminal as term;
+
+use super::Diff;
+
+/// Show the range-diff and interdiff between two revisions of a patch.
+pub fn run(storage: &Repository, profile: &Profile, diff: Diff) -> anyhow::Result<()> {
+    let patches = Patches::open(*profile.id(), storage)?;
+    let patch = patches
+        .get(&diff.id)?
+        .ok_or_else(|| anyhow!("patch `{}` not found", diff.id))?;
+    let to = diff.to.unwrap_or_else(|| patch.version());
+    let from = match diff.from {
+        Some(from) => from,
+        None => to
+            .checked_sub(1)
+            .ok_or_else(|| anyhow!("patch has a single revision; nothing to compare"))?,
+    };
+    let repo = storage.raw();
+    let range_diff = patch
+        .range_diff(from, to, repo)?
+        .ok_or_else(|| anyhow!("revisions R{} and R{} must both exist", from, to))?;
+
+    term::blank();
+    term::info!(
+        "{} {}",
+        term::format::bold(patch.title()),
+        term::format::dim(format!("R{from}..R{to}"))
+    );
+    term::blank();
+
+    for entry in &range_diff {
+        match entry {
+            RangeDiffEntry::Unchanged { old, new } => {
+                print_commit("=", Some(old), Some(new), repo)?;
+            }
+            RangeDiffEntry::Modified { old, new, changes } => {
+                print_commit(&term::format::yellow("!"), Some(old), Some(new), repo)?;
+                term::blob(changes);
+            }
+            RangeDiffEntry::Removed { old } => {
+                print_commit(&term::format::negative("<"), Some(old), None, repo)?;
+            }
+            RangeDiffEntry::Added { new } => {
+                print_commit(&term::format::positive(">"), None, Some(new), repo)?;
+            }
+        }
+    }
+
+    let interdiff = patch
+        .interdiff(from, to, repo)?
+        .ok_or_else(|| anyhow!("revisions R{} and R{} must both exist", from, to))?;
+
+    term::blank();
+    interdiff.print(git::raw::DiffFormat::Patch, |_, _, line| {
+        let content = String::from_utf8_lossy(line.content());
+        let content = content.trim_end_matches('\n');
+
+        match line.origin() {
+            '+' => term::print(term::format::positive(format!("+{content}"))),
+            '-' => term::print(term::format::negative(format!("-{content}"))),
+            ' ' => term::print(format!(" {content}")),
+            _ => term::print(term::format::dim(content)),
+        }
+        true
+    })?;
+
+    Ok(())
+}
+
+fn print_commit(
+    symbol: &str,
+    old: Option<&git::Oid>,
+    new: Option<&git::Oid>,
+    repo: &git::raw::Repository,
+) -> anyhow::Result<()> {
+    let oid = |oid: Option<&git::Oid>| {
+        oid.map(|o| term::format::secondary(term::format::oid(*o)))
+            .unwrap_or_else(|| "-------".to_owned())
+    };
+    let commit = repo.find_commit(**new.or(old).ok_or_else(|| anyhow!("no commit"))?)?;
+
+    term::info!(
+        "{} {} {} {}",
+        oid(old),
+        symbol,
+        oid(new),
+        commit.summary().unwrap_or_default()
+    );
+    Ok(())
+}
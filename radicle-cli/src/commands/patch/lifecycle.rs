@@ -0,0 +1,44 @@
+use radicle::cob::patch::{PatchId, Patches};
+use radicle::profile::Profile;
+use radicle::storage::git::Repository;
+
+use crate::terminal as term;
+
+use super::Lifecycle;
+
+/// Change the status of a patch.
+pub fn run(
+    storage: &Repository,
+    profile: &Profile,
+    patch_id: &PatchId,
+    lifecycle: Lifecycle,
+) -> anyhow::Result<()> {
+    let signer = term::signer(profile)?;
+    let mut patches = Patches::open(*profile.id(), storage)?;
+    let mut patch = patches.get_mut(patch_id)?;
+
+    match lifecycle {
+        Lifecycle::Ready => {
+            patch.ready(&signer)?;
+            term::success!(
+                "Patch {} is ready for review",
+                term::format::highlight(term::format::cob(patch_id))
+            );
+        }
+        Lifecycle::Archive => {
+            patch.archive(&signer)?;
+            term::success!(
+                "Patch {} archived",
+                term::format::highlight(term::format::cob(patch_id))
+            );
+        }
+        Lifecycle::Reopen => {
+            patch.reopen(&signer)?;
+            term::success!(
+                "Patch {} reopened",
+                term::format::highlight(term::format::cob(patch_id))
+            );
+        }
+    }
+    Ok(())
+}
@@ -1,11 +1,13 @@
 use anyhow::anyhow;
 
-use radicle::cob::patch::{Patch, PatchId, Patches, Verdict};
+use radicle::cob::label::Labels;
+use radicle::cob::patch::{Patch, PatchId, Patches, Status, Verdict};
 use radicle::git;
 use radicle::prelude::*;
 use radicle::profile::Profile;
 use radicle::storage::git::Repository;
 
+use crate::commands::rad_label;
 use crate::terminal as term;
 
 use super::common;
@@ -24,14 +26,22 @@ pub fn run(
 
     let me = *profile.id();
     let patches = Patches::open(*profile.id(), storage)?;
-    let proposed = patches.proposed()?;
+    let listed: Vec<_> = if options.all {
+        patches.all()?.filter_map(|result| result.ok()).collect()
+    } else if options.drafts {
+        patches.drafts()?.collect()
+    } else if options.archived {
+        patches.archived()?.collect()
+    } else {
+        patches.proposed()?.collect()
+    };
 
     // Patches the user authored.
     let mut own = Vec::new();
     // Patches other users authored.
     let mut other = Vec::new();
 
-    for (id, patch, _) in proposed {
+    for (id, patch, _) in listed {
         if *patch.author().id() == me {
             own.push((id, patch));
         } else {
@@ -97,8 +107,13 @@ fn print(
     let (_, revision) = patch
         .latest()
         .ok_or_else(|| anyhow!("patch is malformed: no revisions found"))?;
+    let status = match patch.status() {
+        Status::Proposed => String::new(),
+        status => term::format::dim(format!("[{status}] ")),
+    };
     term::info!(
-        "{} {} {} {} {}",
+        "{}{} {} {} {} {}",
+        status,
         term::format::bold(patch.title()),
         term::format::highlight(term::format::cob(patch_id)),
         term::format::dim(format!("R{}", patch.version())),
@@ -107,6 +122,15 @@ fn print(
     );
     term::info!("{}", author_info.join(" "));
 
+    if !patch.labels.is_empty() {
+        let labels = Labels::open(*whoami, storage)?;
+        term::info!(
+            "{}{}",
+            " ".repeat(prefix.chars().count()),
+            rad_label::format(&labels, patch.labels.iter())
+        );
+    }
+
     let mut timeline = Vec::new();
     for merge in revision.merges.iter() {
         let peer = storage.remote(&merge.node)?;
@@ -5,6 +5,7 @@ use std::str::FromStr;
 use anyhow::anyhow;
 
 use radicle::identity::project::Id;
+use radicle::storage::git::alternates;
 use radicle::storage::ReadStorage;
 
 use crate::commands::rad_untrack;
@@ -22,6 +23,9 @@ Usage
 
     Removes a project from storage.
 
+    Working copies that borrow objects from the project's storage are
+    dissociated from it first, by copying the objects they need.
+
 Options
 
     --no-confirm        Do not ask for confirmation before removal
@@ -82,6 +86,15 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     if let Ok(Some(_)) = storage.get(signer.public_key(), id.to_owned()) {
         let namespace = profile.paths().storage().join(&id.to_human());
+        let borrowers = alternates::borrowers(&namespace)?;
+
+        if !borrowers.is_empty() {
+            term::warning("The following working copies borrow objects from this project:");
+            for borrower in &borrowers {
+                term::indented(&borrower.display().to_string());
+            }
+            term::info!("They will be dissociated from storage before the project is removed.");
+        }
 
         if !options.confirm
             || term::confirm(format!(
@@ -89,6 +102,9 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                 term::format::dim(id.to_human())
             ))
         {
+            for borrower in &borrowers {
+                alternates::dissociate(borrower)?;
+            }
             rad_untrack::untrack(id.to_owned(), &profile)?;
             fs::remove_dir_all(namespace)?;
             term::success!("Successfully removed project {}", &id);
@@ -110,6 +110,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "bundle" => {
+            term::run_command_args::<rad_bundle::Options, _>(
+                rad_bundle::HELP,
+                "Bundle",
+                rad_bundle::run,
+                args.to_vec(),
+            );
+        }
         "checkout" => {
             term::run_command_args::<rad_checkout::Options, _>(
                 rad_checkout::HELP,
@@ -134,6 +142,22 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "fork" => {
+            term::run_command_args::<rad_fork::Options, _>(
+                rad_fork::HELP,
+                "Fork",
+                rad_fork::run,
+                args.to_vec(),
+            );
+        }
+        "gc" => {
+            term::run_command_args::<rad_gc::Options, _>(
+                rad_gc::HELP,
+                "Garbage collection",
+                rad_gc::run,
+                args.to_vec(),
+            );
+        }
         "help" => {
             term::run_command_args::<rad_help::Options, _>(
                 rad_help::HELP,
@@ -166,6 +190,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "label" => {
+            term::run_command_args::<rad_label::Options, _>(
+                rad_label::HELP,
+                "Label",
+                rad_label::run,
+                args.to_vec(),
+            );
+        }
         "ls" => {
             term::run_command_args::<rad_ls::Options, _>(
                 rad_ls::HELP,
@@ -182,6 +214,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "node" => {
+            term::run_command_args::<rad_node::Options, _>(
+                rad_node::HELP,
+                "Node",
+                rad_node::run,
+                args.to_vec(),
+            );
+        }
         "patch" => {
             term::run_command_args::<rad_patch::Options, _>(
                 rad_patch::HELP,
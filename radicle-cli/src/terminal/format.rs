@@ -2,6 +2,7 @@ use std::{fmt, time};
 
 pub use dialoguer::console::style;
 
+use radicle::cob::label::Label;
 use radicle::cob::{ObjectId, Timestamp};
 use radicle::node::NodeId;
 use radicle::profile::Profile;
@@ -27,6 +28,18 @@ pub fn cob(id: &ObjectId) -> String {
     format!("{:.11}", id.to_string())
 }
 
+/// Format a label, in its own color.
+pub fn label(label: &Label) -> String {
+    let (r, g, b) = label.color().rgb();
+    // Nb. The color is approximated with the closest color of the 256-color palette.
+    let [r, g, b] = [r, g, b].map(|c| (c as u16 * 5 + 127) / 255);
+
+    style(format!(" {} ", label.name()))
+        .color256((16 + 36 * r + 6 * g + b) as u8)
+        .reverse()
+        .to_string()
+}
+
 /// Format a timestamp.
 pub fn timestamp(time: &Timestamp) -> String {
     let fmt = timeago::Formatter::new();
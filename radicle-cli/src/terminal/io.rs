@@ -375,10 +375,9 @@ where
     result.map(|i| &options[i])
 }
 
-pub fn comment_select(issue: &Issue) -> Option<CommentId> {
+pub fn comment_select(issue: &Issue, prompt: &str) -> Option<CommentId> {
     let selection = dialoguer::Select::with_theme(&theme())
-        .with_prompt("Which comment do you want to react to?")
-        .item(issue.description().unwrap_or_default())
+        .with_prompt(prompt)
         .items(
             &issue
                 .comments()
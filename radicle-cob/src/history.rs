@@ -87,6 +87,15 @@ impl History {
             .unwrap_or_default()
     }
 
+    /// Get the root entry of the history, ie. the change that created the object.
+    pub fn root(&self) -> &EntryWithClock {
+        self.graph
+            .externals(petgraph::Direction::Incoming)
+            .next()
+            .map(|n| &self.graph[n])
+            .expect("History::root: the root entry is always present")
+    }
+
     /// Get the current history timestamp.
     /// This is the latest timestamp of any tip.
     pub fn timestamp(&self) -> Timestamp {
@@ -72,7 +72,7 @@ impl Serialize for ObjectId {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_bytes(self.0.as_bytes())
+        serializer.collect_str(&self.0)
     }
 }
 
@@ -81,8 +81,8 @@ impl<'de> Deserialize<'de> for ObjectId {
     where
         D: serde::Deserializer<'de>,
     {
-        let raw = <&[u8]>::deserialize(deserializer)?;
-        let oid = Oid::try_from(raw).map_err(serde::de::Error::custom)?;
+        let raw = String::deserialize(deserializer)?;
+        let oid = Oid::from_str(&raw).map_err(serde::de::Error::custom)?;
         Ok(ObjectId(oid))
     }
 }
@@ -95,3 +95,20 @@ impl From<&ObjectId> for Component<'_> {
             .expect("collaborative object id's are valid refname components")
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr as _;
+
+    use super::ObjectId;
+
+    #[test]
+    fn serde_roundtrip() {
+        let id = ObjectId::from_str("cad2d5c8ae7a2a5db3cd3e4ec4bdd78d68d6d4c1").unwrap();
+        let json = serde_json::to_string(&id).unwrap();
+
+        assert_eq!(json, "\"cad2d5c8ae7a2a5db3cd3e4ec4bdd78d68d6d4c1\"");
+        assert_eq!(serde_json::from_str::<ObjectId>(&json).unwrap(), id);
+        assert!(serde_json::from_str::<ObjectId>("\"not-an-object-id\"").is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use std::sync::Arc;
 use std::{fmt, ops::Deref, str::FromStr};
 
 use ed25519_compact as ed25519;
@@ -69,6 +70,23 @@ where
     }
 }
 
+impl<T> Signer for Arc<T>
+where
+    T: Signer + ?Sized,
+{
+    fn public_key(&self) -> &PublicKey {
+        self.deref().public_key()
+    }
+
+    fn sign(&self, msg: &[u8]) -> Signature {
+        self.deref().sign(msg)
+    }
+
+    fn try_sign(&self, msg: &[u8]) -> Result<Signature, SignerError> {
+        self.deref().try_sign(msg)
+    }
+}
+
 /// Cryptographic signature.
 #[derive(PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
 #[serde(into = "String", try_from = "String")]
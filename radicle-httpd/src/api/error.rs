@@ -50,6 +50,10 @@ pub enum Error {
     #[error(transparent)]
     CobStore(#[from] radicle::cob::store::Error),
 
+    /// Cob cache error.
+    #[error(transparent)]
+    CobCache(#[from] radicle::cob::cache::Error),
+
     /// Git project error.
     #[error(transparent)]
     GitProject(#[from] radicle::storage::git::ProjectError),
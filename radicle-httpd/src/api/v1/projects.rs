@@ -1,4 +1,5 @@
 use std::collections::BTreeSet;
+use std::str::FromStr;
 
 use axum::handler::Handler;
 use axum::http::{header, HeaderValue};
@@ -10,8 +11,12 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tower_http::set_header::SetResponseHeaderLayer;
 
+use radicle::cob::cache;
 use radicle::cob::issue::Issues;
+use radicle::cob::label::LabelId;
+use radicle::cob::patch::{Patches, RevisionIx};
 use radicle::git::raw::BranchType;
+use radicle::git::raw::DiffFormat;
 use radicle::identity::{Doc, Id};
 use radicle::node::NodeId;
 use radicle::storage::{Oid, ReadRepository, WriteRepository, WriteStorage};
@@ -47,6 +52,11 @@ pub fn router(ctx: Context) -> Router {
         .route("/projects/:project/readme/:sha", get(readme_handler))
         .route("/projects/:project/issues", get(issues_handler))
         .route("/projects/:project/issues/:id", get(issue_handler))
+        .route("/projects/:project/patches", get(patches_handler))
+        .route(
+            "/projects/:project/patches/:id/diff",
+            get(patch_diff_handler),
+        )
         .layer(Extension(ctx))
 }
 
@@ -181,14 +191,15 @@ async fn history_handler(
     Ok::<_, Error>((StatusCode::OK, Json(response)))
 }
 
-/// Get project commit.
+/// Get project commit. The commit can also be given as the name of a canonical tag.
 /// `GET /projects/:project/commits/:sha`
 async fn commit_handler(
     Extension(ctx): Extension<Context>,
-    Path((project, sha)): Path<(Id, Oid)>,
+    Path((project, rev)): Path<(Id, String)>,
 ) -> impl IntoResponse {
     let storage = &ctx.profile.storage;
     let repo = storage.repository(project)?;
+    let sha = resolve(&repo, &rev)?;
     let commit = radicle_surf::commit(&repo.raw().into(), sha)?;
 
     Ok::<_, Error>(Json(commit))
@@ -220,15 +231,16 @@ async fn activity_handler(
     Ok::<_, Error>((StatusCode::OK, Json(json!({ "activity": timestamps }))))
 }
 
-/// Get project source tree.
+/// Get project source tree. The commit can also be given as the name of a canonical tag.
 /// `GET /projects/:project/tree/:sha/*path`
 async fn tree_handler(
     Extension(ctx): Extension<Context>,
-    Path((project, sha, path)): Path<(Id, Oid, String)>,
+    Path((project, rev, path)): Path<(Id, String, String)>,
 ) -> impl IntoResponse {
     let path = path.strip_prefix('/').ok_or(Error::NotFound)?.to_string();
     let storage = &ctx.profile.storage;
     let repo = storage.repository(project)?;
+    let sha = resolve(&repo, &rev)?;
     let tree = radicle_surf::object::tree(&repo.raw().into(), Some(Sha { sha }), Some(path))?;
     let response = json!({
         "path": &tree.path,
@@ -311,24 +323,48 @@ async fn readme_handler(
     ))?
 }
 
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct IssuesQuery {
+    page: Option<usize>,
+    per_page: Option<usize>,
+    status: Option<String>,
+    author: Option<NodeId>,
+    tag: Option<String>,
+    label: Option<LabelId>,
+    assignee: Option<NodeId>,
+    q: Option<String>,
+    sort: Option<cache::Sort>,
+}
+
 /// Get project issues list.
 /// `GET /projects/:project/issues`
 async fn issues_handler(
     Extension(ctx): Extension<Context>,
     Path(project): Path<Id>,
-    Query(qs): Query<PaginationQuery>,
+    Query(qs): Query<IssuesQuery>,
 ) -> impl IntoResponse {
-    let PaginationQuery { page, per_page } = qs;
-    let page = page.unwrap_or(0);
-    let per_page = per_page.unwrap_or(10);
+    let page = qs.page.unwrap_or(0);
+    let per_page = qs.per_page.unwrap_or(10);
     let storage = &ctx.profile.storage;
     let repo = storage.repository(project)?;
+    let cache = cache::Cache::reader(&repo)?;
     let issues = Issues::open(ctx.profile.public_key, &repo)?;
-    let issues = issues
-        .all()?
+    let entries = cache.issues(&cache::Query {
+        status: qs.status,
+        author: qs.author,
+        tag: qs.tag,
+        label: qs.label,
+        assignee: qs.assignee,
+        search: qs.q,
+        sort: qs.sort.unwrap_or_default(),
+    })?;
+    let issues = entries
         .into_iter()
-        .filter_map(|r| r.ok())
-        .map(|(id, issue, _)| {
+        .skip(page * per_page)
+        .take(per_page)
+        .filter_map(|entry| Some((entry.id, issues.get(&entry.id).ok()??)))
+        .map(|(id, issue)| {
             json!({
                 "id": id,
                 "author": issue.author(),
@@ -338,8 +374,6 @@ async fn issues_handler(
                 "tags": issue.tags().collect::<Vec<_>>(),
             })
         })
-        .skip(page * per_page)
-        .take(per_page)
         .collect::<Vec<_>>();
 
     Ok::<_, Error>(Json(issues))
@@ -368,6 +402,114 @@ async fn issue_handler(
     Ok::<_, Error>(Json(issue))
 }
 
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+struct PatchesQuery {
+    page: Option<usize>,
+    per_page: Option<usize>,
+    status: Option<String>,
+    author: Option<NodeId>,
+    tag: Option<String>,
+    label: Option<LabelId>,
+    q: Option<String>,
+    sort: Option<cache::Sort>,
+}
+
+/// Get project patches list.
+/// `GET /projects/:project/patches`
+async fn patches_handler(
+    Extension(ctx): Extension<Context>,
+    Path(project): Path<Id>,
+    Query(qs): Query<PatchesQuery>,
+) -> impl IntoResponse {
+    let page = qs.page.unwrap_or(0);
+    let per_page = qs.per_page.unwrap_or(10);
+    let storage = &ctx.profile.storage;
+    let repo = storage.repository(project)?;
+    let cache = cache::Cache::reader(&repo)?;
+    let patches = Patches::open(ctx.profile.public_key, &repo)?;
+    let entries = cache.patches(&cache::Query {
+        status: qs.status,
+        author: qs.author,
+        tag: qs.tag,
+        label: qs.label,
+        search: qs.q,
+        sort: qs.sort.unwrap_or_default(),
+        ..cache::Query::default()
+    })?;
+    let patches = entries
+        .into_iter()
+        .skip(page * per_page)
+        .take(per_page)
+        .filter_map(|entry| Some((entry.id, patches.get(&entry.id).ok()??)))
+        .map(|(id, patch)| {
+            json!({
+                "id": id,
+                "author": patch.author(),
+                "title": patch.title(),
+                "description": patch.description(),
+                "status": patch.status().to_string(),
+                "target": patch.target().to_string(),
+                "head": patch.latest().map(|(_, r)| r.oid),
+                "version": patch.version(),
+                "tags": patch.tags.iter().collect::<Vec<_>>(),
+                "labels": patch.labels.iter().collect::<Vec<_>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Ok::<_, Error>(Json(patches))
+}
+
+#[derive(Deserialize, Clone)]
+struct PatchDiffQuery {
+    from: Option<RevisionIx>,
+    to: Option<RevisionIx>,
+}
+
+/// Get the range-diff and interdiff between two revisions of a patch. By default, the latest
+/// revision is compared to the previous one.
+/// `GET /projects/:project/patches/:id/diff`
+async fn patch_diff_handler(
+    Extension(ctx): Extension<Context>,
+    Path((project, patch_id)): Path<(Id, Oid)>,
+    Query(qs): Query<PatchDiffQuery>,
+) -> impl IntoResponse {
+    let storage = &ctx.profile.storage;
+    let repo = storage.repository(project)?;
+    let patch = Patches::open(ctx.profile.public_key, &repo)?
+        .get(&patch_id.into())?
+        .ok_or(Error::NotFound)?;
+    let to = qs.to.unwrap_or_else(|| patch.version());
+    let from = qs
+        .from
+        .or_else(|| to.checked_sub(1))
+        .ok_or(Error::NotFound)?;
+    let range_diff = patch
+        .range_diff(from, to, repo.raw())?
+        .ok_or(Error::NotFound)?;
+    let interdiff = patch
+        .interdiff(from, to, repo.raw())?
+        .ok_or(Error::NotFound)?;
+
+    let mut text = String::new();
+    interdiff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            text.push(line.origin());
+        }
+        text.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })?;
+    let response = json!({
+        "from": from,
+        "to": to,
+        "rangeDiff": range_diff,
+        "interdiff": text,
+    });
+
+    Ok::<_, Error>(Json(response))
+}
+
 #[derive(Serialize)]
 struct Stats {
     branches: usize,
@@ -375,6 +517,22 @@ struct Stats {
     contributors: usize,
 }
 
+/// Resolve a revision, which is either the name of a canonical tag, eg. `v1.0`, or a commit
+/// SHA, to a commit.
+fn resolve<R: WriteRepository>(repo: &R, rev: &str) -> Result<Oid, Error> {
+    let tag = format!("refs/tags/{rev}");
+    let target = repo
+        .canonical_tags()?
+        .into_iter()
+        .find_map(|(name, oid)| (name.as_str() == tag).then_some(oid));
+
+    if let Some(oid) = target {
+        let commit = repo.raw().find_object(*oid, None)?.peel_to_commit()?;
+        return Ok(commit.id().into());
+    }
+    Oid::from_str(rev).map_err(|_| Error::NotFound)
+}
+
 fn stats<R: WriteRepository>(repo: &R) -> Result<Stats, Error> {
     let branches = repo.raw().branches(Some(BranchType::Local))?.count();
     let (_, head) = repo.head()?;
@@ -1,11 +1,21 @@
-use std::{io, net};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::{io, net, thread};
 
 use crossbeam_channel as chan;
-use nakamoto_net::{LocalTime, Reactor};
+use nakamoto_net::{LocalTime, Reactor, Waker};
 use thiserror::Error;
 
+use radicle::cob::cache::Cache;
+use radicle::cob::issue::Issues;
+use radicle::cob::patch::Patches;
+use radicle::cob::xref;
 use radicle::crypto::Signer;
+use radicle::identity::Id;
+use radicle::storage::git::{mirror, Storage};
+use radicle::storage::{WriteRepository as _, WriteStorage as _};
 
+use crate::client::handle::traits::Handle as _;
 use crate::clock::RefClock;
 use crate::profile::Profile;
 use crate::service::routing;
@@ -86,7 +96,11 @@ impl<R: Reactor> Client<R> {
         let (shutdown, shutdown_recv) = chan::bounded(1);
         let (listening_send, listening) = chan::bounded(1);
         let reactor = R::new(shutdown_recv, listening_send)?;
-        let events = Events {};
+        let events = Events {
+            node_dir: None,
+            jobs: None,
+            mirrors: None,
+        };
 
         Ok(Self {
             reactor,
@@ -98,12 +112,15 @@ impl<R: Reactor> Client<R> {
         })
     }
 
-    pub fn run<G: Signer>(
+    pub fn run<G: Signer + 'static>(
         mut self,
         config: Config,
         profile: Profile,
         signer: G,
-    ) -> Result<(), Error> {
+    ) -> Result<(), Error>
+    where
+        R::Waker: 'static,
+    {
         let network = config.service.network;
         let rng = fastrand::Rng::new();
         let time = LocalTime::now();
@@ -120,6 +137,12 @@ impl<R: Reactor> Client<R> {
 
         log::info!("Initializing client ({:?})..", network);
 
+        // The signer is shared with the worker, which records patch merges on our behalf.
+        let signer = Arc::new(signer);
+        self.events.node_dir = Some(node_dir);
+        self.events.jobs = Some(worker(storage.clone(), signer.clone(), self.handle()));
+        self.events.mirrors = Some(mirrors(storage.clone()));
+
         let service = service::Service::new(
             config.service,
             RefClock::from(time),
@@ -151,10 +174,200 @@ impl<R: Reactor> Client<R> {
     }
 }
 
-pub struct Events {}
+pub struct Events {
+    /// Node directory, where the node configuration is persisted.
+    node_dir: Option<PathBuf>,
+    /// Background jobs, run by the worker spawned with [`worker`].
+    jobs: Option<chan::Sender<Job>>,
+    /// Projects to mirror, synced by the worker spawned with [`mirrors`].
+    mirrors: Option<chan::Sender<Id>>,
+}
+
+impl Events {
+    /// Queue a job to be run in the background.
+    fn queue(&self, job: Job) {
+        if let Some(jobs) = &self.jobs {
+            if jobs.send(job).is_err() {
+                log::error!("Error queueing {:?}: worker has stopped", job);
+            }
+        }
+    }
+
+    /// Queue a sync of a project's mirror targets.
+    fn mirror(&self, project: Id) {
+        if let Some(mirrors) = &self.mirrors {
+            if mirrors.send(project).is_err() {
+                log::error!(
+                    "Error queueing mirror sync of {}: worker has stopped",
+                    project
+                );
+            }
+        }
+    }
+}
 
 impl nakamoto_net::Publisher<service::Event> for Events {
     fn publish(&mut self, e: service::Event) {
         log::info!("Received event {:?}", e);
+
+        match e {
+            service::Event::RefsFetched {
+                project, updated, ..
+            } if !updated.is_empty() => {
+                self.queue(Job::DetectMerges(project));
+                self.queue(Job::UpdateCache(project));
+                self.mirror(project);
+            }
+            service::Event::RefsFetched { .. } => {}
+            service::Event::RefsAnnounced { project } => {
+                self.mirror(project);
+            }
+            service::Event::NodeUpdated(update) => {
+                if let Some(dir) = &self.node_dir {
+                    if let Err(err) = update.persist(dir) {
+                        log::error!("Error saving node configuration: {}", err);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Work that is done in response to events, outside of the reactor thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Job {
+    /// Mark the patches of a project that were merged as such.
+    DetectMerges(Id),
+    /// Bring the issue and patch cache of a project up to date.
+    UpdateCache(Id),
+}
+
+/// Spawn a worker thread that runs the jobs sent on the returned channel, one at a time.
+/// Jobs that are queued more than once while the worker is busy are only run once.
+/// The worker stops when the channel is closed.
+fn worker<G: Signer + 'static, W: Waker + 'static>(
+    storage: Storage,
+    signer: Arc<G>,
+    mut handle: handle::Handle<W>,
+) -> chan::Sender<Job> {
+    let (sender, receiver) = chan::unbounded::<Job>();
+
+    thread::spawn(move || {
+        while let Ok(job) = receiver.recv() {
+            let mut pending = vec![job];
+
+            for job in receiver.try_iter() {
+                if !pending.contains(&job) {
+                    pending.push(job);
+                }
+            }
+            for job in pending {
+                match job {
+                    Job::DetectMerges(project) => {
+                        detect_merges(&storage, project, &signer, &mut handle)
+                    }
+                    Job::UpdateCache(project) => update_cache(&storage, project),
+                }
+            }
+        }
+    });
+    sender
+}
+
+/// Spawn a worker thread that syncs the mirror targets of the projects sent on the returned
+/// channel. Pushing to mirrors is slow and retried on failure, so it's kept apart from the
+/// other jobs, which would otherwise have to wait for it. Projects that are queued more than
+/// once while the worker is busy are only synced once.
+fn mirrors(storage: Storage) -> chan::Sender<Id> {
+    let (sender, receiver) = chan::unbounded::<Id>();
+
+    thread::spawn(move || {
+        while let Ok(project) = receiver.recv() {
+            let mut pending = vec![project];
+
+            for project in receiver.try_iter() {
+                if !pending.contains(&project) {
+                    pending.push(project);
+                }
+            }
+            for project in pending {
+                sync_mirrors(&storage, project);
+            }
+        }
+    });
+    sender
+}
+
+/// Mark the patches of a project that were merged by fetched commits as merged, along with
+/// the issues they solve. If we're not a delegate of the project, nothing is done. Our refs
+/// are signed and announced if anything changed.
+fn detect_merges<G: Signer, W: Waker>(
+    storage: &Storage,
+    project: Id,
+    signer: &G,
+    handle: &mut handle::Handle<W>,
+) {
+    let result = || -> anyhow::Result<Vec<_>> {
+        let repo = storage.repository(project)?;
+        let mut patches = Patches::open(*signer.public_key(), &repo)?;
+        let merged = patches.detect_merges(signer)?;
+
+        if merged.is_empty() {
+            return Ok(merged);
+        }
+        let mut issues = Issues::open(*signer.public_key(), &repo)?;
+
+        for (id, _) in &merged {
+            if let Some(patch) = patches.get(id)? {
+                if let Err(err) = xref::solve(&repo, &mut issues, &patch, signer) {
+                    log::error!("Error solving issues of patch {}: {}", id, err);
+                }
+            }
+        }
+        repo.sign_refs(signer)?;
+
+        Ok(merged)
+    };
+
+    match result() {
+        Ok(merged) if merged.is_empty() => {}
+        Ok(merged) => {
+            log::info!("Marked {} patch(es) of {} as merged", merged.len(), project);
+
+            if let Err(err) = handle.announce_refs(project) {
+                log::error!("Error announcing refs of {}: {}", project, err);
+            }
+        }
+        Err(err) => log::error!("Error detecting patch merges of {}: {}", project, err),
+    }
+}
+
+/// Bring the issue and patch cache of a project up to date with its fetched refs.
+fn update_cache(storage: &Storage, project: Id) {
+    let result = storage
+        .repository(project)
+        .map_err(radicle::cob::cache::Error::from)
+        .and_then(|repo| Cache::open(&repo)?.update(&repo));
+
+    match result {
+        Ok(count) => log::debug!("Updated {} cached object(s) of {}", count, project),
+        Err(err) => log::error!("Error updating object cache of {}: {}", project, err),
+    }
+}
+
+/// Push the canonical refs of a project to its mirror targets, if any.
+fn sync_mirrors(storage: &Storage, project: Id) {
+    let result = storage
+        .repository(project)
+        .map_err(mirror::Error::from)
+        .and_then(|repo| mirror::sync(&repo, &mirror::Options::default()));
+
+    match result {
+        Ok(outcomes) => {
+            for (url, outcome) in outcomes {
+                log::info!("Mirroring {} to {}: {:?}", project, url, outcome);
+            }
+        }
+        Err(err) => log::error!("Error mirroring {}: {}", project, err),
     }
 }
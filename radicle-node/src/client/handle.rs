@@ -7,6 +7,7 @@ use thiserror::Error;
 
 use crate::identity::Id;
 use crate::service;
+use crate::service::config::NodeUpdate;
 use crate::service::{CommandError, FetchLookup, QueryState};
 use crate::service::{NodeId, Session};
 
@@ -82,6 +83,12 @@ impl<W: Waker> traits::Handle for Handle<W> {
         self.command(service::Command::AnnounceRefs(id))
     }
 
+    fn update_node(&mut self, update: NodeUpdate) -> Result<bool, Error> {
+        let (sender, receiver) = chan::bounded(1);
+        self.command(service::Command::UpdateNode(update, sender))?;
+        receiver.recv().map_err(Error::from)
+    }
+
     fn command(&self, cmd: service::Command) -> Result<(), Error> {
         self.commands.send(cmd)?;
         self.waker.wake()?;
@@ -151,6 +158,9 @@ pub mod traits {
         fn untrack(&mut self, id: Id) -> Result<bool, Error>;
         /// Notify the client that a project has been updated.
         fn announce_refs(&mut self, id: Id) -> Result<(), Error>;
+        /// Update the alias, features or addresses announced by our node.
+        /// Returns whether our node announcement changed.
+        fn update_node(&mut self, update: NodeUpdate) -> Result<bool, Error>;
         /// Send a command to the command channel, and wake up the event loop.
         fn command(&self, cmd: service::Command) -> Result<(), Error>;
         /// Ask the client to shutdown.
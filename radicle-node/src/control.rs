@@ -11,6 +11,7 @@ use crate::client;
 use crate::client::handle::traits::Handle;
 use crate::identity::Id;
 use crate::node;
+use crate::service::config::NodeUpdate;
 use crate::service::FetchLookup;
 use crate::service::FetchResult;
 
@@ -128,6 +129,28 @@ fn drain<H: Handle>(stream: &UnixStream, handle: &mut H) -> Result<(), DrainErro
                     return Err(DrainError::InvalidCommandArg(arg.to_owned()));
                 }
             }
+            Some(("update-node", arg)) => {
+                let update = serde_json::from_str::<node::NodeUpdate>(arg)
+                    .ok()
+                    .and_then(|update| NodeUpdate::try_from(update).ok());
+
+                if let Some(update) = update {
+                    match handle.update_node(update) {
+                        Ok(updated) => {
+                            if updated {
+                                writeln!(writer, "{}", node::RESPONSE_OK)?;
+                            } else {
+                                writeln!(writer, "{}", node::RESPONSE_NOOP)?;
+                            }
+                        }
+                        Err(e) => {
+                            return Err(DrainError::Client(e));
+                        }
+                    }
+                } else {
+                    return Err(DrainError::InvalidCommandArg(arg.to_owned()));
+                }
+            }
             Some((cmd, _)) => return Err(DrainError::UnknownCommand(cmd.to_owned())),
 
             // Commands with no arguments.
@@ -209,6 +232,7 @@ fn fetch<W: Write, H: Handle>(id: Id, mut writer: W, handle: &mut H) -> Result<(
 mod tests {
     use std::io::prelude::*;
     use std::os::unix::net::UnixStream;
+    use std::str::FromStr;
     use std::{net, thread};
 
     use super::*;
@@ -274,4 +298,36 @@ mod tests {
         assert!(handle.untrack(&proj).unwrap());
         assert!(!handle.untrack(&proj).unwrap());
     }
+
+    #[test]
+    fn test_update_node() {
+        let tmp = tempfile::tempdir().unwrap();
+        let socket = tmp.path().join("node.sock");
+
+        thread::spawn({
+            let socket = socket.clone();
+            let handle = crate::test::handle::Handle::default();
+
+            move || crate::control::listen(socket, handle)
+        });
+
+        let handle = loop {
+            if let Ok(conn) = Node::connect(&socket) {
+                break conn;
+            }
+        };
+        let update = node::NodeUpdate {
+            alias: Some(node::Alias::from_str("alice").unwrap()),
+            external_addresses: Some(vec!["203.0.113.1:8776".to_owned()]),
+            ..node::NodeUpdate::default()
+        };
+        assert!(handle.update_node(&update).unwrap());
+        assert!(!handle.update_node(&update).unwrap());
+
+        let invalid = node::NodeUpdate {
+            external_addresses: Some(vec!["seed.example.com".to_owned()]),
+            ..node::NodeUpdate::default()
+        };
+        assert!(handle.update_node(&invalid).is_err());
+    }
 }
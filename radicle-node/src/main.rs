@@ -4,18 +4,22 @@ use anyhow::Context as _;
 
 use nakamoto_net::LocalDuration;
 
+use radicle::node::{Alias, Features};
 use radicle::profile;
 use radicle_node::crypto::ssh::keystore::MemorySigner;
 use radicle_node::logger;
 use radicle_node::prelude::Address;
+use radicle_node::service::config::NodeUpdate;
 use radicle_node::{client, control, service};
 
 type Reactor = nakamoto_net_poll::Reactor<net::TcpStream>;
 
 #[derive(Debug)]
 struct Options {
+    alias: Option<Alias>,
     connect: Vec<Address>,
     external_addresses: Vec<Address>,
+    features: Option<Features>,
     limits: service::config::Limits,
     listen: Vec<net::SocketAddr>,
 }
@@ -25,13 +29,19 @@ impl Options {
         use lexopt::prelude::*;
 
         let mut parser = lexopt::Parser::from_env();
+        let mut alias = None;
         let mut connect = Vec::new();
         let mut external_addresses = Vec::new();
+        let mut features = None;
         let mut limits = service::config::Limits::default();
         let mut listen = Vec::new();
 
         while let Some(arg) = parser.next()? {
             match arg {
+                Long("alias") => {
+                    let value: String = parser.value()?.parse()?;
+                    alias = Some(Alias::try_from(value)?);
+                }
                 Long("connect") => {
                     let addr = parser.value()?.parse()?;
                     connect.push(addr);
@@ -51,6 +61,9 @@ impl Options {
                     let addr = parser.value()?.parse()?;
                     listen.push(addr);
                 }
+                Long("no-seed") => {
+                    features = Some(Features::NONE);
+                }
                 Long("help") => {
                     println!("usage: radicle-node [--connect <addr>]..");
                     process::exit(0);
@@ -67,8 +80,10 @@ impl Options {
         }
 
         Ok(Self {
+            alias,
             connect,
             external_addresses,
+            features,
             limits,
             listen,
         })
@@ -93,11 +108,39 @@ fn main() -> anyhow::Result<()> {
         }
     };
     let handle = client.handle();
+    let node_dir = profile.paths().node();
+
+    // The alias, features and addresses given on the command line are kept for the next runs.
+    let update = NodeUpdate {
+        alias: options.alias,
+        features: options.features,
+        external_addresses: (!options.external_addresses.is_empty())
+            .then_some(options.external_addresses),
+    };
+    if update != NodeUpdate::default() {
+        update
+            .persist(&node_dir)
+            .context("Failed to save node configuration")?;
+    }
+    let persisted = radicle::node::config::Config::load(&node_dir)
+        .context("Failed to load node configuration")?;
+    let external_addresses = persisted
+        .external_addresses
+        .iter()
+        .flatten()
+        .map(|addr| addr.parse())
+        .collect::<Result<Vec<Address>, _>>()
+        .context("Failed to parse external addresses of node configuration")?;
     let config = client::Config {
         service: service::Config {
             connect: options.connect,
-            external_addresses: options.external_addresses,
+            external_addresses,
+            alias: persisted
+                .alias
+                .unwrap_or_else(|| service::Config::default().alias),
+            features: persisted.features.unwrap_or(Features::SEED),
             limits: options.limits,
+            remote_tracking: persisted.remote_tracking,
             ..service::Config::default()
         },
         listen: options.listen,
@@ -28,9 +28,8 @@ use crate::crypto;
 use crate::crypto::{Signer, Verified};
 use crate::git;
 use crate::identity::{Doc, Id};
-use crate::node;
 use crate::prelude::*;
-use crate::service::config::ProjectTracking;
+use crate::service::config::{NodeUpdate, ProjectTracking};
 use crate::service::message::{Address, Announcement, AnnouncementMessage, Ping};
 use crate::service::message::{NodeAnnouncement, RefsAnnouncement};
 use crate::storage;
@@ -48,7 +47,7 @@ use self::reactor::Reactor;
 /// Default radicle protocol port.
 pub const DEFAULT_PORT: u16 = 8776;
 /// Protocol version. Only updated for wire protocol changes.
-pub const PROTOCOL_VERSION: u32 = 1;
+pub const PROTOCOL_VERSION: u32 = 2;
 /// Target number of peers to maintain connections to.
 pub const TARGET_OUTBOUND_PEERS: usize = 8;
 /// How often to run the "idle" task.
@@ -81,6 +80,10 @@ pub enum Event {
         project: Id,
         updated: Vec<RefUpdate>,
     },
+    /// Our refs were announced, eg. after a push from a working copy.
+    RefsAnnounced { project: Id },
+    /// Our node announcement was updated. Carries the update, so that it can be persisted.
+    NodeUpdated(NodeUpdate),
 }
 
 /// General service error.
@@ -152,6 +155,8 @@ pub enum Command {
     Track(Id, chan::Sender<bool>),
     /// Untrack the given project.
     Untrack(Id, chan::Sender<bool>),
+    /// Update the information announced about our node.
+    UpdateNode(NodeUpdate, chan::Sender<bool>),
     /// Query the internal service state.
     QueryState(Arc<QueryState>, chan::Sender<Result<(), CommandError>>),
 }
@@ -164,6 +169,7 @@ impl fmt::Debug for Command {
             Self::Fetch(id, _) => write!(f, "Fetch({})", id),
             Self::Track(id, _) => write!(f, "Track({})", id),
             Self::Untrack(id, _) => write!(f, "Untrack({})", id),
+            Self::UpdateNode(update, _) => write!(f, "UpdateNode({:?})", update),
             Self::QueryState { .. } => write!(f, "QueryState(..)"),
         }
     }
@@ -192,6 +198,9 @@ pub struct Service<R, A, S, G> {
     addresses: A,
     /// State relating to gossip.
     gossip: Gossip,
+    /// Our own signed node announcement. Cached, since solving the proof-of-work
+    /// is expensive. Only set if we have external addresses to announce.
+    announcement: Option<Announcement>,
     /// Peer sessions, currently or recently connected.
     sessions: Sessions,
     /// Keeps track of node states.
@@ -258,6 +267,7 @@ where
             clock,
             routing,
             gossip: Gossip::default(),
+            announcement: None,
             // FIXME: This should be loaded from the address store.
             nodes: BTreeMap::new(),
             reactor: Reactor::default(),
@@ -312,6 +322,34 @@ where
         self.config.untrack(id)
     }
 
+    /// Update the information announced about our node.
+    /// Returns whether or not our node announcement changed.
+    /// If it did, the new announcement is sent to all connected peers.
+    pub fn update_node(&mut self, update: NodeUpdate) -> bool {
+        if let Some(addrs) = &update.external_addresses {
+            if addrs.len() > ADDRESS_LIMIT {
+                error!("Error updating node: external address limit ({ADDRESS_LIMIT}) exceeded");
+                return false;
+            }
+        }
+        if !self.config.update_node(update.clone()) {
+            return false;
+        }
+        self.refresh_announcement();
+        self.reactor.event(Event::NodeUpdated(update));
+
+        if let Some(ann) = &self.announcement {
+            let peers = self.sessions.negotiated().map(|(_, _, p)| p);
+            self.reactor.broadcast(ann.clone(), peers);
+        }
+        true
+    }
+
+    /// Get our own node announcement, if any.
+    pub fn announcement(&self) -> Option<&Announcement> {
+        self.announcement.as_ref()
+    }
+
     /// Find the closest `n` peers by proximity in tracking graphs.
     /// Returns a sorted list from the closest peer to the furthest.
     /// Peers with more trackings in common score score higher.
@@ -364,6 +402,7 @@ where
         trace!("Init {}", time.as_secs());
 
         self.start_time = time;
+        self.refresh_announcement();
 
         // Connect to configured peers.
         let addrs = self.config.connect.clone();
@@ -484,9 +523,14 @@ where
             Command::Untrack(id, resp) => {
                 resp.send(self.untrack(id)).ok();
             }
+            Command::UpdateNode(update, resp) => {
+                resp.send(self.update_node(update)).ok();
+            }
             Command::AnnounceRefs(id) => {
                 if let Err(err) = self.announce_refs(id) {
                     error!("Error announcing refs: {}", err);
+                } else {
+                    self.reactor.event(Event::RefsAnnounced { project: id });
                 }
             }
             Command::QueryState(query, sender) => {
@@ -532,6 +576,7 @@ where
                             &self.storage,
                             &self.signer,
                             &self.config,
+                            self.announcement.as_ref(),
                         ),
                     );
                 }
@@ -796,6 +841,7 @@ where
                             &self.storage,
                             &self.signer,
                             &self.config,
+                            self.announcement.as_ref(),
                         ),
                     );
                 }
@@ -923,6 +969,22 @@ where
         Ok(())
     }
 
+    /// Re-build and sign our node announcement from the current configuration.
+    fn refresh_announcement(&mut self) {
+        let now = self.clock.timestamp();
+        // Peers ignore node announcements that aren't newer than the last one they've seen,
+        // so make sure the timestamp increases, even if we're updated twice in the same second.
+        let timestamp = match &self.announcement {
+            Some(Announcement {
+                message: AnnouncementMessage::Node(prev),
+                ..
+            }) => now.max(prev.timestamp + 1),
+            _ => now,
+        };
+        self.announcement = gossip::node(timestamp, &self.config)
+            .map(|ann| AnnouncementMessage::from(ann).signed(&self.signer));
+    }
+
     ////////////////////////////////////////////////////////////////////////////
     // Periodic tasks
     ////////////////////////////////////////////////////////////////////////////
@@ -1265,6 +1327,7 @@ mod gossip {
         storage: &S,
         signer: &G,
         config: &Config,
+        announcement: Option<&Announcement>,
     ) -> Vec<Message> {
         let inventory = match storage.inventory() {
             Ok(i) => i,
@@ -1288,15 +1351,15 @@ mod gossip {
             Message::inventory(gossip::inventory(timestamp, inventory), signer),
             Message::subscribe(config.filter(), timestamp, Timestamp::MAX),
         ];
-        if let Some(m) = gossip::node(timestamp, config) {
-            msgs.push(Message::node(m, signer));
+        if let Some(ann) = announcement {
+            msgs.push(ann.clone().into());
         };
 
         msgs
     }
 
     pub fn node(timestamp: Timestamp, config: &Config) -> Option<NodeAnnouncement> {
-        let features = node::Features::SEED;
+        let features = config.features;
         let alias = config.alias();
         let addresses: BoundedVec<_, ADDRESS_LIMIT> = config
             .external_addresses
@@ -1,9 +1,14 @@
+use std::path::Path;
+use std::str::FromStr;
+
 use super::nakamoto::LocalDuration;
 
 use crate::collections::HashSet;
-use crate::identity::{Id, PublicKey};
+use crate::identity::Id;
+use crate::node;
+use crate::node::{Alias, Features};
 use crate::service::filter::Filter;
-use crate::service::message::Address;
+use crate::service::message::{Address, AddressParseError};
 
 /// Peer-to-peer network.
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
@@ -31,16 +36,7 @@ impl Default for ProjectTracking {
 }
 
 /// Project remote tracking policy.
-#[derive(Debug, Default, Clone)]
-pub enum RemoteTracking {
-    /// Only track remotes of project delegates.
-    #[default]
-    DelegatesOnly,
-    /// Track all remotes.
-    All { blocked: HashSet<PublicKey> },
-    /// Track a specific list of users as well as the project delegates.
-    Allowed(HashSet<PublicKey>),
-}
+pub use crate::node::config::RemoteTracking;
 
 /// Configuration parameters defining attributes of minima and maxima.
 #[derive(Debug, Clone)]
@@ -60,6 +56,9 @@ impl Default for Limits {
     }
 }
 
+/// Default node alias, used when none is configured.
+pub const DEFAULT_ALIAS: &str = "anonymous";
+
 /// Service configuration.
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -68,6 +67,10 @@ pub struct Config {
     pub connect: Vec<Address>,
     /// Specify the node's public addresses
     pub external_addresses: Vec<Address>,
+    /// Non-unique node alias, advertized to the network.
+    pub alias: Alias,
+    /// Features advertized to the network.
+    pub features: Features,
     /// Peer-to-peer network.
     pub network: Network,
     /// Project tracking policy.
@@ -86,6 +89,8 @@ impl Default for Config {
         Self {
             connect: Vec::default(),
             external_addresses: Vec::default(),
+            alias: Alias::from_str(DEFAULT_ALIAS).expect("Config::default: alias is valid"),
+            features: Features::SEED,
             network: Network::default(),
             project_tracking: ProjectTracking::default(),
             remote_tracking: RemoteTracking::default(),
@@ -131,10 +136,79 @@ impl Config {
         }
     }
 
+    /// Get the node alias, as it is announced to the network, padded with zeros.
     pub fn alias(&self) -> [u8; 32] {
         let mut alias = [0u8; 32];
+        let bytes = self.alias.as_str().as_bytes();
 
-        alias[..9].copy_from_slice("anonymous".as_bytes());
+        alias[..bytes.len()].copy_from_slice(bytes);
         alias
     }
+
+    /// Apply a node update. Returns whether the configuration was changed.
+    pub fn update_node(&mut self, update: NodeUpdate) -> bool {
+        let mut changed = false;
+
+        if let Some(alias) = update.alias {
+            changed |= alias != self.alias;
+            self.alias = alias;
+        }
+        if let Some(features) = update.features {
+            changed |= features != self.features;
+            self.features = features;
+        }
+        if let Some(addresses) = update.external_addresses {
+            changed |= addresses != self.external_addresses;
+            self.external_addresses = addresses;
+        }
+        changed
+    }
+}
+
+/// Update to the information our node announces about itself.
+/// Fields that are `None` are left unchanged.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NodeUpdate {
+    /// New node alias.
+    pub alias: Option<Alias>,
+    /// New advertized features.
+    pub features: Option<Features>,
+    /// New external addresses.
+    pub external_addresses: Option<Vec<Address>>,
+}
+
+impl TryFrom<node::NodeUpdate> for NodeUpdate {
+    type Error = AddressParseError;
+
+    fn try_from(update: node::NodeUpdate) -> Result<Self, Self::Error> {
+        let external_addresses = update
+            .external_addresses
+            .map(|addrs| addrs.iter().map(|a| a.parse()).collect::<Result<_, _>>())
+            .transpose()?;
+
+        Ok(Self {
+            alias: update.alias,
+            features: update.features,
+            external_addresses,
+        })
+    }
+}
+
+impl NodeUpdate {
+    /// Persist the update in the node configuration of the given node directory, so that it is
+    /// kept for the next runs. See [`node::config::Config`].
+    pub fn persist(&self, dir: &Path) -> Result<(), node::config::Error> {
+        let mut config = node::config::Config::load(dir)?;
+
+        if let Some(alias) = &self.alias {
+            config.alias = Some(alias.clone());
+        }
+        if let Some(features) = self.features {
+            config.features = Some(features);
+        }
+        if let Some(addresses) = &self.external_addresses {
+            config.external_addresses = Some(addresses.iter().map(|a| a.to_string()).collect());
+        }
+        config.save(dir)
+    }
 }
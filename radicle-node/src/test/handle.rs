@@ -7,12 +7,14 @@ use crate::client::handle::traits;
 use crate::client::handle::Error;
 use crate::identity::Id;
 use crate::service;
+use crate::service::config::NodeUpdate;
 use crate::service::FetchLookup;
 
 #[derive(Default, Clone)]
 pub struct Handle {
     pub updates: Arc<Mutex<Vec<Id>>>,
     pub tracking: HashSet<Id>,
+    pub node_updates: Arc<Mutex<Vec<NodeUpdate>>>,
 }
 
 impl traits::Handle for Handle {
@@ -38,6 +40,15 @@ impl traits::Handle for Handle {
         Ok(())
     }
 
+    fn update_node(&mut self, update: NodeUpdate) -> Result<bool, Error> {
+        let mut updates = self.node_updates.lock().unwrap();
+        let changed = updates.last() != Some(&update);
+
+        updates.push(update);
+
+        Ok(changed)
+    }
+
     fn command(&self, _cmd: service::Command) -> Result<(), Error> {
         Ok(())
     }
@@ -1,4 +1,6 @@
 use std::io;
+use std::net;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use crossbeam_channel as chan;
@@ -8,6 +10,7 @@ use crate::address;
 use crate::collections::{HashMap, HashSet};
 use crate::crypto::test::signer::MockSigner;
 use crate::identity::Id;
+use crate::node::Alias;
 use crate::prelude::*;
 use crate::prelude::{LocalDuration, Timestamp};
 use crate::service::config::*;
@@ -30,8 +33,8 @@ use crate::test::simulator::{Peer as _, Simulation};
 use crate::test::storage::MockStorage;
 use crate::wire::Decode;
 use crate::wire::Encode;
-use crate::LocalTime;
 use crate::{client, git, identity, rad, service, test};
+use crate::{Link, LocalTime};
 
 // NOTE
 //
@@ -202,9 +205,36 @@ fn test_persistent_peer_connect() {
 }
 
 #[test]
-#[ignore]
 fn test_wrong_peer_version() {
-    // TODO
+    let mut alice = Peer::new("alice", [8, 8, 8, 8], MockStorage::empty());
+    let bob = Peer::new("bob", [9, 9, 9, 9], MockStorage::empty());
+    let local = net::SocketAddr::new(alice.ip, 8776);
+
+    alice.initialize();
+    alice.service.connecting(bob.addr(), &local, Link::Inbound);
+    alice.service.connected(bob.addr(), Link::Inbound);
+    alice.receive(
+        &bob.addr(),
+        Message::Initialize {
+            id: bob.node_id(),
+            version: PROTOCOL_VERSION - 1,
+            addrs: Some(Address::from(bob.addr())).into(),
+        },
+    );
+
+    alice
+        .outbox()
+        .find(|m| {
+            matches!(
+                m,
+                &Io::Disconnect(
+                    addr,
+                    DisconnectReason::Error(session::Error::WrongVersion(v))
+                ) if addr == bob.addr() && v == PROTOCOL_VERSION - 1
+            )
+        })
+        .expect("disconnect bob, who speaks an older protocol");
+    assert_eq!(0, alice.sessions().negotiated().count());
 }
 
 #[test]
@@ -339,6 +369,64 @@ fn test_inventory_pruning() {
     }
 }
 
+#[test]
+fn test_node_announcement_update() {
+    let mut rng = fastrand::Rng::new();
+    let signer = MockSigner::new(&mut rng);
+    let config = Config {
+        external_addresses: vec![net::SocketAddr::from(([8, 8, 8, 8], DEFAULT_PORT)).into()],
+        ..Config::default()
+    };
+    let mut alice = Peer::config(
+        "alice",
+        config,
+        [8, 8, 8, 8],
+        MockStorage::empty(),
+        address::Book::memory().unwrap(),
+        signer,
+        rng,
+    );
+    let bob = Peer::new("bob", [9, 9, 9, 9], MockStorage::empty());
+
+    alice.connect_to(&bob);
+
+    let original = alice
+        .announcement()
+        .cloned()
+        .expect("alice has a node announcement");
+    let update = NodeUpdate {
+        alias: Some(Alias::from_str("alice").unwrap()),
+        features: Some(radicle::node::Features::NONE),
+        ..NodeUpdate::default()
+    };
+    assert!(
+        alice.update_node(update.clone()),
+        "the announcement is updated"
+    );
+    assert!(
+        !alice.update_node(update.clone()),
+        "the announcement is unchanged"
+    );
+
+    let updated = alice.announcement().cloned().unwrap();
+    assert_ne!(original, updated);
+    assert_matches!(
+        &updated.message,
+        AnnouncementMessage::Node(NodeAnnouncement { alias, features, .. })
+        if alias.starts_with(b"alice\0") && *features == radicle::node::Features::NONE
+    );
+    assert_matches!(
+        alice.messages(&bob.addr()).next(),
+        Some(Message::Announcement(ann)) if ann == updated,
+        "the updated announcement is sent to connected peers"
+    );
+    assert_matches!(
+        alice.events().next(),
+        Some(service::Event::NodeUpdated(u)) if u == update,
+        "the update is published, so that it can be persisted"
+    );
+}
+
 #[test]
 fn test_tracking() {
     let mut alice = Peer::config(
@@ -408,6 +408,11 @@ impl<V> Encode for SignedRefs<V> {
         let mut n = 0;
 
         n += self.refs.encode(writer)?;
+        // Nb. The zero object id is used when there is no parent.
+        n += self
+            .parent
+            .unwrap_or_else(|| git::raw::Oid::zero().into())
+            .encode(writer)?;
         n += self.signature.encode(writer)?;
 
         Ok(n)
@@ -417,9 +422,11 @@ impl<V> Encode for SignedRefs<V> {
 impl Decode for SignedRefs<Unverified> {
     fn decode<R: io::Read + ?Sized>(reader: &mut R) -> Result<Self, Error> {
         let refs = Refs::decode(reader)?;
+        let parent = git::Oid::decode(reader)?;
+        let parent = (!parent.is_zero()).then_some(parent);
         let signature = Signature::decode(reader)?;
 
-        Ok(Self::new(refs, signature))
+        Ok(Self::new(refs, parent, signature))
     }
 }
 
@@ -1,12 +1,20 @@
 #![allow(clippy::collapsible_if)]
+use std::collections::BTreeMap;
 use std::path::PathBuf;
-use std::{env, io, process};
+use std::{env, io, iter, process};
 
 use thiserror::Error;
 
-use radicle::crypto::PublicKey;
+use radicle::cob::cache::Cache;
+use radicle::cob::issue::Issues;
+use radicle::cob::patch::Patches;
+use radicle::cob::xref;
+use radicle::crypto::{PublicKey, Signer};
+use radicle::git;
 use radicle::node::Handle;
 use radicle::storage::git::transport::local::{Url, UrlError};
+use radicle::storage::git::Repository;
+use radicle::storage::refs;
 use radicle::storage::{ReadRepository, WriteRepository, WriteStorage};
 
 /// The service invoked by git on the remote repository, during a push.
@@ -32,6 +40,9 @@ pub enum Error {
     /// Error with the remote url.
     #[error("invalid remote url: {0}")]
     RemoteUrl(#[from] UrlError),
+    /// A pushed reference can't be signed.
+    #[error("pushed reference was rejected: {0}")]
+    InvalidRef(refs::CategoryError),
 }
 
 /// Run the radicle remote helper using the given profile.
@@ -90,9 +101,20 @@ pub fn run(profile: radicle::Profile) -> Result<(), Box<dyn std::error::Error +
                 } else {
                     None
                 };
+                // The references of our namespace before the push, so that they can be
+                // restored if the push is rejected.
+                let snapshot = if signer.is_some() {
+                    namespace_refs(&proj, &namespace)?
+                } else {
+                    BTreeMap::new()
+                };
                 println!(); // Empty line signifies connection is established.
 
+                // Nb. Refs outside of the namespace are advertised by `git-receive-pack` as
+                // `.have` lines, so objects that are already in storage, eg. borrowed by the
+                // working copy, or fetched from other remotes, aren't transferred again.
                 let mut child = process::Command::new(service)
+                    .envs(hide_refs())
                     .arg(proj.path())
                     .env("GIT_DIR", proj.path())
                     .env("GIT_NAMESPACE", namespace.to_string())
@@ -103,8 +125,29 @@ pub fn run(profile: radicle::Profile) -> Result<(), Box<dyn std::error::Error +
 
                 if child.wait()?.success() {
                     if let Some(signer) = signer {
+                        // References that can't be signed are rejected before anything is
+                        // signed or announced: our namespace is restored to what it was
+                        // before the push, and the push fails.
+                        if let Err(err) = check_refs(&namespace_refs(&proj, &namespace)?) {
+                            restore_refs(&proj, &namespace, &snapshot)?;
+
+                            return Err(Error::InvalidRef(err).into());
+                        }
+                        // If we're a delegate, mark the patches that were merged by this
+                        // push, and the issues they solve, before our refs are signed.
+                        // The push itself already succeeded, so failing to do so shouldn't
+                        // prevent our refs from being signed.
+                        if let Err(err) = detect_merges(&proj, &profile, &signer) {
+                            eprintln!("warning: couldn't detect merged patches: {err}");
+                        }
                         proj.sign_refs(&signer)?;
                         proj.set_head()?;
+                        proj.set_canonical_tags()?;
+                        // The cache can always be rebuilt, so failing to update it
+                        // shouldn't fail the push.
+                        if let Err(err) = Cache::open(&proj).and_then(|mut c| c.update(&proj)) {
+                            eprintln!("warning: couldn't update object cache: {err}");
+                        }
                         // Connect to local node and announce refs to the network.
                         // If our node is not running, we simply skip this step, as the
                         // refs will be announced eventually, when the node restarts.
@@ -126,3 +169,116 @@ pub fn run(profile: radicle::Profile) -> Result<(), Box<dyn std::error::Error +
 
     Ok(())
 }
+
+/// Check that the given namespaced references are of a known category, eg. that a
+/// `refs/cobs/*` reference names a valid object, since they can't be signed otherwise.
+///
+/// Every invalid reference is reported, and the first error is returned.
+fn check_refs(pushed: &BTreeMap<String, git::raw::Oid>) -> Result<(), refs::CategoryError> {
+    let mut invalid = Vec::new();
+
+    for name in pushed.keys() {
+        let Ok((_, refname)) = git::parse_ref::<PublicKey>(name) else {
+            continue;
+        };
+        if let Err(err) = refs::check_category(&refname) {
+            eprintln!("error: {err}");
+            invalid.push(err);
+        }
+    }
+    match invalid.into_iter().next() {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// The direct references of the given namespace, by name.
+fn namespace_refs(
+    proj: &Repository,
+    namespace: &PublicKey,
+) -> Result<BTreeMap<String, git::raw::Oid>, git::raw::Error> {
+    let mut refs = BTreeMap::new();
+
+    for r in proj
+        .raw()
+        .references_glob(&format!("refs/namespaces/{namespace}/*"))?
+    {
+        let r = r?;
+        if let (Some(name), Some(oid)) = (r.name(), r.target()) {
+            refs.insert(name.to_owned(), oid);
+        }
+    }
+    Ok(refs)
+}
+
+/// Restore the references of the given namespace to the given snapshot, undoing a push.
+fn restore_refs(
+    proj: &Repository,
+    namespace: &PublicKey,
+    snapshot: &BTreeMap<String, git::raw::Oid>,
+) -> Result<(), git::raw::Error> {
+    for name in namespace_refs(proj, namespace)?.keys() {
+        if !snapshot.contains_key(name) {
+            proj.raw().find_reference(name)?.delete()?;
+        }
+    }
+    for (name, oid) in snapshot {
+        proj.raw().reference(name, *oid, true, "rejected push")?;
+    }
+    Ok(())
+}
+
+/// Mark the patches merged by a push as merged, along with the issues they solve, if we're
+/// a delegate of the project.
+fn detect_merges<G: Signer>(
+    proj: &Repository,
+    profile: &radicle::Profile,
+    signer: &G,
+) -> Result<(), Box<dyn std::error::Error + 'static>> {
+    let (_, doc) = proj.project()?;
+    if !doc.delegates.iter().any(|d| *d.id == profile.public_key) {
+        return Ok(());
+    }
+    let mut patches = Patches::open(profile.public_key, proj)?;
+    let mut issues = Issues::open(profile.public_key, proj)?;
+
+    for (id, _) in patches.detect_merges(signer)? {
+        if let Some(patch) = patches.get(&id)? {
+            if let Err(err) = xref::solve(proj, &mut issues, &patch, signer) {
+                eprintln!("warning: couldn't solve issues of patch {id}: {err}");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Configuration passed to `git-receive-pack` via the environment, so that only references
+/// of known categories can be pushed. Pushing to any other reference is denied by `git`.
+///
+/// Since `git` only matches prefixes, references within a category that are still invalid,
+/// eg. `refs/cobs/<name>` without an object id, are rejected by [`check_refs`].
+fn hide_refs() -> Vec<(String, String)> {
+    let values = iter::once(String::from("refs"))
+        .chain(refs::CATEGORIES.iter().map(|c| format!("!refs/{c}")))
+        .collect::<Vec<_>>();
+    // Configuration the user passed via the environment is kept, by appending our entries
+    // after theirs.
+    let offset = env::var("GIT_CONFIG_COUNT")
+        .ok()
+        .and_then(|count| count.parse::<usize>().ok())
+        .unwrap_or_default();
+    let mut envs = vec![(
+        String::from("GIT_CONFIG_COUNT"),
+        (offset + values.len()).to_string(),
+    )];
+
+    for (i, value) in values.into_iter().enumerate() {
+        let i = offset + i;
+        envs.push((
+            format!("GIT_CONFIG_KEY_{i}"),
+            String::from("receive.hideRefs"),
+        ));
+        envs.push((format!("GIT_CONFIG_VALUE_{i}"), value));
+    }
+    envs
+}
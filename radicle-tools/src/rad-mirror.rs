@@ -0,0 +1,82 @@
+use std::env;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+use radicle::identity::Id;
+use radicle::storage::git::mirror;
+use radicle::storage::WriteStorage as _;
+
+const USAGE: &str =
+    "usage: rad-mirror (add|remove) <id> <url> | list <id> | sync [<id>] [--watch <secs>]";
+
+fn main() -> anyhow::Result<()> {
+    let profile = radicle::Profile::load()?;
+    let storage = &profile.storage;
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(|a| a.as_str()).collect::<Vec<_>>();
+    let options = mirror::Options::default();
+
+    match args.as_slice() {
+        ["add", id, url] => {
+            let repo = storage.repository(Id::from_str(id)?)?;
+
+            if mirror::add(&repo, url)? {
+                println!("ok: added {url}");
+            } else {
+                println!("ok: {url} was already added");
+            }
+        }
+        ["remove", id, url] => {
+            let repo = storage.repository(Id::from_str(id)?)?;
+
+            if mirror::remove(&repo, url)? {
+                println!("ok: removed {url}");
+            } else {
+                anyhow::bail!("Error: {url} is not a mirror target");
+            }
+        }
+        ["list", id] => {
+            let repo = storage.repository(Id::from_str(id)?)?;
+
+            for target in mirror::targets(&repo)? {
+                println!("{} {}", target.url, target.status);
+            }
+        }
+        ["sync", id] => {
+            let repo = storage.repository(Id::from_str(id)?)?;
+
+            for (url, outcome) in mirror::sync(&repo, &options)? {
+                println!("{id} {url}: {outcome:?}");
+            }
+        }
+        ["sync"] => loop_sync(storage, &options, None)?,
+        ["sync", "--watch", secs] => {
+            loop_sync(storage, &options, Some(Duration::from_secs(secs.parse()?)))?
+        }
+        _ => {
+            anyhow::bail!("Error: {USAGE}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Sync all projects, repeatedly if an interval is given.
+fn loop_sync(
+    storage: &radicle::storage::git::Storage,
+    options: &mirror::Options,
+    interval: Option<Duration>,
+) -> anyhow::Result<()> {
+    loop {
+        for (id, outcomes) in mirror::run(storage, options)? {
+            for (url, outcome) in outcomes {
+                println!("{id} {url}: {outcome:?}");
+            }
+        }
+        match interval {
+            Some(interval) => thread::sleep(interval),
+            None => return Ok(()),
+        }
+    }
+}
@@ -15,6 +15,7 @@ fn main() -> anyhow::Result<()> {
     let project = profile.storage.repository(id)?;
     let sigrefs = project.sign_refs(&signer)?;
     let head = project.set_head()?;
+    project.set_canonical_tags()?;
 
     radicle::node::connect(&profile.node())?.announce_refs(&id)?;
 
@@ -1,9 +1,14 @@
+#[cfg(feature = "sql")]
+pub mod cache;
 pub mod common;
 pub mod issue;
+pub mod label;
 pub mod op;
 pub mod patch;
+pub mod proposal;
 pub mod store;
 pub mod thread;
+pub mod xref;
 
 pub use cob::{create, get, list, remove, update};
 pub use cob::{
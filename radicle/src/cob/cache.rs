@@ -0,0 +1,748 @@
+//! Queryable cache of the issues and patches of a repository.
+//!
+//! Listing collaborative objects requires replaying their full change history. The cache
+//! instead stores a summary of every issue and patch, keyed by object id, history tips and
+//! canonical identity head, so that objects only have to be replayed when their history or
+//! the project delegates change.
+//!
+//! The cache is fully derived from the repository and can be rebuilt at any time.
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use std::{fmt, io};
+
+use serde::{Deserialize, Serialize};
+use sqlite as sql;
+use thiserror::Error;
+
+use crate::cob;
+use crate::cob::common::{Delegates, Timestamp};
+use crate::cob::issue::{self, Issue};
+use crate::cob::label::LabelId;
+use crate::cob::patch::{self, Patch};
+use crate::cob::store::FromHistory as _;
+use crate::cob::{ActorId, ObjectId, TypeName};
+use crate::git;
+use crate::sql::transaction;
+use crate::storage;
+use crate::storage::git::Repository;
+use crate::storage::ReadRepository;
+
+/// Name of the cache database, in the repository's git directory.
+pub const CACHE_FILE: &str = "cobs.db";
+
+#[derive(Error, Debug)]
+pub enum Error {
+    /// I/O error.
+    #[error("i/o error: {0}")]
+    Io(#[from] io::Error),
+    /// An Internal error.
+    #[error("internal error: {0}")]
+    Internal(#[from] sql::Error),
+    #[error("git: {0}")]
+    Git(#[from] git2::Error),
+    #[error("storage: {0}")]
+    Storage(#[from] storage::Error),
+    #[error("store: {0}")]
+    Store(#[from] cob::store::Error),
+    #[error("retrieve error: {0}")]
+    Retrieve(#[from] cob::error::Retrieve),
+}
+
+/// Kind of cached object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Issue,
+    Patch,
+}
+
+impl Kind {
+    fn typename(&self) -> &'static TypeName {
+        match self {
+            Self::Issue => &*issue::TYPENAME,
+            Self::Patch => &*patch::TYPENAME,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Issue => "issue",
+            Self::Patch => "patch",
+        }
+    }
+}
+
+/// Cached summary of an issue or patch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub id: ObjectId,
+    pub title: String,
+    /// Object status, eg. `open` or `closed` for issues, and `proposed` for patches.
+    pub status: String,
+    pub author: ActorId,
+    pub timestamp: Timestamp,
+    pub tags: Vec<String>,
+    pub labels: Vec<LabelId>,
+    pub assignees: Vec<ActorId>,
+}
+
+/// Order in which query results are returned.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Sort {
+    /// Most recently created first.
+    #[default]
+    Newest,
+    /// Least recently created first.
+    Oldest,
+    /// By title, in alphabetical order.
+    Title,
+}
+
+/// Cache query. Only entries matching all given criteria are returned.
+#[derive(Debug, Default, Clone)]
+pub struct Query {
+    pub status: Option<String>,
+    pub author: Option<ActorId>,
+    pub tag: Option<String>,
+    pub label: Option<LabelId>,
+    pub assignee: Option<ActorId>,
+    /// Text that should appear in the title, description or comments.
+    pub search: Option<String>,
+    pub sort: Sort,
+}
+
+/// A file-backed object cache.
+pub struct Cache {
+    db: sql::Connection,
+}
+
+impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cache(..)")
+    }
+}
+
+impl Cache {
+    const SCHEMA: &str = include_str!("cache.sql");
+
+    /// Open the cache of a repository. Creates a new, empty cache if it doesn't exist.
+    pub fn open(repo: &Repository) -> Result<Self, Error> {
+        Self::open_at(repo.path().join(CACHE_FILE))
+    }
+
+    /// Open the cache of a repository for reading only, eg. to serve queries without writing
+    /// to storage. The cache is expected to be kept up to date by the node and the remote
+    /// helper. If it isn't, or doesn't exist yet, it is copied into an in-memory cache, in
+    /// which only the objects that changed are replayed.
+    pub fn reader(repo: &Repository) -> Result<Self, Error> {
+        let path = repo.path().join(CACHE_FILE);
+        let exists = path.exists();
+
+        if exists {
+            let db =
+                sql::Connection::open_with_flags(&path, sql::OpenFlags::new().set_read_only())?;
+            let cache = Self { db };
+
+            if cache.is_current(repo)? {
+                return Ok(cache);
+            }
+        }
+        let mut cache = Self::memory()?;
+        if exists {
+            cache.copy(&path)?;
+        }
+        cache.update(repo)?;
+
+        Ok(cache)
+    }
+
+    /// Open a cache at the given path. Creates a new, empty cache if it doesn't exist.
+    pub fn open_at<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let db = sql::Connection::open(path)?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Create a new in-memory cache.
+    pub fn memory() -> Result<Self, Error> {
+        let db = sql::Connection::open(":memory:")?;
+        db.execute(Self::SCHEMA)?;
+
+        Ok(Self { db })
+    }
+
+    /// Bring the cache up to date with the repository. Only objects whose history tips
+    /// changed are replayed, unless the project identity changed, in which case all objects
+    /// are, since their authorization may have changed. Objects that can no longer be summarized, eg. a patch whose
+    /// revisions were all redacted, are removed from the cache.
+    ///
+    /// Returns the number of objects that were added, updated or removed.
+    pub fn update(&mut self, repo: &Repository) -> Result<usize, Error> {
+        let mut count = 0;
+
+        let identity = cob::store::identity(repo)?;
+        let delegates = Delegates::from(&identity);
+
+        for kind in [Kind::Issue, Kind::Patch] {
+            let cached = self.keys(kind)?;
+            let current = keys(repo, kind.typename(), &identity.head)?;
+            let mut removed = cached
+                .keys()
+                .filter(|id| !current.contains_key(id))
+                .copied()
+                .collect::<Vec<_>>();
+            let mut updated = Vec::new();
+
+            for (id, key) in current {
+                if cached.get(&id) == Some(&key) {
+                    continue;
+                }
+                let row = match cob::get(repo, kind.typename(), &id)? {
+                    Some(obj) => match Row::from_history(kind, id, obj.history(), &delegates) {
+                        Ok(row) => row,
+                        Err(err) => {
+                            log::warn!("Error loading {} {id} for cache: {err}", kind.as_str());
+                            None
+                        }
+                    },
+                    None => None,
+                };
+                match row {
+                    Some(row) => updated.push((key, row)),
+                    None if cached.contains_key(&id) => removed.push(id),
+                    None => {}
+                }
+            }
+            count += removed.len() + updated.len();
+
+            transaction(&self.db, |db| {
+                for id in &removed {
+                    remove(db, id)?;
+                }
+                for (key, row) in &updated {
+                    insert(db, kind, key, row)?;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(count)
+    }
+
+    /// Check whether the cache is up to date with the repository.
+    pub fn is_current(&self, repo: &Repository) -> Result<bool, Error> {
+        let identity = cob::store::identity(repo)?;
+
+        for kind in [Kind::Issue, Kind::Patch] {
+            if self.keys(kind)? != keys(repo, kind.typename(), &identity.head)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Clear the cache and rebuild it from the repository.
+    ///
+    /// Returns the number of objects cached.
+    pub fn rebuild(&mut self, repo: &Repository) -> Result<usize, Error> {
+        transaction(&self.db, |db| {
+            db.execute("DELETE FROM attributes")?;
+            db.execute("DELETE FROM objects")?;
+
+            Ok(())
+        })?;
+        self.update(repo)
+    }
+
+    /// Query the cached issues.
+    pub fn issues(&self, query: &Query) -> Result<Vec<Entry>, Error> {
+        self.query(Kind::Issue, query)
+    }
+
+    /// Query the cached patches.
+    pub fn patches(&self, query: &Query) -> Result<Vec<Entry>, Error> {
+        self.query(Kind::Patch, query)
+    }
+
+    fn query(&self, kind: Kind, query: &Query) -> Result<Vec<Entry>, Error> {
+        let mut conditions = vec!["type = ?"];
+        let mut params = vec![kind.as_str().to_owned()];
+        let attribute = "EXISTS (
+            SELECT 1 FROM attributes
+            WHERE object = objects.id AND name = ? AND value = ?
+        )";
+
+        if let Some(status) = &query.status {
+            conditions.push("status = ?");
+            params.push(status.clone());
+        }
+        if let Some(author) = &query.author {
+            conditions.push("author = ?");
+            params.push(author.to_human());
+        }
+        if let Some(tag) = &query.tag {
+            conditions.push(attribute);
+            params.extend(["tag".to_owned(), tag.clone()]);
+        }
+        if let Some(label) = &query.label {
+            conditions.push(attribute);
+            params.extend(["label".to_owned(), label.to_string()]);
+        }
+        if let Some(assignee) = &query.assignee {
+            conditions.push(attribute);
+            params.extend(["assignee".to_owned(), assignee.to_human()]);
+        }
+        if let Some(search) = &query.search {
+            let pattern = format!("%{}%", escape(search));
+
+            conditions.push("(title LIKE ? ESCAPE '\\' OR body LIKE ? ESCAPE '\\')");
+            params.extend([pattern.clone(), pattern]);
+        }
+        let order = match query.sort {
+            Sort::Newest => "timestamp DESC, id",
+            Sort::Oldest => "timestamp ASC, id",
+            Sort::Title => "title COLLATE NOCASE, id",
+        };
+        let sql = format!(
+            "SELECT id, title, status, author, timestamp FROM objects
+             WHERE {}
+             ORDER BY {order}",
+            conditions.join(" AND ")
+        );
+        let mut stmt = self.db.prepare(sql.as_str())?;
+
+        for (i, param) in params.iter().enumerate() {
+            stmt.bind(i + 1, param.as_str())?;
+        }
+        let mut entries = Vec::new();
+
+        for row in stmt.into_cursor() {
+            let row = row?;
+            let id = row.get::<ObjectId, _>("id");
+            let mut entry = Entry {
+                id,
+                title: row.get::<String, _>("title"),
+                status: row.get::<String, _>("status"),
+                author: row.get::<ActorId, _>("author"),
+                timestamp: Timestamp::new(row.get::<i64, _>("timestamp") as u64),
+                tags: Vec::new(),
+                labels: Vec::new(),
+                assignees: Vec::new(),
+            };
+            let mut stmt = self
+                .db
+                .prepare("SELECT name, value FROM attributes WHERE object = ? ORDER BY value")?;
+            stmt.bind(1, &id)?;
+
+            for row in stmt.into_cursor() {
+                let row = row?;
+                let value = row.get::<String, _>("value");
+
+                match row.get::<String, _>("name").as_str() {
+                    "tag" => entry.tags.push(value),
+                    "label" => entry.labels.extend(LabelId::from_str(&value).ok()),
+                    "assignee" => entry.assignees.extend(ActorId::from_str(&value).ok()),
+                    _ => {}
+                }
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Copy the objects of the cache at the given path into this cache.
+    fn copy(&self, path: &Path) -> Result<(), Error> {
+        let mut stmt = self.db.prepare("ATTACH DATABASE ? AS source")?;
+        stmt.bind(1, path.to_string_lossy().as_ref())?;
+        stmt.next()?;
+        drop(stmt);
+
+        let result = transaction(&self.db, |db| {
+            db.execute("INSERT INTO objects SELECT * FROM source.objects")?;
+            db.execute("INSERT INTO attributes SELECT * FROM source.attributes")
+        });
+        self.db.execute("DETACH DATABASE source")?;
+
+        result.map_err(Error::from)
+    }
+
+    /// Keys of the cached objects of the given kind. See [`keys`].
+    fn keys(&self, kind: Kind) -> Result<BTreeMap<ObjectId, String>, Error> {
+        let mut stmt = self
+            .db
+            .prepare("SELECT id, key FROM objects WHERE type = ?")?;
+        stmt.bind(1, kind.as_str())?;
+
+        let mut keys = BTreeMap::new();
+        for row in stmt.into_cursor() {
+            let row = row?;
+            keys.insert(row.get::<ObjectId, _>("id"), row.get::<String, _>("key"));
+        }
+        Ok(keys)
+    }
+}
+
+/// Summary of an object, as it is stored in the cache.
+struct Row {
+    entry: Entry,
+    body: String,
+}
+
+impl Row {
+    fn from_history(
+        kind: Kind,
+        id: ObjectId,
+        history: &cob::History,
+        delegates: &Delegates,
+    ) -> Result<Option<Self>, cob::store::Error> {
+        Ok(match kind {
+            Kind::Issue => Self::issue(id, &Issue::from_history(history, delegates)?.0),
+            Kind::Patch => Self::patch(id, &Patch::from_history(history, delegates)?.0),
+        })
+    }
+
+    fn issue(id: ObjectId, issue: &Issue) -> Option<Self> {
+        let (_, first) = issue.comments().next()?;
+        let author = issue.author()?;
+
+        Some(Self {
+            entry: Entry {
+                id,
+                title: issue.title().to_owned(),
+                status: issue.status().to_string(),
+                author: *author.id(),
+                timestamp: first.timestamp,
+                tags: issue.tags().map(|t| t.name().to_owned()).collect(),
+                labels: issue.labels().copied().collect(),
+                assignees: issue.assigned().copied().collect(),
+            },
+            body: issue
+                .comments()
+                .map(|(_, c)| c.body.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        })
+    }
+
+    fn patch(id: ObjectId, patch: &Patch) -> Option<Self> {
+        patch.revisions().next()?;
+
+        let body = [patch.description().unwrap_or_default()]
+            .into_iter()
+            .chain(patch.revisions().flat_map(|(_, r)| {
+                [r.description().unwrap_or_default()]
+                    .into_iter()
+                    .chain(r.discussion.comments().map(|(_, c)| c.body.as_str()))
+            }))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Some(Self {
+            entry: Entry {
+                id,
+                title: patch.title().to_owned(),
+                status: patch.status().to_string(),
+                author: *patch.author().id(),
+                timestamp: patch.timestamp(),
+                tags: patch.tags.iter().map(|t| t.name().to_owned()).collect(),
+                labels: patch.labels.iter().copied().collect(),
+                assignees: Vec::new(),
+            },
+            body,
+        })
+    }
+}
+
+fn insert(db: &sql::Connection, kind: Kind, key: &str, row: &Row) -> Result<(), sql::Error> {
+    let entry = &row.entry;
+
+    remove(db, &entry.id)?;
+
+    let mut stmt = db.prepare(
+        "INSERT INTO objects (id, type, key, title, status, author, timestamp, body)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+    stmt.bind(1, &entry.id)?;
+    stmt.bind(2, kind.as_str())?;
+    stmt.bind(3, key)?;
+    stmt.bind(4, entry.title.as_str())?;
+    stmt.bind(5, entry.status.as_str())?;
+    stmt.bind(6, &entry.author)?;
+    stmt.bind(7, entry.timestamp.as_secs() as i64)?;
+    stmt.bind(8, row.body.as_str())?;
+    stmt.next()?;
+
+    let attributes = entry
+        .tags
+        .iter()
+        .map(|t| ("tag", t.clone()))
+        .chain(entry.labels.iter().map(|l| ("label", l.to_string())))
+        .chain(entry.assignees.iter().map(|a| ("assignee", a.to_human())));
+
+    for (name, value) in attributes {
+        let mut stmt = db.prepare(
+            "INSERT INTO attributes (object, name, value)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT DO NOTHING",
+        )?;
+        stmt.bind(1, &entry.id)?;
+        stmt.bind(2, name)?;
+        stmt.bind(3, value.as_str())?;
+        stmt.next()?;
+    }
+    Ok(())
+}
+
+fn remove(db: &sql::Connection, id: &ObjectId) -> Result<(), sql::Error> {
+    for query in [
+        "DELETE FROM attributes WHERE object = ?",
+        "DELETE FROM objects WHERE id = ?",
+    ] {
+        let mut stmt = db.prepare(query)?;
+        stmt.bind(1, id)?;
+        stmt.next()?;
+    }
+    Ok(())
+}
+
+/// Keys of the objects of the given type: the canonical identity head, followed by the
+/// history tips of the object across all remotes.
+fn keys(
+    repo: &Repository,
+    typename: &TypeName,
+    identity: &git::Oid,
+) -> Result<BTreeMap<ObjectId, String>, Error> {
+    let mut tips = BTreeMap::<ObjectId, Vec<String>>::new();
+
+    for r in repo
+        .backend
+        .references_glob(&format!("refs/namespaces/*/refs/cobs/{typename}/*"))?
+    {
+        let r = r?;
+        let (Some(name), Some(oid)) = (r.name(), r.target()) else {
+            continue;
+        };
+        let Some(id) = name.rsplit('/').next().and_then(|id| ObjectId::from_str(id).ok()) else {
+            continue;
+        };
+        tips.entry(id).or_default().push(oid.to_string());
+    }
+    Ok(tips
+        .into_iter()
+        .map(|(id, mut oids)| {
+            oids.sort();
+            oids.dedup();
+            (id, format!("{identity} {}", oids.join(" ")))
+        })
+        .collect())
+}
+
+/// Escape the wildcards of a `LIKE` pattern.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::cob::common::Tag;
+    use crate::cob::issue::{CloseReason, Issues, Status};
+    use crate::cob::patch::{MergeTarget, Patches};
+    use crate::crypto::test::signer::MockSigner;
+    use crate::crypto::Signer as _;
+    use crate::test;
+
+    #[test]
+    fn test_cache_update_and_query() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut issues = Issues::open(*signer.public_key(), &project).unwrap();
+        let mut cache = Cache::memory().unwrap();
+        let bug = Tag::new("bug").unwrap();
+
+        let first = issues
+            .create("First issue", "The cache is 100% empty.", &[], &signer)
+            .unwrap()
+            .id;
+        let second = issues
+            .create("Second issue", "Blah blah blah.", &[bug.clone()], &signer)
+            .unwrap()
+            .id;
+
+        assert_eq!(cache.update(&project).unwrap(), 2);
+        assert_eq!(cache.update(&project).unwrap(), 0);
+
+        let ids = |query: &Query| -> Vec<ObjectId> {
+            cache
+                .issues(query)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.id)
+                .collect()
+        };
+        let mut all = ids(&Query::default());
+        all.sort();
+        let mut expected = vec![first, second];
+        expected.sort();
+
+        assert_eq!(all, expected);
+        assert_eq!(
+            ids(&Query {
+                tag: Some(bug.name().to_owned()),
+                ..Query::default()
+            }),
+            vec![second]
+        );
+        assert_eq!(
+            ids(&Query {
+                search: Some("100%".to_owned()),
+                ..Query::default()
+            }),
+            vec![first]
+        );
+        assert_eq!(
+            ids(&Query {
+                sort: Sort::Title,
+                author: Some(*signer.public_key()),
+                ..Query::default()
+            }),
+            vec![first, second]
+        );
+        assert!(cache.patches(&Query::default()).unwrap().is_empty());
+
+        let mut issue = issues.get_mut(&first).unwrap();
+        issue
+            .lifecycle(
+                Status::Closed {
+                    reason: CloseReason::Solved,
+                },
+                &signer,
+            )
+            .unwrap();
+        issue.assign([*signer.public_key()], [], &signer).unwrap();
+
+        assert_eq!(cache.update(&project).unwrap(), 1);
+        assert_eq!(
+            ids(&Query {
+                status: Some("open".to_owned()),
+                ..Query::default()
+            }),
+            vec![second]
+        );
+        let entries = cache
+            .issues(&Query {
+                assignee: Some(*signer.public_key()),
+                ..Query::default()
+            })
+            .unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, first);
+        assert_eq!(entries[0].status, "closed");
+        assert_eq!(entries[0].assignees, vec![*signer.public_key()]);
+
+        issues.remove(&second).unwrap();
+        assert_eq!(cache.update(&project).unwrap(), 1);
+        assert_eq!(ids(&Query::default()), vec![first]);
+        assert_eq!(cache.rebuild(&project).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_cache_remove_stale() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut patches = Patches::open(*signer.public_key(), &project).unwrap();
+        let mut cache = Cache::memory().unwrap();
+        let (_, base) = project.canonical_head().unwrap();
+        let mut patch = patches
+            .create(
+                "My patch",
+                "",
+                MergeTarget::Delegates,
+                base,
+                base,
+                &[],
+                &signer,
+            )
+            .unwrap();
+
+        assert_eq!(cache.update(&project).unwrap(), 1);
+        assert_eq!(cache.patches(&Query::default()).unwrap().len(), 1);
+
+        // Once its only revision is redacted, the patch can't be summarized anymore.
+        let (revision, _) = patch.latest().unwrap();
+        let revision = *revision;
+        patch
+            .apply("Redact", patch::Action::Redact { revision }, &signer)
+            .unwrap();
+
+        assert_eq!(cache.update(&project).unwrap(), 1);
+        assert!(cache.patches(&Query::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_cache_reader() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut issues = Issues::open(*signer.public_key(), &project).unwrap();
+
+        issues
+            .create("First issue", "Blah blah blah.", &[], &signer)
+            .unwrap();
+        Cache::open(&project).unwrap().update(&project).unwrap();
+        assert!(Cache::reader(&project)
+            .unwrap()
+            .is_current(&project)
+            .unwrap());
+
+        // Objects that changed since the cache was updated are replayed on read, without
+        // writing to the cache.
+        issues
+            .create("Second issue", "Blah blah blah.", &[], &signer)
+            .unwrap();
+        assert_eq!(
+            Cache::reader(&project)
+                .unwrap()
+                .issues(&Query::default())
+                .unwrap()
+                .len(),
+            2
+        );
+        let cache = Cache::open(&project).unwrap();
+        assert!(!cache.is_current(&project).unwrap());
+        assert_eq!(cache.issues(&Query::default()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_cache_identity_change() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut issues = Issues::open(*signer.public_key(), &project).unwrap();
+        let mut cache = Cache::memory().unwrap();
+
+        issues
+            .create("First issue", "Blah blah blah.", &[], &signer)
+            .unwrap();
+        assert_eq!(cache.update(&project).unwrap(), 1);
+        assert_eq!(cache.update(&project).unwrap(), 0);
+
+        // When the delegates change, objects have to be authorized again.
+        let mut doc = project.project_of(signer.public_key()).unwrap();
+        assert!(doc.delegate("bob".to_owned(), *MockSigner::default().public_key()));
+        let (_, sig) = doc.sign(&signer).unwrap();
+        doc.update(
+            signer.public_key(),
+            "Add bob",
+            &[(signer.public_key(), sig)],
+            &project,
+        )
+        .unwrap();
+
+        assert_eq!(cache.update(&project).unwrap(), 1);
+        assert_eq!(cache.update(&project).unwrap(), 0);
+    }
+}
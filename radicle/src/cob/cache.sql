@@ -0,0 +1,38 @@
+--
+-- Collaborative object cache SQL schema.
+--
+create table if not exists "objects" (
+  -- Object ID.
+  "id"                 text      primary key not null,
+  -- Object type, eg. `issue` or `patch`.
+  "type"               text      not null,
+  -- Canonical identity head, followed by the history tips the object was cached at, sorted.
+  -- Separated by spaces. The object is replayed when this changes.
+  "key"                text      not null,
+  -- Object title.
+  "title"              text      not null,
+  -- Object status, eg. `open` or `proposed`.
+  "status"             text      not null,
+  -- Object author.
+  "author"             text      not null,
+  -- When the object was created.
+  "timestamp"          integer   not null,
+  -- Description and comments, used for text search.
+  "body"               text      not null
+  --
+) strict;
+
+create table if not exists "attributes" (
+  -- Object ID.
+  "object"             text      not null references "objects" ("id"),
+  -- Attribute name, eg. `tag`, `label` or `assignee`.
+  "name"               text      not null,
+  -- Attribute value.
+  "value"              text      not null,
+  --
+  unique ("object", "name", "value")
+  --
+) strict;
+
+create index if not exists "objects_type" on "objects" ("type");
+create index if not exists "attributes_value" on "attributes" ("name", "value");
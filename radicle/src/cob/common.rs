@@ -1,8 +1,13 @@
+use std::collections::{BTreeSet, HashMap};
 use std::fmt;
 use std::str::FromStr;
 
+use radicle_crdt::Semilattice;
 use serde::{Deserialize, Serialize};
 
+use crate::cob::{ActorId, History};
+use crate::git;
+use crate::identity::project::Identity;
 use crate::prelude::*;
 
 pub use radicle_crdt::clock::Physical as Timestamp;
@@ -23,6 +28,142 @@ impl Author {
     }
 }
 
+/// Delegates and revoked keys of a version of the project identity.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Delegation {
+    pub delegates: BTreeSet<ActorId>,
+    pub revoked: BTreeSet<ActorId>,
+}
+
+/// Project delegates, against which privileged actions are authorized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Delegates {
+    /// The same delegates, whichever version of the project identity an action was made
+    /// against.
+    Fixed(BTreeSet<ActorId>),
+    /// The delegation of each version of the project identity, keyed by document blob.
+    /// Actions made against a version that isn't part of the identity history are
+    /// unprivileged.
+    Versioned(HashMap<git::Oid, Delegation>),
+}
+
+impl Default for Delegates {
+    fn default() -> Self {
+        Self::Fixed(BTreeSet::default())
+    }
+}
+
+impl Delegates {
+    /// Check whether the actor was a delegate in the given identity version.
+    pub fn is_delegate(&self, actor: &ActorId, identity: &git::Oid) -> bool {
+        match self {
+            Self::Fixed(delegates) => delegates.contains(actor),
+            Self::Versioned(versions) => versions
+                .get(identity)
+                .map_or(false, |v| v.delegates.contains(actor)),
+        }
+    }
+
+    /// Check whether the actor was revoked in or before the given identity version.
+    pub fn is_revoked(&self, actor: &ActorId, identity: &git::Oid) -> bool {
+        match self {
+            Self::Fixed(_) => false,
+            Self::Versioned(versions) => versions
+                .get(identity)
+                .map_or(false, |v| v.revoked.contains(actor)),
+        }
+    }
+}
+
+impl<I> From<&Identity<I>> for Delegates {
+    fn from(identity: &Identity<I>) -> Self {
+        Self::Versioned(
+            identity
+                .versions
+                .iter()
+                .map(|(blob, doc)| {
+                    let delegation = Delegation {
+                        delegates: doc.delegates.iter().map(|d| *d.id).collect(),
+                        revoked: doc.revoked.iter().map(|key| **key).collect(),
+                    };
+                    (*blob, delegation)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Semilattice for Delegates {
+    fn merge(&mut self, other: Self) {
+        match (self, other) {
+            (Self::Fixed(a), Self::Fixed(b)) => a.extend(b),
+            (Self::Versioned(a), Self::Versioned(b)) => a.extend(b),
+            // Versioned delegates are more specific than fixed ones.
+            (this @ Self::Fixed(_), other @ Self::Versioned(_)) => *this = other,
+            (Self::Versioned(_), Self::Fixed(_)) => {}
+        }
+    }
+}
+
+/// Who may carry out privileged actions on an object: the author of the object, and the
+/// delegates of the project it belongs to.
+///
+/// Actions are authorized against the delegates of the project identity version they were
+/// made against, so that changes to the delegates only affect later actions. Keys that were
+/// revoked in or before that version may not act at all.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Authorization {
+    /// Author of the object, ie. of its root change.
+    author: Option<ActorId>,
+    /// Delegates of the project.
+    delegates: Delegates,
+}
+
+impl Authorization {
+    pub fn new(author: ActorId, delegates: impl IntoIterator<Item = ActorId>) -> Self {
+        Self {
+            author: Some(author),
+            delegates: Delegates::Fixed(delegates.into_iter().collect()),
+        }
+    }
+
+    /// Authorization of the object with the given history, in a project with the given
+    /// delegates.
+    pub fn from_history(history: &History, delegates: &Delegates) -> Self {
+        Self {
+            author: Some(*history.root().entry.actor()),
+            delegates: delegates.clone(),
+        }
+    }
+
+    pub fn author(&self) -> Option<&ActorId> {
+        self.author.as_ref()
+    }
+
+    pub fn is_author(&self, actor: &ActorId) -> bool {
+        self.author.as_ref() == Some(actor)
+    }
+
+    pub fn is_delegate(&self, actor: &ActorId, identity: &git::Oid) -> bool {
+        self.delegates.is_delegate(actor, identity)
+    }
+
+    pub fn is_author_or_delegate(&self, actor: &ActorId, identity: &git::Oid) -> bool {
+        self.is_author(actor) || self.is_delegate(actor, identity)
+    }
+
+    pub fn is_revoked(&self, actor: &ActorId, identity: &git::Oid) -> bool {
+        self.delegates.is_revoked(actor, identity)
+    }
+}
+
+impl Semilattice for Authorization {
+    fn merge(&mut self, other: Self) {
+        self.author = self.author.max(other.author);
+        self.delegates.merge(other.delegates);
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum ReactionError {
     #[error("invalid reaction")]
@@ -99,9 +240,17 @@ impl From<Tag> for String {
 }
 
 /// RGB color.
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 pub struct Color(u32);
 
+impl Color {
+    /// Get the red, green and blue components of the color.
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        let [_, r, g, b] = self.0.to_be_bytes();
+        (r, g, b)
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum ColorConversionError {
     #[error("invalid format: expect '#rrggbb'")]
@@ -164,6 +313,8 @@ mod test {
         assert_eq!(serde_json::to_string(&c).unwrap(), "\"#ffccaa\"".to_owned());
         assert_eq!(serde_json::from_str::<'_, Color>("\"#ffccaa\"").unwrap(), c);
 
+        assert_eq!(c.rgb(), (0xff, 0xcc, 0xaa));
+
         let c = Color::from_str("#0000aa").unwrap();
         assert_eq!(c.to_string(), "#0000aa".to_owned());
 
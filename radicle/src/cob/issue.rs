@@ -8,11 +8,14 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::cob;
-use crate::cob::common::{Author, Reaction, Tag};
+use crate::cob::common::{Author, Authorization, Delegates, Reaction, Tag};
+use crate::cob::label::LabelId;
 use crate::cob::thread;
 use crate::cob::thread::{CommentId, Thread};
-use crate::cob::{store, ObjectId, OpId, TypeName};
+use crate::cob::xref;
+use crate::cob::{store, ActorId, ObjectId, OpId, TypeName};
 use crate::crypto::{PublicKey, Signer};
+use crate::git;
 use crate::storage::git as storage;
 
 /// Issue operation.
@@ -30,6 +33,10 @@ pub type IssueId = ObjectId;
 pub enum Error {
     #[error("apply failed")]
     Apply,
+    #[error("issue has no description")]
+    NoDescription,
+    #[error("{0} is not authorized to perform this action")]
+    Unauthorized(ActorId),
     #[error("store: {0}")]
     Store(#[from] store::Error),
 }
@@ -77,14 +84,20 @@ pub struct Issue {
     title: LWWReg<Max<String>, clock::Lamport>,
     status: LWWReg<Max<Status>, clock::Lamport>,
     tags: LWWSet<Tag>,
+    labels: LWWSet<LabelId>,
+    assignees: LWWSet<ActorId>,
     thread: Thread,
+    auth: Authorization,
 }
 
 impl Semilattice for Issue {
     fn merge(&mut self, other: Self) {
         self.title.merge(other.title);
         self.status.merge(other.status);
+        self.labels.merge(other.labels);
+        self.assignees.merge(other.assignees);
         self.thread.merge(other.thread);
+        self.auth.merge(other.auth);
     }
 }
 
@@ -94,7 +107,10 @@ impl Default for Issue {
             title: Max::from(String::default()).into(),
             status: Max::from(Status::default()).into(),
             tags: LWWSet::default(),
+            labels: LWWSet::default(),
+            assignees: LWWSet::default(),
             thread: Thread::default(),
+            auth: Authorization::default(),
         }
     }
 }
@@ -108,12 +124,25 @@ impl store::FromHistory for Issue {
 
     fn from_history(
         history: &radicle_cob::History,
+        delegates: &Delegates,
     ) -> Result<(Self, clock::Lamport), store::Error> {
-        let obj = history.traverse(Self::default(), |mut acc, entry| {
+        let init = Self {
+            auth: Authorization::from_history(history, delegates),
+            ..Self::default()
+        };
+        let obj = history.traverse(init, |mut acc, entry| {
             if let Ok(op) = Op::try_from(entry) {
-                if let Err(err) = acc.apply(op) {
-                    log::warn!("Error applying op to issue state: {err}");
-                    return ControlFlow::Break(acc);
+                match acc.apply(op) {
+                    Ok(()) => {}
+                    // Nb. Unauthorized ops are skipped rather than pruned, so that they
+                    // can't prevent later, authorized ops from being applied.
+                    Err(Error::Unauthorized(actor)) => {
+                        log::debug!("Skipping unauthorized issue op by {actor}");
+                    }
+                    Err(err) => {
+                        log::warn!("Error applying op to issue state: {err}");
+                        return ControlFlow::Break(acc);
+                    }
                 }
             } else {
                 return ControlFlow::Break(acc);
@@ -138,6 +167,14 @@ impl Issue {
         self.tags.iter()
     }
 
+    pub fn labels(&self) -> impl Iterator<Item = &LabelId> {
+        self.labels.iter()
+    }
+
+    pub fn assigned(&self) -> impl Iterator<Item = &ActorId> {
+        self.assignees.iter()
+    }
+
     pub fn author(&self) -> Option<Author> {
         self.thread
             .comments()
@@ -153,7 +190,32 @@ impl Issue {
         self.thread.comments().map(|(id, comment)| (id, comment))
     }
 
+    /// Authorization of privileged actions on the issue.
+    pub fn authorization(&self) -> &Authorization {
+        &self.auth
+    }
+
+    /// Whether the given actor is allowed to carry out the given action. Changing the
+    /// title, status, tags, labels or assignees of an issue is reserved to its author and
+    /// to the project delegates.
+    pub fn authorized(&self, action: &Action, actor: &ActorId, identity: &git::Oid) -> bool {
+        if self.auth.is_revoked(actor, identity) {
+            return false;
+        }
+        match action {
+            Action::Title { .. }
+            | Action::Lifecycle { .. }
+            | Action::Tag { .. }
+            | Action::Label { .. }
+            | Action::Assign { .. } => self.auth.is_author_or_delegate(actor, identity),
+            Action::Thread { .. } => true,
+        }
+    }
+
     pub fn apply(&mut self, op: Op) -> Result<(), Error> {
+        if !self.authorized(&op.action, &op.author, &op.identity) {
+            return Err(Error::Unauthorized(op.author));
+        }
         match op.action {
             Action::Title { title } => {
                 self.title.set(title, op.clock);
@@ -169,12 +231,29 @@ impl Issue {
                     self.tags.remove(tag, op.clock);
                 }
             }
+            Action::Label { add, remove } => {
+                for label in add {
+                    self.labels.insert(label, op.clock);
+                }
+                for label in remove {
+                    self.labels.remove(label, op.clock);
+                }
+            }
+            Action::Assign { add, remove } => {
+                for assignee in add {
+                    self.assignees.insert(assignee, op.clock);
+                }
+                for assignee in remove {
+                    self.assignees.remove(assignee, op.clock);
+                }
+            }
             Action::Thread { action } => {
                 self.thread.apply([cob::Op {
                     action,
                     author: op.author,
                     clock: op.clock,
                     timestamp: op.timestamp,
+                    identity: op.identity,
                 }]);
             }
         }
@@ -191,7 +270,7 @@ impl Deref for Issue {
 }
 
 pub struct IssueMut<'a, 'g> {
-    id: ObjectId,
+    pub id: ObjectId,
     clock: clock::Lamport,
     issue: Issue,
     store: &'g mut Issues<'a>,
@@ -209,6 +288,54 @@ impl<'a, 'g> IssueMut<'a, 'g> {
         self.apply("Lifecycle", action, signer)
     }
 
+    /// Assign or unassign people to an issue.
+    pub fn assign<G: Signer>(
+        &mut self,
+        add: impl IntoIterator<Item = ActorId>,
+        remove: impl IntoIterator<Item = ActorId>,
+        signer: &G,
+    ) -> Result<OpId, Error> {
+        let add = add.into_iter().collect::<Vec<_>>();
+        let remove = remove.into_iter().collect::<Vec<_>>();
+        let action = Action::Assign { add, remove };
+
+        self.apply("Assign", action, signer)
+    }
+
+    /// Edit the issue title.
+    pub fn edit<G: Signer>(&mut self, title: impl Into<String>, signer: &G) -> Result<OpId, Error> {
+        let action = Action::Title {
+            title: title.into(),
+        };
+        self.apply("Edit", action, signer)
+    }
+
+    /// Edit the issue description, ie. the body of its first comment.
+    pub fn edit_description<G: Signer>(
+        &mut self,
+        body: impl Into<String>,
+        signer: &G,
+    ) -> Result<OpId, Error> {
+        let (id, _) = self.thread.comments().next().ok_or(Error::NoDescription)?;
+        let id = *id;
+
+        self.edit_comment(id, body, signer)
+    }
+
+    /// Edit an issue comment. The edit history of the comment is kept.
+    pub fn edit_comment<G: Signer>(
+        &mut self,
+        id: CommentId,
+        body: impl Into<String>,
+        signer: &G,
+    ) -> Result<OpId, Error> {
+        let action = Action::from(thread::Action::Edit {
+            id,
+            body: body.into(),
+        });
+        self.apply("Edit comment", action, signer)
+    }
+
     /// Comment on an issue.
     pub fn comment<G: Signer, S: Into<String>>(
         &mut self,
@@ -237,6 +364,20 @@ impl<'a, 'g> IssueMut<'a, 'g> {
         self.apply("Tag", action, signer)
     }
 
+    /// Label an issue.
+    pub fn label<G: Signer>(
+        &mut self,
+        add: impl IntoIterator<Item = LabelId>,
+        remove: impl IntoIterator<Item = LabelId>,
+        signer: &G,
+    ) -> Result<OpId, Error> {
+        let add = add.into_iter().collect::<Vec<_>>();
+        let remove = remove.into_iter().collect::<Vec<_>>();
+        let action = Action::Label { add, remove };
+
+        self.apply("Label", action, signer)
+    }
+
     /// Reply to on an issue comment.
     pub fn reply<G: Signer, S: Into<String>>(
         &mut self,
@@ -279,6 +420,12 @@ impl<'a, 'g> IssueMut<'a, 'g> {
         action: Action,
         signer: &G,
     ) -> Result<OpId, Error> {
+        if !self
+            .issue
+            .authorized(&action, signer.public_key(), self.store.identity())
+        {
+            return Err(Error::Unauthorized(*signer.public_key()));
+        }
         let cob = self
             .store
             .update(self.id, msg, action.clone(), signer)
@@ -290,6 +437,7 @@ impl<'a, 'g> IssueMut<'a, 'g> {
             author: *signer.public_key(),
             clock,
             timestamp,
+            identity: *self.store.identity(),
         };
         self.issue.apply(op)?;
 
@@ -377,16 +525,43 @@ impl<'a> Issues<'a> {
     pub fn remove(&self, id: &ObjectId) -> Result<(), store::Error> {
         self.raw.remove(id)
     }
+
+    /// Get the issues, patches and commits that mention an issue.
+    pub fn backlinks(&self, id: &ObjectId) -> Result<Vec<xref::Reference>, xref::Error> {
+        let index = xref::Index::refresh(self.raw.as_ref())?;
+
+        Ok(index
+            .backlinks(&xref::Reference::Issue(*id))
+            .copied()
+            .collect())
+    }
 }
 
 /// Issue operation.
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
 pub enum Action {
-    Title { title: String },
-    Lifecycle { status: Status },
-    Tag { add: Vec<Tag>, remove: Vec<Tag> },
-    Thread { action: thread::Action },
+    Title {
+        title: String,
+    },
+    Lifecycle {
+        status: Status,
+    },
+    Tag {
+        add: Vec<Tag>,
+        remove: Vec<Tag>,
+    },
+    Label {
+        add: Vec<LabelId>,
+        remove: Vec<LabelId>,
+    },
+    Assign {
+        add: Vec<ActorId>,
+        remove: Vec<ActorId>,
+    },
+    Thread {
+        action: thread::Action,
+    },
 }
 
 impl From<thread::Action> for Action {
@@ -400,7 +575,9 @@ mod test {
     use pretty_assertions::assert_eq;
 
     use super::*;
+    use crate::cob::label::{self, Labels};
     use crate::cob::Reaction;
+    use crate::crypto::test::signer::MockSigner;
     use crate::test;
 
     #[test]
@@ -532,6 +709,87 @@ mod test {
         assert!(tags.contains(&wontfix_tag));
     }
 
+    #[test]
+    fn test_issue_label() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut labels = Labels::open(*signer.public_key(), &project).unwrap();
+        let bug = labels
+            .create("bug", "", *label::DEFAULT_COLOR, &signer)
+            .unwrap()
+            .id;
+        let wontfix = labels
+            .create("wontfix", "", *label::DEFAULT_COLOR, &signer)
+            .unwrap()
+            .id;
+
+        let mut issues = Issues::open(*signer.public_key(), &project).unwrap();
+        let mut issue = issues
+            .create("My first issue", "Blah blah blah.", &[], &signer)
+            .unwrap();
+
+        issue.label([bug, wontfix], [], &signer).unwrap();
+        issue.label([], [wontfix], &signer).unwrap();
+
+        let id = issue.id;
+        let issue = issues.get(&id).unwrap().unwrap();
+        let labels = issue.labels().copied().collect::<Vec<_>>();
+
+        assert_eq!(labels, vec![bug]);
+    }
+
+    #[test]
+    fn test_issue_assign() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut issues = Issues::open(*signer.public_key(), &project).unwrap();
+        let mut issue = issues
+            .create("My first issue", "Blah blah blah.", &[], &signer)
+            .unwrap();
+
+        let alice = ActorId::from([1; 32]);
+        let bob = ActorId::from([2; 32]);
+
+        issue.assign([alice, bob], [], &signer).unwrap();
+        issue.assign([], [alice], &signer).unwrap();
+
+        let id = issue.id;
+        let issue = issues.get(&id).unwrap().unwrap();
+        let assigned = issue.assigned().copied().collect::<Vec<_>>();
+
+        assert_eq!(assigned, vec![bob]);
+    }
+
+    #[test]
+    fn test_issue_edit() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut issues = Issues::open(*signer.public_key(), &project).unwrap();
+        let mut issue = issues
+            .create("My first issue", "Blah blah blah.", &[], &signer)
+            .unwrap();
+        let comment = issue.comment("Ho ho hi.", &signer).unwrap();
+
+        issue.edit("My first edited issue", &signer).unwrap();
+        issue.edit_description("Blah blah.", &signer).unwrap();
+        issue.edit_comment(comment, "Ho ho ho.", &signer).unwrap();
+
+        let id = issue.id;
+        let issue = issues.get(&id).unwrap().unwrap();
+        let (_, c1) = issue.comments().nth(1).unwrap();
+
+        assert_eq!(issue.title(), "My first edited issue");
+        assert_eq!(issue.description(), Some("Blah blah."));
+        assert_eq!(c1.body, "Ho ho ho.");
+        assert_eq!(
+            issue
+                .edits(&comment)
+                .map(|e| e.body.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Ho ho hi.", "Ho ho ho."]
+        );
+    }
+
     #[test]
     fn test_issue_comment() {
         let tmp = tempfile::tempdir().unwrap();
@@ -598,4 +856,58 @@ mod test {
         issues.iter().find(|i| i.title() == "Second").unwrap();
         issues.iter().find(|i| i.title() == "Third").unwrap();
     }
+
+    #[test]
+    fn test_issue_authorization() {
+        let mut alice = cob::Actor::<_, Action>::new(MockSigner::default());
+        let mut bob = cob::Actor::<_, Action>::new(MockSigner::default());
+        let mut eve = cob::Actor::<_, Action>::new(MockSigner::default());
+        let mut issue = Issue {
+            auth: Authorization::new(*alice.signer.public_key(), [*bob.signer.public_key()]),
+            ..Issue::default()
+        };
+        let closed = Status::Closed {
+            reason: CloseReason::Other,
+        };
+
+        issue
+            .apply(alice.op(Action::Title {
+                title: "Alice's issue".to_owned(),
+            }))
+            .unwrap();
+        issue
+            .apply(eve.op(Action::Title {
+                title: "Eve's issue".to_owned(),
+            }))
+            .unwrap_err();
+        issue
+            .apply(eve.op(Action::Lifecycle { status: closed }))
+            .unwrap_err();
+        issue
+            .apply(eve.op(Action::Thread {
+                action: thread::Action::Comment {
+                    body: "Hi Alice".to_owned(),
+                    reply_to: None,
+                },
+            }))
+            .unwrap();
+
+        assert_eq!(issue.title(), "Alice's issue");
+        assert_eq!(*issue.status(), Status::Open);
+        assert_eq!(issue.comments().count(), 1);
+
+        // Delegates may close issues they didn't author.
+        issue
+            .apply(bob.op(Action::Thread {
+                action: thread::Action::Comment {
+                    body: "Closing as duplicate".to_owned(),
+                    reply_to: None,
+                },
+            }))
+            .unwrap();
+        issue
+            .apply(bob.op(Action::Lifecycle { status: closed }))
+            .unwrap();
+        assert_eq!(*issue.status(), closed);
+    }
 }
@@ -0,0 +1,380 @@
+use std::ops::{ControlFlow, Deref};
+use std::str::FromStr;
+
+use once_cell::sync::Lazy;
+use radicle_crdt::clock;
+use radicle_crdt::{LWWReg, Max, Semilattice};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::cob::common::{Authorization, Color, Delegates};
+use crate::cob::{store, ActorId, ObjectId, OpId, TypeName};
+use crate::crypto::{PublicKey, Signer};
+use crate::git;
+use crate::storage::git as storage;
+
+/// Label operation.
+pub type Op = crate::cob::Op<Action>;
+
+/// Type name of a label.
+pub static TYPENAME: Lazy<TypeName> =
+    Lazy::new(|| FromStr::from_str("xyz.radicle.label").expect("type name is valid"));
+
+/// Identifier for a label.
+pub type LabelId = ObjectId;
+
+/// Default label color.
+pub static DEFAULT_COLOR: Lazy<Color> =
+    Lazy::new(|| Color::from_str("#808080").expect("color is valid"));
+
+/// Error updating or creating labels.
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("invalid label name '{0}'")]
+    InvalidName(String),
+    #[error("{0} is not authorized to perform this action")]
+    Unauthorized(ActorId),
+    #[error("store: {0}")]
+    Store(#[from] store::Error),
+}
+
+/// Label state. Accumulates [`Action`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    name: LWWReg<Max<String>, clock::Lamport>,
+    description: LWWReg<Max<String>, clock::Lamport>,
+    color: LWWReg<Max<Color>, clock::Lamport>,
+    auth: Authorization,
+}
+
+impl Semilattice for Label {
+    fn merge(&mut self, other: Self) {
+        self.name.merge(other.name);
+        self.description.merge(other.description);
+        self.color.merge(other.color);
+        self.auth.merge(other.auth);
+    }
+}
+
+impl Default for Label {
+    fn default() -> Self {
+        Self {
+            name: Max::from(String::default()).into(),
+            description: Max::from(String::default()).into(),
+            color: Max::from(*DEFAULT_COLOR).into(),
+            auth: Authorization::default(),
+        }
+    }
+}
+
+impl store::FromHistory for Label {
+    type Action = Action;
+
+    fn type_name() -> &'static TypeName {
+        &*TYPENAME
+    }
+
+    fn from_history(
+        history: &radicle_cob::History,
+        delegates: &Delegates,
+    ) -> Result<(Self, clock::Lamport), store::Error> {
+        let init = Self {
+            auth: Authorization::from_history(history, delegates),
+            ..Self::default()
+        };
+        let obj = history.traverse(init, |mut acc, entry| {
+            if let Ok(op) = Op::try_from(entry) {
+                if let Err(err) = acc.apply(op) {
+                    log::debug!("Skipping label op: {err}");
+                }
+            } else {
+                return ControlFlow::Break(acc);
+            }
+            ControlFlow::Continue(acc)
+        });
+
+        Ok((obj, history.clock().into()))
+    }
+}
+
+impl Label {
+    pub fn name(&self) -> &str {
+        self.name.get().as_str()
+    }
+
+    pub fn description(&self) -> &str {
+        self.description.get().as_str()
+    }
+
+    pub fn color(&self) -> &Color {
+        self.color.get()
+    }
+
+    /// Whether the given actor is allowed to carry out the given action. Labels can only be
+    /// edited by their author and by the project delegates.
+    pub fn authorized(&self, action: &Action, actor: &ActorId, identity: &git::Oid) -> bool {
+        if self.auth.is_revoked(actor, identity) {
+            return false;
+        }
+        match action {
+            Action::Edit { .. } => self.auth.is_author_or_delegate(actor, identity),
+        }
+    }
+
+    pub fn apply(&mut self, op: Op) -> Result<(), Error> {
+        if !self.authorized(&op.action, &op.author, &op.identity) {
+            return Err(Error::Unauthorized(op.author));
+        }
+        match op.action {
+            Action::Edit {
+                name,
+                description,
+                color,
+            } => {
+                self.name.set(name, op.clock);
+                self.description.set(description, op.clock);
+                self.color.set(color, op.clock);
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct LabelMut<'a, 'g> {
+    pub id: ObjectId,
+
+    clock: clock::Lamport,
+    label: Label,
+    store: &'g mut Labels<'a>,
+}
+
+impl<'a, 'g> LabelMut<'a, 'g> {
+    /// Get the internal logical clock.
+    pub fn clock(&self) -> &clock::Lamport {
+        &self.clock
+    }
+
+    /// Edit a label.
+    pub fn edit<G: Signer>(
+        &mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        color: Color,
+        signer: &G,
+    ) -> Result<OpId, Error> {
+        let action = Action::Edit {
+            name: validate(name.into())?,
+            description: description.into(),
+            color,
+        };
+        self.apply("Edit", action, signer)
+    }
+
+    /// Apply an op to the label.
+    pub fn apply<G: Signer>(
+        &mut self,
+        msg: &'static str,
+        action: Action,
+        signer: &G,
+    ) -> Result<OpId, Error> {
+        if !self
+            .label
+            .authorized(&action, signer.public_key(), self.store.identity())
+        {
+            return Err(Error::Unauthorized(*signer.public_key()));
+        }
+        let cob = self
+            .store
+            .update(self.id, msg, action.clone(), signer)
+            .map_err(Error::Store)?;
+        let clock = cob.history().clock().into();
+        let timestamp = cob.history().timestamp().into();
+        let op = Op {
+            action,
+            author: *signer.public_key(),
+            clock,
+            timestamp,
+            identity: *self.store.identity(),
+        };
+        self.label.apply(op)?;
+
+        Ok((clock, *signer.public_key()))
+    }
+}
+
+impl<'a, 'g> Deref for LabelMut<'a, 'g> {
+    type Target = Label;
+
+    fn deref(&self) -> &Self::Target {
+        &self.label
+    }
+}
+
+pub struct Labels<'a> {
+    raw: store::Store<'a, Label>,
+}
+
+impl<'a> Deref for Labels<'a> {
+    type Target = store::Store<'a, Label>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.raw
+    }
+}
+
+impl<'a> Labels<'a> {
+    /// Open a labels store.
+    pub fn open(
+        whoami: PublicKey,
+        repository: &'a storage::Repository,
+    ) -> Result<Self, store::Error> {
+        let raw = store::Store::open(whoami, repository)?;
+
+        Ok(Self { raw })
+    }
+
+    /// Get a label.
+    pub fn get(&self, id: &ObjectId) -> Result<Option<Label>, store::Error> {
+        self.raw.get(id).map(|r| r.map(|(l, _clock)| l))
+    }
+
+    /// Get a label mutably.
+    pub fn get_mut<'g>(&'g mut self, id: &ObjectId) -> Result<LabelMut<'a, 'g>, store::Error> {
+        let (label, clock) = self
+            .raw
+            .get(id)?
+            .ok_or_else(move || store::Error::NotFound(TYPENAME.clone(), *id))?;
+
+        Ok(LabelMut {
+            id: *id,
+            clock,
+            label,
+            store: self,
+        })
+    }
+
+    /// Find a label by name.
+    pub fn find(&self, name: &str) -> Result<Option<(LabelId, Label)>, store::Error> {
+        for result in self.raw.all()? {
+            let (id, label, _) = result?;
+
+            if label.name() == name.trim() {
+                return Ok(Some((id, label)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Create a new label.
+    pub fn create<'g, G: Signer>(
+        &'g mut self,
+        name: impl Into<String>,
+        description: impl Into<String>,
+        color: Color,
+        signer: &G,
+    ) -> Result<LabelMut<'a, 'g>, Error> {
+        let action = Action::Edit {
+            name: validate(name.into())?,
+            description: description.into(),
+            color,
+        };
+        let (id, label, clock) = self.raw.create("Create label", action, signer)?;
+
+        Ok(LabelMut {
+            id,
+            clock,
+            label,
+            store: self,
+        })
+    }
+
+    /// Remove a label.
+    pub fn remove(&self, id: &ObjectId) -> Result<(), store::Error> {
+        self.raw.remove(id)
+    }
+}
+
+/// Label operation.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Action {
+    Edit {
+        name: String,
+        description: String,
+        color: Color,
+    },
+}
+
+/// Validate a label name. Label names can't be empty or contain control characters, eg.
+/// newlines. Surrounding whitespace is removed.
+fn validate(name: String) -> Result<String, Error> {
+    let trimmed = name.trim();
+
+    if trimmed.is_empty() || trimmed.contains(char::is_control) {
+        return Err(Error::InvalidName(name));
+    }
+    Ok(trimmed.to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::test;
+
+    #[test]
+    fn test_label_create_and_get() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut labels = Labels::open(*signer.public_key(), &project).unwrap();
+        let label = labels
+            .create(
+                "bug",
+                "Something that doesn't work",
+                Color::from_str("#ff0000").unwrap(),
+                &signer,
+            )
+            .unwrap();
+        let (id, created) = (label.id, label.label);
+        let label = labels.get(&id).unwrap().unwrap();
+
+        assert_eq!(created, label);
+        assert_eq!(label.name(), "bug");
+        assert_eq!(label.description(), "Something that doesn't work");
+        assert_eq!(label.color().to_string(), "#ff0000");
+        assert_eq!(labels.find("bug").unwrap().map(|(i, _)| i), Some(id));
+        assert!(labels.find("feature").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_label_edit() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut labels = Labels::open(*signer.public_key(), &project).unwrap();
+        let mut label = labels.create("bug", "", *DEFAULT_COLOR, &signer).unwrap();
+
+        label
+            .edit(
+                "regression",
+                "Something that used to work",
+                Color::from_str("#00ff00").unwrap(),
+                &signer,
+            )
+            .unwrap();
+        labels
+            .create(" good first issue ", "", *DEFAULT_COLOR, &signer)
+            .unwrap();
+        labels
+            .create("  ", "", *DEFAULT_COLOR, &signer)
+            .unwrap_err();
+        labels
+            .create("good\nfirst issue", "", *DEFAULT_COLOR, &signer)
+            .unwrap_err();
+
+        let label = labels.find("regression").unwrap().unwrap().1;
+        assert_eq!(label.description(), "Something that used to work");
+        assert_eq!(label.color().to_string(), "#00ff00");
+        assert!(labels.find("good first issue").unwrap().is_some());
+    }
+}
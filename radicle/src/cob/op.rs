@@ -7,6 +7,8 @@ use radicle_crdt::clock;
 use radicle_crdt::clock::Lamport;
 use radicle_crypto::{PublicKey, Signer};
 
+use crate::git;
+
 /// Identifies an [`Op`].
 pub type OpId = (Lamport, ActorId);
 /// The author of an [`Op`].
@@ -33,6 +35,9 @@ pub struct Op<A> {
     pub clock: Lamport,
     /// Timestamp of this operation.
     pub timestamp: clock::Physical,
+    /// The version of the project identity document this operation was made against. The
+    /// operation is authorized against the delegates of that version.
+    pub identity: git::Oid,
 }
 
 impl<'a: 'de, 'de, A: serde::Deserialize<'de>> TryFrom<&'a EntryWithClock> for Op<A> {
@@ -46,6 +51,7 @@ impl<'a: 'de, 'de, A: serde::Deserialize<'de>> TryFrom<&'a EntryWithClock> for O
             author: *entry.actor(),
             clock: entry.clock().into(),
             timestamp: entry.timestamp().into(),
+            identity: entry.resource(),
         })
     }
 }
@@ -106,6 +112,7 @@ impl<G: Signer, A: Clone> Actor<G, A> {
             author,
             clock,
             timestamp,
+            identity: git::raw::Oid::zero().into(),
         };
         self.ops.insert((self.clock, author), op.clone());
         self.clock.tick();
@@ -1,8 +1,10 @@
 #![allow(clippy::too_many_arguments)]
+use std::collections::BTreeMap;
 use std::fmt;
 use std::ops::ControlFlow;
 use std::ops::Deref;
 use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use once_cell::sync::Lazy;
@@ -12,15 +14,18 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::cob;
-use crate::cob::common::{Author, Tag, Timestamp};
+use crate::cob::common::{Author, Authorization, Delegates, Tag, Timestamp};
+use crate::cob::label::LabelId;
 use crate::cob::thread;
 use crate::cob::thread::CommentId;
 use crate::cob::thread::Thread;
+use crate::cob::xref;
 use crate::cob::{store, ActorId, ObjectId, OpId, TypeName};
 use crate::crypto::{PublicKey, Signer};
 use crate::git;
 use crate::prelude::*;
 use crate::storage::git as storage;
+use crate::storage::{BranchName, ProjectError, ReadRepository};
 
 /// The logical clock we use to order operations to patches.
 pub use clock::Lamport as Clock;
@@ -41,6 +46,9 @@ pub type RevisionId = OpId;
 /// Index of a revision in the revisions list.
 pub type RevisionIx = usize;
 
+/// Unique identifier for a code thread. This is also the identifier of its first comment.
+pub type CodeThreadId = OpId;
+
 /// Error applying an operation onto a state.
 #[derive(Error, Debug)]
 pub enum ApplyError {
@@ -53,6 +61,9 @@ pub enum ApplyError {
     /// that hasn't happened yet.
     #[error("causal dependency {0:?} missing")]
     Missing(OpId),
+    /// The author of the operation isn't allowed to carry it out.
+    #[error("{0} is not authorized to perform this action")]
+    Unauthorized(ActorId),
 }
 
 /// Error updating or creating patches.
@@ -62,6 +73,16 @@ pub enum Error {
     Apply(#[from] ApplyError),
     #[error("store: {0}")]
     Store(#[from] store::Error),
+    #[error("git: {0}")]
+    Git(#[from] git2::Error),
+    #[error("project: {0}")]
+    Project(#[from] ProjectError),
+    /// The patch can't go from its current status to the requested one.
+    #[error("cannot transition patch from {from} to {to}")]
+    InvalidTransition { from: Status, to: Status },
+    /// The base of a revision isn't part of the history of the merge target.
+    #[error("base {base} is not reachable from merge target `{target}`")]
+    UnreachableBase { base: git::Oid, target: MergeTarget },
 }
 
 /// Patch operation.
@@ -73,10 +94,17 @@ pub enum Action {
         description: String,
         target: MergeTarget,
     },
+    Lifecycle {
+        status: Status,
+    },
     Tag {
         add: Vec<Tag>,
         remove: Vec<Tag>,
     },
+    Label {
+        add: Vec<LabelId>,
+        remove: Vec<LabelId>,
+    },
     Revision {
         base: git::Oid,
         oid: git::Oid,
@@ -98,10 +126,28 @@ pub enum Action {
         revision: RevisionId,
         action: thread::Action,
     },
+    /// Start a discussion on a code location of a revision.
+    OpenCodeThread {
+        revision: RevisionId,
+        location: CodeLocation,
+        body: String,
+    },
+    /// Act on a code discussion, eg. reply to it.
+    CodeThread {
+        revision: RevisionId,
+        thread: CodeThreadId,
+        action: thread::Action,
+    },
+    /// Resolve or unresolve a code discussion.
+    ResolveCodeThread {
+        revision: RevisionId,
+        thread: CodeThreadId,
+        resolved: bool,
+    },
 }
 
 /// Where a patch is intended to be merged.
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum MergeTarget {
     /// Intended for the default branch of the project delegates.
@@ -110,6 +156,29 @@ pub enum MergeTarget {
     /// If it were otherwise, patches could become un-mergeable.
     #[default]
     Delegates,
+    /// Intended for the given branch of the project delegates, eg. a maintenance branch.
+    /// Like the default branch, its head is the one the current delegates agree on.
+    Branch(BranchName),
+}
+
+impl MergeTarget {
+    /// The canonical head of the merge target.
+    pub fn head<R: ReadRepository>(&self, repo: &R) -> Result<git::Oid, ProjectError> {
+        let (_, head) = match self {
+            Self::Delegates => repo.canonical_head()?,
+            Self::Branch(branch) => repo.canonical_branch_head(branch)?,
+        };
+        Ok(head)
+    }
+}
+
+impl fmt::Display for MergeTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Delegates => write!(f, "delegates"),
+            Self::Branch(branch) => write!(f, "{branch}"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -124,9 +193,13 @@ pub struct Patch {
     pub target: LWWReg<Max<MergeTarget>>,
     /// Associated tags.
     pub tags: LWWSet<Tag>,
+    /// Associated labels.
+    pub labels: LWWSet<LabelId>,
     /// List of patch revisions. The initial changeset is part of the
     /// first revision.
     pub revisions: GMap<RevisionId, Redactable<Revision>>,
+    /// Authorization of privileged actions on the patch.
+    pub auth: Authorization,
 }
 
 impl Semilattice for Patch {
@@ -136,7 +209,9 @@ impl Semilattice for Patch {
         self.status.merge(other.status);
         self.target.merge(other.target);
         self.tags.merge(other.tags);
+        self.labels.merge(other.labels);
         self.revisions.merge(other.revisions);
+        self.auth.merge(other.auth);
     }
 }
 
@@ -148,7 +223,9 @@ impl Default for Patch {
             status: Max::from(Status::default()).into(),
             target: Max::from(MergeTarget::default()).into(),
             tags: LWWSet::default(),
+            labels: LWWSet::default(),
             revisions: GMap::default(),
+            auth: Authorization::default(),
         }
     }
 }
@@ -162,8 +239,8 @@ impl Patch {
         *self.status.get().get()
     }
 
-    pub fn target(&self) -> MergeTarget {
-        *self.target.get().get()
+    pub fn target(&self) -> &MergeTarget {
+        self.target.get().get()
     }
 
     pub fn timestamp(&self) -> Timestamp {
@@ -214,14 +291,80 @@ impl Patch {
         self.revisions().next_back()
     }
 
+    /// Get the revision with the given index, eg. `0` for the first revision. Redacted
+    /// revisions keep their index, but aren't returned.
+    pub fn revision(&self, ix: RevisionIx) -> Option<(&RevisionId, &Revision)> {
+        self.revisions
+            .iter()
+            .nth(ix)
+            .and_then(|(rid, r)| r.get().map(|r| (rid, r)))
+    }
+
+    /// Range-diff between the revisions with the given indices. See [`Revision::range_diff`].
+    pub fn range_diff(
+        &self,
+        from: RevisionIx,
+        to: RevisionIx,
+        repo: &git2::Repository,
+    ) -> Result<Option<RangeDiff>, git2::Error> {
+        let (Some((_, from)), Some((_, to))) = (self.revision(from), self.revision(to)) else {
+            return Ok(None);
+        };
+        from.range_diff(to, repo).map(Some)
+    }
+
+    /// Interdiff between the revisions with the given indices. See [`Revision::interdiff`].
+    pub fn interdiff<'r>(
+        &self,
+        from: RevisionIx,
+        to: RevisionIx,
+        repo: &'r git2::Repository,
+    ) -> Result<Option<git2::Diff<'r>>, git2::Error> {
+        let (Some((_, from)), Some((_, to))) = (self.revision(from), self.revision(to)) else {
+            return Ok(None);
+        };
+        from.interdiff(to, repo).map(Some)
+    }
+
     pub fn is_proposed(&self) -> bool {
         matches!(self.status.get().get(), Status::Proposed)
     }
 
+    pub fn is_draft(&self) -> bool {
+        matches!(self.status.get().get(), &Status::Draft)
+    }
+
     pub fn is_archived(&self) -> bool {
         matches!(self.status.get().get(), &Status::Archived)
     }
 
+    /// Whether the given actor is allowed to carry out the given action. Editing, tagging,
+    /// labeling, updating and changing the status of a patch is reserved to its author and
+    /// to the project delegates.
+    /// Only delegates may merge a revision, and only its author may redact it. Code threads
+    /// can be resolved by whoever started them, as well as the patch author and delegates.
+    pub fn authorized(&self, action: &Action, actor: &ActorId, identity: &git::Oid) -> bool {
+        if self.auth.is_revoked(actor, identity) {
+            return false;
+        }
+        match action {
+            Action::Edit { .. }
+            | Action::Lifecycle { .. }
+            | Action::Tag { .. }
+            | Action::Label { .. }
+            | Action::Revision { .. } => self.auth.is_author_or_delegate(actor, identity),
+            Action::Redact { revision } => revision.1 == *actor,
+            Action::Merge { .. } => self.auth.is_delegate(actor, identity),
+            Action::ResolveCodeThread { thread, .. } => {
+                thread.1 == *actor || self.auth.is_author_or_delegate(actor, identity)
+            }
+            Action::Review { .. }
+            | Action::Thread { .. }
+            | Action::OpenCodeThread { .. }
+            | Action::CodeThread { .. } => true,
+        }
+    }
+
     /// Apply a list of operations to the state.
     pub fn apply(&mut self, ops: impl IntoIterator<Item = Op>) -> Result<(), ApplyError> {
         for op in ops {
@@ -232,6 +375,9 @@ impl Patch {
 
     /// Apply a single op to the state.
     pub fn apply_one(&mut self, op: Op) -> Result<(), ApplyError> {
+        if !self.authorized(&op.action, &op.author, &op.identity) {
+            return Err(ApplyError::Unauthorized(op.author));
+        }
         let id = op.id();
         let author = Author::new(op.author);
         let timestamp = op.timestamp;
@@ -246,6 +392,9 @@ impl Patch {
                 self.description.set(description, op.clock);
                 self.target.set(target, op.clock);
             }
+            Action::Lifecycle { status } => {
+                self.status.set(status, op.clock);
+            }
             Action::Tag { add, remove } => {
                 for tag in add {
                     self.tags.insert(tag, op.clock);
@@ -254,6 +403,14 @@ impl Patch {
                     self.tags.remove(tag, op.clock);
                 }
             }
+            Action::Label { add, remove } => {
+                for label in add {
+                    self.labels.insert(label, op.clock);
+                }
+                for label in remove {
+                    self.labels.remove(label, op.clock);
+                }
+            }
             Action::Revision { base, oid } => {
                 self.revisions.insert(
                     id,
@@ -306,14 +463,101 @@ impl Patch {
                         author: op.author,
                         clock: op.clock,
                         timestamp,
+                        identity: op.identity,
+                    }]);
+                } else {
+                    return Err(ApplyError::Missing(revision));
+                }
+            }
+            Action::OpenCodeThread {
+                revision,
+                location,
+                body,
+            } => {
+                if let Some(Redactable::Present(revision)) = self.revisions.get_mut(&revision) {
+                    let mut thread = CodeThread::new(location);
+                    thread.discussion.apply([cob::Op {
+                        action: thread::Action::Comment {
+                            body,
+                            reply_to: None,
+                        },
+                        author: op.author,
+                        clock: op.clock,
+                        timestamp,
+                        identity: op.identity,
                     }]);
+                    revision.threads.insert(id, thread);
                 } else {
                     return Err(ApplyError::Missing(revision));
                 }
             }
+            Action::CodeThread {
+                revision,
+                thread,
+                action,
+            } => {
+                let Some(thread) = self.code_thread_mut(&revision, &thread) else {
+                    return Err(ApplyError::Missing(thread));
+                };
+                thread.discussion.apply([cob::Op {
+                    action,
+                    author: op.author,
+                    clock: op.clock,
+                    timestamp,
+                    identity: op.identity,
+                }]);
+            }
+            Action::ResolveCodeThread {
+                revision,
+                thread,
+                resolved,
+            } => {
+                let Some(thread) = self.code_thread_mut(&revision, &thread) else {
+                    return Err(ApplyError::Missing(thread));
+                };
+                thread.resolved.set(resolved, op.clock);
+            }
         }
         Ok(())
     }
+
+    /// Code threads that apply to the given revision. This includes the threads started on
+    /// earlier revisions, with their locations carried forward to the given revision. Threads
+    /// on lines that changed since are outdated, and are returned without a location.
+    pub fn code_threads(
+        &self,
+        revision: &RevisionId,
+        repo: &git2::Repository,
+    ) -> Result<Vec<(CodeThreadId, &CodeThread, Option<CodeLocation>)>, git2::Error> {
+        let Some(Redactable::Present(target)) = self.revisions.get(revision) else {
+            return Ok(vec![]);
+        };
+        let mut threads = Vec::new();
+
+        for (rid, r) in self.revisions().take_while(|(rid, _)| *rid <= revision) {
+            for (id, thread) in r.threads.iter() {
+                let location = if rid == revision {
+                    Some(thread.location.clone())
+                } else {
+                    carry_forward(repo, &thread.location, target.oid)?
+                };
+                threads.push((*id, thread, location));
+            }
+        }
+        Ok(threads)
+    }
+
+    fn code_thread_mut(
+        &mut self,
+        revision: &RevisionId,
+        thread: &CodeThreadId,
+    ) -> Option<&mut CodeThread> {
+        if let Some(Redactable::Present(revision)) = self.revisions.get_mut(revision) {
+            revision.threads.get_mut(thread)
+        } else {
+            None
+        }
+    }
 }
 
 impl store::FromHistory for Patch {
@@ -325,12 +569,30 @@ impl store::FromHistory for Patch {
 
     fn from_history(
         history: &radicle_cob::History,
+        delegates: &Delegates,
     ) -> Result<(Self, clock::Lamport), store::Error> {
-        let obj = history.traverse(Self::default(), |mut acc, entry| {
+        let init = Self {
+            auth: Authorization::from_history(history, delegates),
+            ..Self::default()
+        };
+        let obj = history.traverse(init, |mut acc, entry| {
             if let Ok(op) = Op::try_from(entry) {
-                if let Err(err) = acc.apply([op]) {
-                    log::warn!("Error applying op to patch state: {err}");
-                    return ControlFlow::Break(acc);
+                match acc.apply_one(op) {
+                    Ok(()) => {}
+                    // Nb. Unauthorized ops are skipped rather than pruned, so that they
+                    // can't prevent later, authorized ops from being applied.
+                    Err(ApplyError::Unauthorized(actor)) => {
+                        log::debug!("Skipping unauthorized patch op by {actor}");
+                    }
+                    // Ops that depend on skipped or redacted ops, eg. a review of an
+                    // unauthorized revision, are skipped as well, for the same reason.
+                    Err(ApplyError::Missing(id)) => {
+                        log::debug!("Skipping patch op depending on missing op {id:?}");
+                    }
+                    Err(err) => {
+                        log::warn!("Error applying op to patch state: {err}");
+                        return ControlFlow::Break(acc);
+                    }
                 }
             } else {
                 return ControlFlow::Break(acc);
@@ -357,6 +619,8 @@ pub struct Revision {
     pub merges: LWWSet<Max<Merge>>,
     /// Reviews of this revision's changes (one per actor).
     pub reviews: GMap<ActorId, Review>,
+    /// Discussions on the code of this revision.
+    pub threads: GMap<CodeThreadId, CodeThread>,
     /// When this revision was created.
     pub timestamp: Timestamp,
 }
@@ -370,6 +634,7 @@ impl Revision {
             discussion: Thread::default(),
             merges: LWWSet::default(),
             reviews: GMap::default(),
+            threads: GMap::default(),
             timestamp,
         }
     }
@@ -377,17 +642,251 @@ impl Revision {
     pub fn description(&self) -> Option<&str> {
         self.discussion.first()
     }
+
+    /// The commits of this revision, ie. the commits between its base and its head,
+    /// oldest first.
+    pub fn commits(&self, repo: &git2::Repository) -> Result<Vec<git::Oid>, git2::Error> {
+        let mut walk = repo.revwalk()?;
+        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+        walk.push(*self.oid)?;
+        walk.hide(*self.base)?;
+
+        walk.map(|oid| oid.map(git::Oid::from)).collect()
+    }
+
+    /// Range-diff between this revision and a later one. Commits of both revisions are
+    /// matched up, even if they were rebased, and compared. The result follows the order
+    /// of the commits in the later revision.
+    ///
+    /// Commits are matched when they make the same changes, ignoring line numbers,
+    /// context and whitespace, or otherwise when they have the same summary.
+    pub fn range_diff(
+        &self,
+        other: &Revision,
+        repo: &git2::Repository,
+    ) -> Result<RangeDiff, git2::Error> {
+        let old = self
+            .commits(repo)?
+            .into_iter()
+            .map(|oid| CommitChanges::new(repo, oid))
+            .collect::<Result<Vec<_>, _>>()?;
+        let new = other
+            .commits(repo)?
+            .into_iter()
+            .map(|oid| CommitChanges::new(repo, oid))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // For each new commit, the index of the old commit it matches, if any.
+        let mut matches: Vec<Option<usize>> = vec![None; new.len()];
+        let mut matched = vec![false; old.len()];
+
+        for (i, n) in new.iter().enumerate() {
+            if let Some(j) = (0..old.len()).find(|j| !matched[*j] && old[*j].id == n.id) {
+                matches[i] = Some(j);
+                matched[j] = true;
+            }
+        }
+        for (i, n) in new.iter().enumerate() {
+            if matches[i].is_some() {
+                continue;
+            }
+            if let Some(j) = (0..old.len()).find(|j| !matched[*j] && old[*j].summary == n.summary) {
+                matches[i] = Some(j);
+                matched[j] = true;
+            }
+        }
+
+        let mut diff = Vec::new();
+        let mut removed = (0..old.len()).filter(|j| !matched[*j]).peekable();
+
+        for (n, m) in new.iter().zip(matches) {
+            let Some(j) = m else {
+                diff.push(RangeDiffEntry::Added { new: n.oid });
+                continue;
+            };
+            // Dropped commits are listed where they used to be.
+            while let Some(k) = removed.next_if(|k| *k < j) {
+                diff.push(RangeDiffEntry::Removed { old: old[k].oid });
+            }
+            let o = &old[j];
+
+            if o.id == n.id {
+                diff.push(RangeDiffEntry::Unchanged {
+                    old: o.oid,
+                    new: n.oid,
+                });
+            } else {
+                let changes = git2::Patch::from_buffers(
+                    o.text.as_bytes(),
+                    None,
+                    n.text.as_bytes(),
+                    None,
+                    None,
+                )?
+                .to_buf()?
+                .as_str()
+                .unwrap_or_default()
+                .to_owned();
+
+                diff.push(RangeDiffEntry::Modified {
+                    old: o.oid,
+                    new: n.oid,
+                    changes,
+                });
+            }
+        }
+        for k in removed {
+            diff.push(RangeDiffEntry::Removed { old: old[k].oid });
+        }
+        Ok(diff)
+    }
+
+    /// Interdiff between this revision and a later one, ie. the difference between the
+    /// trees of both revisions. If the later revision has a different base, this revision
+    /// is first replayed on top of it, so that changes brought in by a rebase are left out.
+    /// If that isn't possible without conflicts, the revision trees are compared as-is.
+    pub fn interdiff<'r>(
+        &self,
+        other: &Revision,
+        repo: &'r git2::Repository,
+    ) -> Result<git2::Diff<'r>, git2::Error> {
+        let mut old = repo.find_commit(*self.oid)?.tree()?;
+        let new = repo.find_commit(*other.oid)?.tree()?;
+
+        if self.base != other.base {
+            let ancestor = repo.find_commit(*self.base)?.tree()?;
+            let onto = repo.find_commit(*other.base)?.tree()?;
+            let mut index = repo.merge_trees(&ancestor, &onto, &old, None)?;
+
+            if !index.has_conflicts() {
+                old = repo.find_tree(index.write_tree_to(repo)?)?;
+            }
+        }
+        repo.diff_tree_to_tree(Some(&old), Some(&new), None)
+    }
+}
+
+/// Range-diff between two revisions. See [`Revision::range_diff`].
+pub type RangeDiff = Vec<RangeDiffEntry>;
+
+/// How a commit of a revision relates to the commits of another revision.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RangeDiffEntry {
+    /// The commit makes the same changes in both revisions.
+    Unchanged { old: git::Oid, new: git::Oid },
+    /// The commit was changed. The changes are a diff between the patches of both commits.
+    Modified {
+        old: git::Oid,
+        new: git::Oid,
+        changes: String,
+    },
+    /// The commit is only part of the earlier revision.
+    Removed { old: git::Oid },
+    /// The commit is only part of the later revision.
+    Added { new: git::Oid },
+}
+
+/// The changes made by a commit, used to match commits across revisions.
+struct CommitChanges {
+    /// The commit.
+    oid: git::Oid,
+    /// The commit summary.
+    summary: String,
+    /// Hash of the changed lines, ignoring whitespace, like `git patch-id`.
+    id: git2::Oid,
+    /// The commit patch, without line numbers and object ids.
+    text: String,
+}
+
+impl CommitChanges {
+    fn new(repo: &git2::Repository, oid: git::Oid) -> Result<Self, git2::Error> {
+        let commit = repo.find_commit(*oid)?;
+        let tree = commit.tree()?;
+        let parent = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(e) if e.code() == git2::ErrorCode::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        let diff = repo.diff_tree_to_tree(parent.as_ref(), Some(&tree), None)?;
+        let mut text = String::new();
+        let mut changed = String::new();
+
+        diff.print(git2::DiffFormat::Patch, |delta, _, line| {
+            let content = String::from_utf8_lossy(line.content());
+
+            match line.origin() {
+                'F' => {
+                    let old = delta.old_file().path().unwrap_or_else(|| Path::new(""));
+                    let new = delta.new_file().path().unwrap_or_else(|| Path::new(""));
+                    let header = format!("--- {}\n+++ {}\n", old.display(), new.display());
+
+                    text.push_str(&header);
+                    changed.push_str(&header);
+                }
+                'H' => text.push_str("@@\n"),
+                origin @ ('+' | '-') => {
+                    text.push(origin);
+                    text.push_str(&content);
+                    changed.push(origin);
+                    changed.extend(content.chars().filter(|c| !c.is_whitespace()));
+                    changed.push('\n');
+                }
+                ' ' => {
+                    text.push(' ');
+                    text.push_str(&content);
+                }
+                _ => {}
+            }
+            true
+        })?;
+
+        Ok(Self {
+            oid,
+            summary: commit.summary().unwrap_or_default().to_owned(),
+            id: git2::Oid::hash_object(git2::ObjectType::Blob, changed.as_bytes())?,
+            text,
+        })
+    }
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum Status {
+    /// The patch is open for review and merging.
     #[default]
     Proposed,
+    /// The patch is a work in progress.
     Draft,
+    /// The patch was abandoned.
     Archived,
 }
 
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Proposed => write!(f, "proposed"),
+            Self::Draft => write!(f, "draft"),
+            Self::Archived => write!(f, "archived"),
+        }
+    }
+}
+
+impl Status {
+    /// Whether a patch can go from this status to the given one. Drafts are marked ready
+    /// by proposing them, and archived patches are reopened as proposed patches.
+    pub fn can_transition(self, to: Status) -> bool {
+        matches!(
+            (self, to),
+            (Status::Draft, Status::Proposed)
+                | (Status::Proposed, Status::Draft)
+                | (Status::Proposed, Status::Archived)
+                | (Status::Draft, Status::Archived)
+                | (Status::Archived, Status::Proposed)
+        )
+    }
+}
+
 /// A merged patch revision.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "camelCase")]
@@ -431,11 +930,14 @@ impl fmt::Display for Verdict {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CodeLocation {
+    /// Path of the file being commented on.
+    #[serde(default)]
+    pub path: PathBuf,
     /// File being commented on.
     pub blob: git::Oid,
     /// Commit commented on.
     pub commit: git::Oid,
-    /// Line range commented on.
+    /// Line range commented on. Lines are numbered from one.
     pub lines: Range<usize>,
 }
 
@@ -447,12 +949,20 @@ impl PartialOrd for CodeLocation {
 
 impl Ord for CodeLocation {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        (&self.blob, &self.commit, &self.lines.start, &self.lines.end).cmp(&(
-            &other.blob,
-            &other.commit,
-            &other.lines.start,
-            &other.lines.end,
-        ))
+        (
+            &self.path,
+            &self.blob,
+            &self.commit,
+            &self.lines.start,
+            &self.lines.end,
+        )
+            .cmp(&(
+                &other.path,
+                &other.blob,
+                &other.commit,
+                &other.lines.start,
+                &other.lines.end,
+            ))
     }
 }
 
@@ -468,7 +978,42 @@ pub struct CodeComment {
     pub timestamp: Timestamp,
 }
 
+/// A discussion anchored to a code location.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeThread {
+    /// Where the discussion was started.
+    pub location: CodeLocation,
+    /// The discussion. Its first comment is the one that started the thread.
+    pub discussion: Thread,
+    /// Whether the discussion was resolved.
+    pub resolved: LWWReg<Max<bool>>,
+}
+
+impl CodeThread {
+    pub fn new(location: CodeLocation) -> Self {
+        Self {
+            location,
+            discussion: Thread::default(),
+            resolved: Max::from(false).into(),
+        }
+    }
+
+    pub fn is_resolved(&self) -> bool {
+        *self.resolved.get().get()
+    }
+}
+
+impl Semilattice for CodeThread {
+    fn merge(&mut self, other: Self) {
+        self.discussion.merge(other.discussion);
+        self.resolved.merge(other.resolved);
+    }
+}
+
 /// A patch review on a revision.
+///
+/// Inline comments are part of the review, and can't be replied to. For discussions on code,
+/// see [`CodeThread`].
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Review {
     /// Review verdict.
@@ -551,7 +1096,8 @@ impl<'a, 'g> PatchMut<'a, 'g> {
         &self.clock
     }
 
-    /// Edit patch metadata.
+    /// Edit patch metadata. If the merge target changes, the latest revision must be based
+    /// on it.
     pub fn edit<G: Signer>(
         &mut self,
         title: String,
@@ -559,6 +1105,11 @@ impl<'a, 'g> PatchMut<'a, 'g> {
         target: MergeTarget,
         signer: &G,
     ) -> Result<OpId, Error> {
+        if &target != self.target() {
+            if let Some((_, revision)) = self.latest() {
+                self.store.validate_base(&target, revision.base)?;
+            }
+        }
         let action = Action::Edit {
             title,
             description,
@@ -567,6 +1118,39 @@ impl<'a, 'g> PatchMut<'a, 'g> {
         self.apply("Edit", action, signer)
     }
 
+    /// Change the status of a patch.
+    ///
+    /// Nb. Transitions are only checked here, when the change is made. Concurrent changes
+    /// are resolved like any other register, ie. the latest one wins.
+    pub fn lifecycle<G: Signer>(&mut self, status: Status, signer: &G) -> Result<OpId, Error> {
+        let from = self.status();
+        if !from.can_transition(status) {
+            return Err(Error::InvalidTransition { from, to: status });
+        }
+        self.apply("Lifecycle", Action::Lifecycle { status }, signer)
+    }
+
+    /// Mark a draft patch as ready for review.
+    pub fn ready<G: Signer>(&mut self, signer: &G) -> Result<OpId, Error> {
+        self.lifecycle(Status::Proposed, signer)
+    }
+
+    /// Archive a patch.
+    pub fn archive<G: Signer>(&mut self, signer: &G) -> Result<OpId, Error> {
+        self.lifecycle(Status::Archived, signer)
+    }
+
+    /// Reopen an archived patch.
+    pub fn reopen<G: Signer>(&mut self, signer: &G) -> Result<OpId, Error> {
+        if !self.is_archived() {
+            return Err(Error::InvalidTransition {
+                from: self.status(),
+                to: Status::Proposed,
+            });
+        }
+        self.lifecycle(Status::Proposed, signer)
+    }
+
     /// Comment on a patch revision.
     pub fn comment<G: Signer, S: Into<String>>(
         &mut self,
@@ -585,6 +1169,58 @@ impl<'a, 'g> PatchMut<'a, 'g> {
         self.apply("Comment", action, signer)
     }
 
+    /// Start a discussion on a code location of a patch revision.
+    pub fn open_code_thread<G: Signer, S: Into<String>>(
+        &mut self,
+        revision: RevisionId,
+        location: CodeLocation,
+        body: S,
+        signer: &G,
+    ) -> Result<CodeThreadId, Error> {
+        let action = Action::OpenCodeThread {
+            revision,
+            location,
+            body: body.into(),
+        };
+        self.apply("Open code thread", action, signer)
+    }
+
+    /// Reply to a comment of a code thread.
+    pub fn reply_code_thread<G: Signer, S: Into<String>>(
+        &mut self,
+        revision: RevisionId,
+        thread: CodeThreadId,
+        reply_to: CommentId,
+        body: S,
+        signer: &G,
+    ) -> Result<CommentId, Error> {
+        let action = Action::CodeThread {
+            revision,
+            thread,
+            action: thread::Action::Comment {
+                body: body.into(),
+                reply_to: Some(reply_to),
+            },
+        };
+        self.apply("Reply to code thread", action, signer)
+    }
+
+    /// Resolve or unresolve a code thread.
+    pub fn resolve_code_thread<G: Signer>(
+        &mut self,
+        revision: RevisionId,
+        thread: CodeThreadId,
+        resolved: bool,
+        signer: &G,
+    ) -> Result<OpId, Error> {
+        let action = Action::ResolveCodeThread {
+            revision,
+            thread,
+            resolved,
+        };
+        self.apply("Resolve code thread", action, signer)
+    }
+
     /// Review a patch revision.
     pub fn review<G: Signer>(
         &mut self,
@@ -625,6 +1261,9 @@ impl<'a, 'g> PatchMut<'a, 'g> {
         let description = description.into();
         let base = base.into();
         let oid = oid.into();
+
+        self.store.validate_base(self.patch.target(), base)?;
+
         let revision = self.apply(
             "Update patch with new revision",
             Action::Revision { base, oid },
@@ -649,6 +1288,20 @@ impl<'a, 'g> PatchMut<'a, 'g> {
         self.apply("Tag", action, signer)
     }
 
+    /// Label a patch.
+    pub fn label<G: Signer>(
+        &mut self,
+        add: impl IntoIterator<Item = LabelId>,
+        remove: impl IntoIterator<Item = LabelId>,
+        signer: &G,
+    ) -> Result<OpId, Error> {
+        let add = add.into_iter().collect::<Vec<_>>();
+        let remove = remove.into_iter().collect::<Vec<_>>();
+        let action = Action::Label { add, remove };
+
+        self.apply("Label", action, signer)
+    }
+
     /// Apply an operation to the patch.
     pub fn apply<G: Signer>(
         &mut self,
@@ -656,6 +1309,12 @@ impl<'a, 'g> PatchMut<'a, 'g> {
         action: Action,
         signer: &G,
     ) -> Result<OpId, Error> {
+        if !self
+            .patch
+            .authorized(&action, signer.public_key(), self.store.identity())
+        {
+            return Err(ApplyError::Unauthorized(*signer.public_key()).into());
+        }
         let cob = self
             .store
             .update(self.id, msg, action.clone(), signer)
@@ -667,6 +1326,7 @@ impl<'a, 'g> PatchMut<'a, 'g> {
             author: *signer.public_key(),
             clock,
             timestamp,
+            identity: *self.store.identity(),
         };
         self.patch.apply_one(op)?;
 
@@ -718,8 +1378,12 @@ impl<'a> Patches<'a> {
     ) -> Result<PatchMut<'a, 'g>, Error> {
         let title = title.into();
         let description = description.into();
+        let base = base.into();
+
+        self.validate_base(&target, base)?;
+
         let action = Action::Revision {
-            base: base.into(),
+            base,
             oid: oid.into(),
         };
         let (id, patch, clock) = self.raw.create("Create patch", action, signer)?;
@@ -731,6 +1395,23 @@ impl<'a> Patches<'a> {
         Ok(patch)
     }
 
+    /// Create a draft patch.
+    pub fn draft<'g, G: Signer>(
+        &'g mut self,
+        title: impl Into<String>,
+        description: impl Into<String>,
+        target: MergeTarget,
+        base: impl Into<git::Oid>,
+        oid: impl Into<git::Oid>,
+        tags: &[Tag],
+        signer: &G,
+    ) -> Result<PatchMut<'a, 'g>, Error> {
+        let mut patch = self.create(title, description, target, base, oid, tags, signer)?;
+        patch.lifecycle(Status::Draft, signer)?;
+
+        Ok(patch)
+    }
+
     /// Get an issue.
     pub fn get(&self, id: &ObjectId) -> Result<Option<Patch>, store::Error> {
         self.raw.get(id).map(|r| r.map(|(p, _)| p))
@@ -751,16 +1432,67 @@ impl<'a> Patches<'a> {
         })
     }
 
+    /// Get patches with the given status. When possible, the object cache is used to find
+    /// matching patches, so that only their histories are replayed.
+    pub fn with_status(
+        &self,
+        status: Status,
+    ) -> Result<impl Iterator<Item = (PatchId, Patch, clock::Lamport)>, Error> {
+        #[cfg(feature = "sql")]
+        match self.cached(status) {
+            Ok(patches) => return Ok(patches.into_iter()),
+            Err(err) => log::warn!("Error querying patch cache: {err}; replaying all patches"),
+        }
+        let patches = self
+            .all()?
+            .filter_map(|result| result.ok())
+            .filter(|(_, p, _)| p.status() == status)
+            .collect::<Vec<_>>();
+
+        Ok(patches.into_iter())
+    }
+
+    /// Get patches with the given status, using the object cache. The cache is only read
+    /// from, see [`cob::cache::Cache::reader`].
+    #[cfg(feature = "sql")]
+    fn cached(
+        &self,
+        status: Status,
+    ) -> Result<Vec<(PatchId, Patch, clock::Lamport)>, cob::cache::Error> {
+        let repo = self.raw.as_ref();
+        let cache = cob::cache::Cache::reader(repo)?;
+
+        let entries = cache.patches(&cob::cache::Query {
+            status: Some(status.to_string()),
+            ..cob::cache::Query::default()
+        })?;
+        let mut patches = Vec::with_capacity(entries.len());
+
+        for entry in entries {
+            if let Some((patch, clock)) = self.raw.get(&entry.id)? {
+                patches.push((entry.id, patch, clock));
+            }
+        }
+        Ok(patches)
+    }
+
     /// Get proposed patches.
     pub fn proposed(
         &self,
     ) -> Result<impl Iterator<Item = (PatchId, Patch, clock::Lamport)>, Error> {
-        let all = self.all()?;
+        self.with_status(Status::Proposed)
+    }
 
-        Ok(all
-            .into_iter()
-            .filter_map(|result| result.ok())
-            .filter(|(_, p, _)| p.is_proposed()))
+    /// Get draft patches.
+    pub fn drafts(&self) -> Result<impl Iterator<Item = (PatchId, Patch, clock::Lamport)>, Error> {
+        self.with_status(Status::Draft)
+    }
+
+    /// Get archived patches.
+    pub fn archived(
+        &self,
+    ) -> Result<impl Iterator<Item = (PatchId, Patch, clock::Lamport)>, Error> {
+        self.with_status(Status::Archived)
     }
 
     /// Get patches proposed by the given key.
@@ -772,14 +1504,198 @@ impl<'a> Patches<'a> {
             .proposed()?
             .filter(move |(_, p, _)| p.author().id() == who))
     }
-}
 
-#[cfg(test)]
-mod test {
-    use std::str::FromStr;
-    use std::{array, iter};
-
-    use radicle_crdt::test::{assert_laws, WeightedGenerator};
+    /// Check that a revision base is part of the history of the given merge target.
+    ///
+    /// Nb. Only branch targets are checked. Patches targeting the default branch have their
+    /// base determined from it by the tooling that creates them, and the delegates may not
+    /// agree on a canonical head yet.
+    pub fn validate_base(&self, target: &MergeTarget, base: git::Oid) -> Result<(), Error> {
+        if *target == MergeTarget::Delegates {
+            return Ok(());
+        }
+        let repo = self.raw.as_ref();
+        let head = target.head(repo)?;
+
+        if head == base || repo.backend.graph_descendant_of(*head, *base)? {
+            Ok(())
+        } else {
+            Err(Error::UnreachableBase {
+                base,
+                target: target.clone(),
+            })
+        }
+    }
+
+    /// Mark the proposed patches whose latest revisions became reachable from the canonical
+    /// head of their merge target as merged, eg. after the target branch was pushed directly.
+    /// Merges are recorded on behalf of the signer, and only if the signer is a delegate.
+    ///
+    /// Returns the patches and revisions that were marked as merged.
+    pub fn detect_merges<G: Signer>(
+        &mut self,
+        signer: &G,
+    ) -> Result<Vec<(PatchId, RevisionId)>, Error> {
+        if !self
+            .raw
+            .delegates()
+            .is_delegate(signer.public_key(), self.raw.identity())
+        {
+            return Ok(vec![]);
+        }
+        let repo = self.raw.as_ref();
+        let mut heads = BTreeMap::new();
+        let mut candidates = Vec::new();
+
+        for (id, patch, _) in self.proposed()? {
+            // Patches that were already merged, even partially, are left alone.
+            if patch.revisions().any(|(_, r)| !r.merges.is_empty()) {
+                continue;
+            }
+            let head = match heads.get(patch.target()) {
+                Some(head) => *head,
+                None => {
+                    let head = match patch.target().head(repo) {
+                        Ok(head) => Some(head),
+                        // Targets without an agreed upon head can't have been merged into.
+                        Err(ProjectError::NoQuorum(_)) => None,
+                        Err(e) => return Err(e.into()),
+                    };
+                    heads.insert(patch.target().clone(), head);
+                    head
+                }
+            };
+            let Some(head) = head else {
+                continue;
+            };
+            for (rid, revision) in patch.revisions().rev() {
+                if revision.oid == revision.base {
+                    continue;
+                }
+                if let Some(commit) = merge_commit(&repo.backend, head, revision.oid)? {
+                    candidates.push((id, *rid, commit));
+                    break;
+                }
+            }
+        }
+
+        let mut merged = Vec::new();
+        for (id, rid, commit) in candidates {
+            self.get_mut(&id)?.merge(rid, commit, signer)?;
+            merged.push((id, rid));
+        }
+        Ok(merged)
+    }
+
+    /// Get the issues, patches and commits that mention a patch.
+    pub fn backlinks(&self, id: &ObjectId) -> Result<Vec<xref::Reference>, xref::Error> {
+        let index = xref::Index::refresh(self.raw.as_ref())?;
+
+        Ok(index
+            .backlinks(&xref::Reference::Patch(*id))
+            .copied()
+            .collect())
+    }
+}
+
+/// Carry a code location forward to the given commit. Returns `None` if the commented
+/// lines changed, or if the file no longer exists.
+fn carry_forward(
+    repo: &git2::Repository,
+    location: &CodeLocation,
+    commit: git::Oid,
+) -> Result<Option<CodeLocation>, git2::Error> {
+    if location.commit == commit {
+        return Ok(Some(location.clone()));
+    }
+    let tree = repo.find_commit(*commit)?.tree()?;
+    let blob = match tree.get_path(&location.path) {
+        Ok(entry) => entry.id(),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let (start, end) = (location.lines.start, location.lines.end);
+    let mut offset = 0isize;
+    let mut changed = false;
+
+    if blob != *location.blob {
+        let old = repo.find_blob(*location.blob)?;
+        let new = repo.find_blob(blob)?;
+        let mut opts = git2::DiffOptions::new();
+        let mut on_hunk = |_: git2::DiffDelta<'_>, hunk: git2::DiffHunk<'_>| {
+            let old_start = hunk.old_start() as usize;
+            let old_lines = hunk.old_lines() as usize;
+            let delta = hunk.new_lines() as isize - old_lines as isize;
+
+            if old_lines == 0 {
+                // Lines were inserted after `old_start`.
+                if old_start < start {
+                    offset += delta;
+                } else if old_start + 1 < end {
+                    changed = true;
+                }
+            } else if old_start + old_lines <= start {
+                offset += delta;
+            } else if old_start < end {
+                changed = true;
+            }
+            true
+        };
+        opts.context_lines(0);
+        repo.diff_blobs(
+            Some(&old),
+            None,
+            Some(&new),
+            None,
+            Some(&mut opts),
+            None,
+            None,
+            Some(&mut on_hunk),
+            None,
+        )?;
+    }
+    if changed {
+        return Ok(None);
+    }
+
+    Ok(Some(CodeLocation {
+        path: location.path.clone(),
+        blob: blob.into(),
+        commit,
+        lines: (start as isize + offset) as usize..(end as isize + offset) as usize,
+    }))
+}
+
+/// The commit through which `oid` entered the first-parent history of `head`, if `oid` is
+/// reachable from `head`.
+fn merge_commit(
+    repo: &git2::Repository,
+    head: git::Oid,
+    oid: git::Oid,
+) -> Result<Option<git::Oid>, git2::Error> {
+    let contains = |commit: git2::Oid| -> Result<bool, git2::Error> {
+        Ok(commit == *oid || repo.graph_descendant_of(commit, *oid)?)
+    };
+    if !contains(*head)? {
+        return Ok(None);
+    }
+    let mut commit = repo.find_commit(*head)?;
+
+    while let Ok(parent) = commit.parent(0) {
+        if !contains(parent.id())? {
+            break;
+        }
+        commit = parent;
+    }
+    Ok(Some(commit.id().into()))
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+    use std::{array, iter};
+
+    use radicle_crdt::test::{assert_laws, WeightedGenerator};
 
     use pretty_assertions::assert_eq;
     use qcheck::{Arbitrary, TestResult};
@@ -836,6 +1752,12 @@ mod test {
                         },
                     ))
                 })
+                .variant(1, |(clock, _, _), rng| {
+                    let status =
+                        [Status::Proposed, Status::Draft, Status::Archived][rng.usize(..3)];
+
+                    Some((clock.tick(), Action::Lifecycle { status }))
+                })
                 .variant(1, |(clock, revisions, _), rng| {
                     if revisions.is_empty() {
                         return None;
@@ -888,6 +1810,7 @@ mod test {
                     author,
                     clock,
                     timestamp,
+                    identity: git::raw::Oid::zero().into(),
                 });
             }
 
@@ -903,25 +1826,32 @@ mod test {
     #[test]
     fn prop_invariants() {
         fn property(log: Changes<3>) -> TestResult {
-            let t = Patch::default();
+            let author = ActorId::from([0; 32]);
+            let t = Patch {
+                auth: Authorization::new(author, [author]),
+                ..Patch::default()
+            };
             let [p1, p2, p3] = log.permutations;
 
             let mut t1 = t.clone();
             match t1.apply(p1) {
                 Ok(()) => {}
                 Err(ApplyError::Missing(_)) => return TestResult::discard(),
+                Err(err) => return TestResult::error(err.to_string()),
             }
 
             let mut t2 = t.clone();
             match t2.apply(p2) {
                 Ok(()) => {}
                 Err(ApplyError::Missing(_)) => return TestResult::discard(),
+                Err(err) => return TestResult::error(err.to_string()),
             }
 
             let mut t3 = t;
             match t3.apply(p3) {
                 Ok(()) => {}
                 Err(ApplyError::Missing(_)) => return TestResult::discard(),
+                Err(err) => return TestResult::error(err.to_string()),
             }
 
             assert_eq!(t1, t2);
@@ -962,7 +1892,7 @@ mod test {
             .create(
                 "My first patch",
                 "Blah blah blah.",
-                target,
+                target.clone(),
                 base,
                 oid,
                 &[],
@@ -977,7 +1907,7 @@ mod test {
         assert_eq!(patch.description(), Some("Blah blah blah."));
         assert_eq!(patch.author().id(), &author);
         assert_eq!(patch.status(), Status::Proposed);
-        assert_eq!(patch.target(), target);
+        assert_eq!(patch.target(), &target);
         assert_eq!(patch.version(), 0);
 
         let (_, revision) = patch.latest().unwrap();
@@ -989,6 +1919,67 @@ mod test {
         assert_eq!(revision.base, base);
     }
 
+    #[test]
+    fn test_patch_lifecycle() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut patches = Patches::open(*signer.public_key(), &project).unwrap();
+        let oid = git::Oid::from_str("e2a85016a458cd809c0ecee81f8c99613b0b0945").unwrap();
+        let base = git::Oid::from_str("cb18e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap();
+        let mut patch = patches
+            .draft(
+                "My first patch",
+                "Blah blah blah.",
+                MergeTarget::Delegates,
+                base,
+                oid,
+                &[],
+                &signer,
+            )
+            .unwrap();
+        let id = patch.id;
+
+        assert_eq!(patch.status(), Status::Draft);
+        assert!(matches!(
+            patch.reopen(&signer),
+            Err(Error::InvalidTransition { .. })
+        ));
+
+        patch.ready(&signer).unwrap();
+        assert_eq!(patch.status(), Status::Proposed);
+
+        patch.archive(&signer).unwrap();
+        assert_eq!(patch.status(), Status::Archived);
+        assert!(matches!(
+            patch.lifecycle(Status::Draft, &signer),
+            Err(Error::InvalidTransition { .. })
+        ));
+        assert!(matches!(
+            patch.archive(&signer),
+            Err(Error::InvalidTransition { .. })
+        ));
+        assert_eq!(patches.archived().unwrap().count(), 1);
+        assert_eq!(patches.proposed().unwrap().count(), 0);
+
+        let mut patch = patches.get_mut(&id).unwrap();
+        patch.reopen(&signer).unwrap();
+        patch.lifecycle(Status::Draft, &signer).unwrap();
+
+        let patch = patches.get(&id).unwrap().unwrap();
+        assert_eq!(patch.status(), Status::Draft);
+        assert_eq!(patches.drafts().unwrap().count(), 1);
+
+        // Transitions aren't checked when the history is replayed, so that the status
+        // doesn't depend on the order of concurrent changes: the latest one wins.
+        for status in [Status::Archived, Status::Draft] {
+            patches
+                .update(id, "Lifecycle", Action::Lifecycle { status }, &signer)
+                .unwrap();
+        }
+        let patch = patches.get(&id).unwrap().unwrap();
+        assert_eq!(patch.status(), Status::Draft);
+    }
+
     #[test]
     fn test_patch_merge() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1023,6 +2014,78 @@ mod test {
         assert_eq!(merge.commit, base);
     }
 
+    #[test]
+    fn test_patch_merge_authorized_at_identity_version() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, alice, project) = test::setup::context(&tmp);
+        let bob = MockSigner::default();
+        let oid = git::Oid::from_str("e2a85016a458cd809c0ecee81f8c99613b0b0945").unwrap();
+        let (_, base) = project.canonical_head().unwrap();
+
+        // Alice adds Bob as a delegate.
+        let mut doc = project.project_of(alice.public_key()).unwrap();
+        assert!(doc.delegate("bob".to_owned(), *bob.public_key()));
+        let (_, sig) = doc.sign(&alice).unwrap();
+        doc.update(
+            alice.public_key(),
+            "Add bob",
+            &[(alice.public_key(), sig)],
+            &project,
+        )
+        .unwrap();
+
+        let mut patches = Patches::open(*alice.public_key(), &project).unwrap();
+        let patch = patches
+            .create(
+                "My first patch",
+                "Blah blah blah.",
+                MergeTarget::Delegates,
+                base,
+                oid,
+                &[],
+                &alice,
+            )
+            .unwrap();
+        let id = patch.id;
+        let (rid, _) = patch.latest().unwrap();
+        let rid = *rid;
+
+        // Bob merges the patch while he is a delegate.
+        let mut bob_patches = Patches::open(*bob.public_key(), &project).unwrap();
+        bob_patches
+            .get_mut(&id)
+            .unwrap()
+            .merge(rid, base, &bob)
+            .unwrap();
+
+        // Alice revokes Bob's key.
+        doc.revoke(bob.public_key()).unwrap();
+        let (_, sig) = doc.sign(&alice).unwrap();
+        doc.update(
+            alice.public_key(),
+            "Revoke bob",
+            &[(alice.public_key(), sig)],
+            &project,
+        )
+        .unwrap();
+
+        // Bob's earlier merge still stands, but he can no longer act on the patch.
+        let mut bob_patches = Patches::open(*bob.public_key(), &project).unwrap();
+        let mut patch = bob_patches.get_mut(&id).unwrap();
+        assert_eq!(patch.latest().unwrap().1.merges.iter().count(), 1);
+        assert!(matches!(
+            patch.merge(rid, oid, &bob),
+            Err(Error::Apply(ApplyError::Unauthorized(_)))
+        ));
+        assert!(matches!(
+            patch.comment(rid, "LGTM", &bob),
+            Err(Error::Apply(ApplyError::Unauthorized(_)))
+        ));
+
+        let patch = patches.get(&id).unwrap().unwrap();
+        assert_eq!(patch.latest().unwrap().1.merges.iter().count(), 1);
+    }
+
     #[test]
     fn test_patch_review() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1068,7 +2131,10 @@ mod test {
         let base = git::Oid::from_str("cb18e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap();
         let oid = git::Oid::from_str("518d5069f94c03427f694bb494ac1cd7d1339380").unwrap();
         let mut alice = Actor::<_, Action>::new(MockSigner::default());
-        let mut patch = Patch::default();
+        let mut patch = Patch {
+            auth: Authorization::new(*alice.signer.public_key(), [*alice.signer.public_key()]),
+            ..Patch::default()
+        };
 
         let a1 = alice.op(Action::Revision { base, oid });
         let a2 = alice.op(Action::Redact { revision: a1.id() });
@@ -1098,8 +2164,11 @@ mod test {
         let base = git::Oid::from_str("cb18e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap();
         let oid = git::Oid::from_str("518d5069f94c03427f694bb494ac1cd7d1339380").unwrap();
         let mut alice = Actor::<_, Action>::new(MockSigner::default());
-        let mut p1 = Patch::default();
-        let mut p2 = Patch::default();
+        let mut p1 = Patch {
+            auth: Authorization::new(*alice.signer.public_key(), []),
+            ..Patch::default()
+        };
+        let mut p2 = p1.clone();
 
         let a1 = alice.op(Action::Revision { base, oid });
         let a2 = alice.op(Action::Redact { revision: a1.id() });
@@ -1110,6 +2179,216 @@ mod test {
         assert_eq!(p1, p2);
     }
 
+    #[test]
+    fn test_patch_authorization() {
+        let base = git::Oid::from_str("cb18e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap();
+        let oid = git::Oid::from_str("518d5069f94c03427f694bb494ac1cd7d1339380").unwrap();
+        let mut alice = Actor::<_, Action>::new(MockSigner::default());
+        let mut bob = Actor::<_, Action>::new(MockSigner::default());
+        let mut eve = Actor::<_, Action>::new(MockSigner::default());
+        let mut patch = Patch {
+            auth: Authorization::new(*alice.signer.public_key(), [*bob.signer.public_key()]),
+            ..Patch::default()
+        };
+
+        let a1 = alice.op(Action::Revision { base, oid });
+        let revision = a1.id();
+        patch.apply([a1]).unwrap();
+
+        let e1 = eve.op(Action::Edit {
+            title: "Eve's patch".to_owned(),
+            description: String::new(),
+            target: MergeTarget::Delegates,
+        });
+        let e2 = eve.op(Action::Merge {
+            revision,
+            commit: oid,
+        });
+        let e3 = eve.op(Action::Redact { revision });
+        let e4 = eve.op(Action::Review {
+            revision,
+            comment: None,
+            verdict: Some(Verdict::Reject),
+            inline: vec![],
+        });
+        assert!(matches!(
+            patch.apply([e1]),
+            Err(ApplyError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            patch.apply([e2]),
+            Err(ApplyError::Unauthorized(_))
+        ));
+        assert!(matches!(
+            patch.apply([e3]),
+            Err(ApplyError::Unauthorized(_))
+        ));
+        patch.apply([e4]).unwrap();
+        assert!(matches!(
+            patch.apply([eve.op(Action::Lifecycle {
+                status: Status::Archived
+            })]),
+            Err(ApplyError::Unauthorized(_))
+        ));
+        assert_eq!(patch.status(), Status::Proposed);
+
+        let (_, r) = patch.latest().unwrap();
+        assert_eq!(patch.title(), "");
+        assert!(r.merges.is_empty());
+        assert_eq!(r.reviews.len(), 1);
+
+        // Only delegates can merge.
+        let a2 = alice.op(Action::Merge {
+            revision,
+            commit: oid,
+        });
+        let b1 = bob.op(Action::Merge {
+            revision,
+            commit: oid,
+        });
+        patch.apply([a2]).unwrap_err();
+        patch.apply([b1]).unwrap();
+
+        let (_, r) = patch.latest().unwrap();
+        assert_eq!(r.merges.iter().count(), 1);
+
+        // Only the revision author can redact it.
+        let b2 = bob.op(Action::Redact { revision });
+        let a3 = alice.op(Action::Redact { revision });
+        patch.apply([b2]).unwrap_err();
+        assert!(patch.latest().is_some());
+        patch.apply([a3]).unwrap();
+        assert!(patch.latest().is_none());
+    }
+
+    #[test]
+    fn test_patch_unauthorized_dependencies() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let eve = MockSigner::new(&mut fastrand::Rng::new());
+        let mut patches = Patches::open(*signer.public_key(), &project).unwrap();
+        let (_, base) = project.canonical_head().unwrap();
+        let id = patches
+            .create(
+                "My patch",
+                "",
+                MergeTarget::Delegates,
+                base,
+                base,
+                &[],
+                &signer,
+            )
+            .unwrap()
+            .id;
+
+        // Eve bypasses authorization by writing to the store directly, adding a revision,
+        // and then reviewing and commenting on it.
+        let cob = patches
+            .update(id, "Revision", Action::Revision { base, oid: base }, &eve)
+            .unwrap();
+        let revision = (cob.history().clock().into(), *eve.public_key());
+        patches
+            .update(
+                id,
+                "Review",
+                Action::Review {
+                    revision,
+                    comment: None,
+                    verdict: Some(Verdict::Accept),
+                    inline: vec![],
+                },
+                &eve,
+            )
+            .unwrap();
+        patches
+            .update(
+                id,
+                "Comment",
+                Action::Thread {
+                    revision,
+                    action: thread::Action::Comment {
+                        body: "Looks good".to_owned(),
+                        reply_to: None,
+                    },
+                },
+                &eve,
+            )
+            .unwrap();
+
+        // Later ops are still applied.
+        patches
+            .update(
+                id,
+                "Edit",
+                Action::Edit {
+                    title: "My updated patch".to_owned(),
+                    description: String::new(),
+                    target: MergeTarget::Delegates,
+                },
+                &signer,
+            )
+            .unwrap();
+
+        let patch = patches.get(&id).unwrap().unwrap();
+        assert_eq!(patch.title(), "My updated patch");
+        assert_eq!(patch.revisions().count(), 1);
+    }
+
+    #[test]
+    fn test_patch_authorization_retroactive() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, alice, project) = test::setup::context(&tmp);
+        let bob = MockSigner::new(&mut fastrand::Rng::new());
+        let mut patches = Patches::open(*alice.public_key(), &project).unwrap();
+        let (_, base) = project.canonical_head().unwrap();
+        let patch = patches
+            .create(
+                "My patch",
+                "",
+                MergeTarget::Delegates,
+                base,
+                base,
+                &[],
+                &alice,
+            )
+            .unwrap();
+        let id = patch.id;
+        let (revision, _) = patch.latest().unwrap();
+        let revision = *revision;
+
+        // Bob isn't a delegate, so his merge is skipped.
+        patches
+            .update(
+                id,
+                "Merge",
+                Action::Merge {
+                    revision,
+                    commit: base,
+                },
+                &bob,
+            )
+            .unwrap();
+        let patch = patches.get(&id).unwrap().unwrap();
+        assert!(patch.latest().unwrap().1.merges.is_empty());
+
+        // Ops are authorized against the current delegates, not the ones at the time of the
+        // op: once Bob is a delegate, his earlier merge is applied.
+        let mut doc = project.project_of(alice.public_key()).unwrap();
+        doc.delegate("bob".to_owned(), *bob.public_key());
+        let (_, sig) = doc.sign(&alice).unwrap();
+        doc.update(
+            alice.public_key(),
+            "Add bob",
+            &[(alice.public_key(), sig)],
+            &project,
+        )
+        .unwrap();
+
+        let patches = Patches::open(*alice.public_key(), &project).unwrap();
+        let patch = patches.get(&id).unwrap().unwrap();
+        assert_eq!(patch.latest().unwrap().1.merges.iter().count(), 1);
+    }
+
     #[test]
     fn test_patch_review_edit() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1201,4 +2480,435 @@ mod test {
         assert_eq!(revision.oid, rev1_oid);
         assert_eq!(revision.description(), Some("I've made changes."));
     }
+
+    #[test]
+    fn test_patch_detect_merges() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut patches = Patches::open(*signer.public_key(), &project).unwrap();
+        let (branch, head) = project.canonical_head().unwrap();
+        let repo = &project.backend;
+        let parent = repo.find_commit(head.into()).unwrap();
+        let oid = repo
+            .commit(
+                None,
+                &parent.author(),
+                &parent.committer(),
+                "Add a feature",
+                &parent.tree().unwrap(),
+                &[&parent],
+            )
+            .unwrap();
+        let patch = patches
+            .create(
+                "My patch",
+                "",
+                MergeTarget::Delegates,
+                head,
+                oid,
+                &[],
+                &signer,
+            )
+            .unwrap();
+        let (rid, _) = patch.latest().unwrap();
+        let (id, rid) = (patch.id, *rid);
+
+        assert!(patches.detect_merges(&signer).unwrap().is_empty());
+
+        // The patch is merged by pushing it to the default branch.
+        repo.reference(
+            &format!("refs/namespaces/{}/{}", signer.public_key(), branch),
+            oid,
+            true,
+            "",
+        )
+        .unwrap();
+
+        assert_eq!(patches.detect_merges(&signer).unwrap(), vec![(id, rid)]);
+        assert!(patches.detect_merges(&signer).unwrap().is_empty());
+
+        let patch = patches.get(&id).unwrap().unwrap();
+        let (_, revision) = patch.latest().unwrap();
+        let merges = revision.merges.iter().collect::<Vec<_>>();
+
+        assert_eq!(merges.len(), 1);
+        assert_eq!(merges[0].node, *signer.public_key());
+        assert_eq!(merges[0].commit, oid.into());
+    }
+
+    #[test]
+    fn test_patch_code_threads() {
+        let base = git::Oid::from_str("cb18e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap();
+        let oid = git::Oid::from_str("518d5069f94c03427f694bb494ac1cd7d1339380").unwrap();
+        let mut alice = Actor::<_, Action>::new(MockSigner::default());
+        let mut bob = Actor::<_, Action>::new(MockSigner::default());
+        let mut eve = Actor::<_, Action>::new(MockSigner::default());
+        let mut patch = Patch {
+            auth: Authorization::new(*alice.signer.public_key(), [*bob.signer.public_key()]),
+            ..Patch::default()
+        };
+        let location = CodeLocation {
+            path: PathBuf::from("README"),
+            blob: oid,
+            commit: oid,
+            lines: 1..3,
+        };
+
+        let a1 = alice.op(Action::Revision { base, oid });
+        let revision = a1.id();
+        patch.apply([a1]).unwrap();
+
+        let e1 = eve.op(Action::OpenCodeThread {
+            revision,
+            location: location.clone(),
+            body: "Why?".to_owned(),
+        });
+        let thread = e1.id();
+        let a2 = alice.op(Action::CodeThread {
+            revision,
+            thread,
+            action: thread::Action::Comment {
+                body: "Because.".to_owned(),
+                reply_to: Some(thread),
+            },
+        });
+        let a3 = alice.op(Action::OpenCodeThread {
+            revision,
+            location: location.clone(),
+            body: "Needs a test".to_owned(),
+        });
+        let other = a3.id();
+        patch.apply([e1, a2, a3]).unwrap();
+
+        let (_, r) = patch.latest().unwrap();
+        let t = r.threads.get(&thread).unwrap();
+        assert_eq!(r.threads.len(), 2);
+        assert_eq!(t.location, location);
+        assert_eq!(t.discussion.first(), Some("Why?"));
+        assert_eq!(t.discussion.replies(&thread).count(), 1);
+        assert!(!t.is_resolved());
+
+        // Only the thread author, the patch author and delegates can resolve threads.
+        assert!(matches!(
+            patch.apply([eve.op(Action::ResolveCodeThread {
+                revision,
+                thread: other,
+                resolved: true,
+            })]),
+            Err(ApplyError::Unauthorized(_))
+        ));
+        patch
+            .apply([
+                eve.op(Action::ResolveCodeThread {
+                    revision,
+                    thread,
+                    resolved: true,
+                }),
+                bob.op(Action::ResolveCodeThread {
+                    revision,
+                    thread: other,
+                    resolved: true,
+                }),
+            ])
+            .unwrap();
+
+        let (_, r) = patch.latest().unwrap();
+        assert!(r.threads.values().all(|t| t.is_resolved()));
+
+        // Threads must exist to be acted on.
+        assert!(matches!(
+            patch.apply([eve.op(Action::CodeThread {
+                revision,
+                thread: revision,
+                action: thread::Action::Comment {
+                    body: "Hello?".to_owned(),
+                    reply_to: None,
+                },
+            })]),
+            Err(ApplyError::Missing(_))
+        ));
+    }
+
+    #[test]
+    fn test_patch_code_threads_carry_forward() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(tmp.path()).unwrap();
+        let sig = git2::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let commit = |contents: &str| -> (git::Oid, git::Oid) {
+            let blob = repo.blob(contents.as_bytes()).unwrap();
+            let mut tree = repo.treebuilder(None).unwrap();
+            tree.insert("README", blob, 0o100644).unwrap();
+
+            let tree = repo.find_tree(tree.write().unwrap()).unwrap();
+            let oid = repo.commit(None, &sig, &sig, "Update", &tree, &[]).unwrap();
+
+            (oid.into(), blob.into())
+        };
+        let (base, _) = commit("a\nb\nc\nd\ne\n");
+        let (c1, b1) = commit("a\nb\nc\nd\ne\nf\n");
+        let (c2, b2) = commit("z\na\nb\nc\nd\nE\nf\n");
+        let (c3, _) = commit("z\na\nb\nC\nd\nE\nf\n");
+
+        let mut alice = Actor::<_, Action>::new(MockSigner::default());
+        let mut patch = Patch {
+            auth: Authorization::new(*alice.signer.public_key(), []),
+            ..Patch::default()
+        };
+        let location = |lines| CodeLocation {
+            path: PathBuf::from("README"),
+            blob: b1,
+            commit: c1,
+            lines,
+        };
+        let r1 = alice.op(Action::Revision { base, oid: c1 });
+        let t1 = alice.op(Action::OpenCodeThread {
+            revision: r1.id(),
+            location: location(2..4),
+            body: "Lines b and c".to_owned(),
+        });
+        let t2 = alice.op(Action::OpenCodeThread {
+            revision: r1.id(),
+            location: location(5..6),
+            body: "Line e".to_owned(),
+        });
+        let r2 = alice.op(Action::Revision { base, oid: c2 });
+        let r3 = alice.op(Action::Revision { base, oid: c3 });
+        let (rid1, rid2, rid3) = (r1.id(), r2.id(), r3.id());
+        let (tid1, tid2) = (t1.id(), t2.id());
+
+        patch.apply([r1, t1, t2, r2, r3]).unwrap();
+
+        // On the revision they were started on, threads keep their location.
+        let threads = patch.code_threads(&rid1, &repo).unwrap();
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].2, Some(location(2..4)));
+        assert_eq!(threads[1].2, Some(location(5..6)));
+
+        // A line was inserted above both threads, and the line of the second thread changed.
+        let threads = patch.code_threads(&rid2, &repo).unwrap();
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].0, tid1);
+        assert_eq!(
+            threads[0].2,
+            Some(CodeLocation {
+                path: PathBuf::from("README"),
+                blob: b2,
+                commit: c2,
+                lines: 3..5,
+            })
+        );
+        assert_eq!(threads[1].0, tid2);
+        assert_eq!(threads[1].2, None);
+
+        // The lines of the first thread changed too.
+        let threads = patch.code_threads(&rid3, &repo).unwrap();
+        assert_eq!(threads.len(), 2);
+        assert!(threads.iter().all(|(_, _, location)| location.is_none()));
+    }
+
+    #[test]
+    fn test_patch_range_diff() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = git2::Repository::init(tmp.path()).unwrap();
+        let sig = git2::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let commit = |parent: Option<git::Oid>, files: &[(&str, &str)], msg: &str| {
+            let parent = parent.map(|p| repo.find_commit(*p).unwrap());
+            let tree = parent.as_ref().map(|p| p.tree().unwrap());
+            let mut builder = repo.treebuilder(tree.as_ref()).unwrap();
+
+            for (path, contents) in files {
+                let blob = repo.blob(contents.as_bytes()).unwrap();
+                builder.insert(path, blob, 0o100644).unwrap();
+            }
+            let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+            let parents = parent.iter().collect::<Vec<_>>();
+
+            git::Oid::from(repo.commit(None, &sig, &sig, msg, &tree, &parents).unwrap())
+        };
+
+        let base = commit(None, &[("README", "Hello\n")], "Initial commit");
+        let a = commit(Some(base), &[("a", "a\n")], "Add a");
+        let b = commit(Some(a), &[("b", "b\n")], "Add b");
+        let d = commit(Some(b), &[("d", "d\n")], "Add d");
+
+        // The second revision is rebased on a new base, changes `b`, drops `d` and adds `c`.
+        let onto = commit(Some(base), &[("README", "Hello World\n")], "Update README");
+        let a2 = commit(Some(onto), &[("a", "a\n")], "Add a");
+        let b2 = commit(Some(a2), &[("b", "B\n")], "Add b");
+        let c2 = commit(Some(b2), &[("c", "c\n")], "Add c");
+
+        let mut alice = Actor::<_, Action>::new(MockSigner::default());
+        let mut patch = Patch {
+            auth: Authorization::new(*alice.signer.public_key(), []),
+            ..Patch::default()
+        };
+        patch
+            .apply([
+                alice.op(Action::Revision { base, oid: d }),
+                alice.op(Action::Revision {
+                    base: onto,
+                    oid: c2,
+                }),
+            ])
+            .unwrap();
+
+        let (_, r1) = patch.revision(0).unwrap();
+        assert_eq!(r1.commits(&repo).unwrap(), vec![a, b, d]);
+
+        let diff = patch.range_diff(0, 1, &repo).unwrap().unwrap();
+        assert_eq!(diff.len(), 4);
+        assert_eq!(diff[0], RangeDiffEntry::Unchanged { old: a, new: a2 });
+        assert!(matches!(
+            &diff[1],
+            RangeDiffEntry::Modified { old, new, changes }
+                if *old == b && *new == b2 && changes.contains("-+b") && changes.contains("++B")
+        ));
+        assert_eq!(diff[2], RangeDiffEntry::Added { new: c2 });
+        assert_eq!(diff[3], RangeDiffEntry::Removed { old: d });
+        assert!(patch.range_diff(0, 2, &repo).unwrap().is_none());
+
+        // The change to the README brought in by the rebase isn't part of the interdiff.
+        let interdiff = patch.interdiff(0, 1, &repo).unwrap().unwrap();
+        let mut paths = interdiff
+            .deltas()
+            .filter_map(|d| d.new_file().path().map(|p| p.to_owned()))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("b"), PathBuf::from("c"), PathBuf::from("d")]
+        );
+    }
+
+    #[test]
+    fn test_patch_branch_target() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut patches = Patches::open(*signer.public_key(), &project).unwrap();
+        let (_, head) = project.canonical_head().unwrap();
+        let repo = &project.backend;
+        let parent = repo.find_commit(head.into()).unwrap();
+        let tree = parent.tree().unwrap();
+        let oid = repo
+            .commit(
+                None,
+                &parent.author(),
+                &parent.committer(),
+                "Backport a fix",
+                &tree,
+                &[&parent],
+            )
+            .unwrap();
+        let orphan = repo
+            .commit(
+                None,
+                &parent.author(),
+                &parent.committer(),
+                "Unrelated",
+                &tree,
+                &[],
+            )
+            .unwrap();
+        let release = |oid: git2::Oid| {
+            repo.reference(
+                &format!(
+                    "refs/namespaces/{}/refs/heads/release/1.x",
+                    signer.public_key()
+                ),
+                oid,
+                true,
+                "",
+            )
+            .unwrap();
+        };
+        let target = MergeTarget::Branch(BranchName::try_from("release/1.x").unwrap());
+        release(head.into());
+
+        assert!(matches!(
+            patches.create("Backport", "", target.clone(), orphan, oid, &[], &signer),
+            Err(Error::UnreachableBase { .. })
+        ));
+
+        let patch = patches
+            .create("Backport", "", target.clone(), head, oid, &[], &signer)
+            .unwrap();
+        let id = patch.id;
+
+        assert_eq!(patch.target(), &target);
+        assert!(patches.detect_merges(&signer).unwrap().is_empty());
+
+        // The patch is merged by pushing it to the release branch.
+        release(oid);
+
+        let merged = patches.detect_merges(&signer).unwrap();
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].0, id);
+    }
+
+    #[test]
+    fn test_patch_validate_base() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut patches = Patches::open(*signer.public_key(), &project).unwrap();
+        let (_, head) = project.canonical_head().unwrap();
+        let repo = &project.backend;
+        let parent = repo.find_commit(head.into()).unwrap();
+        let orphan = repo
+            .commit(
+                None,
+                &parent.author(),
+                &parent.committer(),
+                "Unrelated",
+                &parent.tree().unwrap(),
+                &[],
+            )
+            .unwrap();
+        let oid = git::Oid::from_str("518d5069f94c03427f694bb494ac1cd7d1339380").unwrap();
+        let target = MergeTarget::Branch(BranchName::try_from("master").unwrap());
+
+        // Bases of patches targeting the default branch aren't checked.
+        patches
+            .validate_base(&MergeTarget::Delegates, orphan.into())
+            .unwrap();
+        let mut patch = patches
+            .create(
+                "My first patch",
+                "",
+                MergeTarget::Delegates,
+                orphan,
+                oid,
+                &[],
+                &signer,
+            )
+            .unwrap();
+
+        // Retargeting the patch to a branch checks the base of its latest revision.
+        assert!(matches!(
+            patch.edit(
+                "My first patch".to_owned(),
+                String::new(),
+                target.clone(),
+                &signer
+            ),
+            Err(Error::UnreachableBase { .. })
+        ));
+        patch.update("Rebased", head, oid, &signer).unwrap();
+        patch
+            .edit(
+                "My first patch".to_owned(),
+                String::new(),
+                target.clone(),
+                &signer,
+            )
+            .unwrap();
+        assert_eq!(patch.target(), &target);
+
+        // New revisions of patches targeting a branch must be based on it.
+        assert!(matches!(
+            patch.update("Unrelated", orphan, oid, &signer),
+            Err(Error::UnreachableBase { .. })
+        ));
+        assert_eq!(patch.revisions().count(), 2);
+    }
 }
@@ -0,0 +1,541 @@
+use std::collections::BTreeMap;
+use std::ops::{ControlFlow, Deref};
+use std::str::FromStr;
+
+use once_cell::sync::Lazy;
+use radicle_crdt::clock;
+use radicle_crdt::{LWWReg, Max};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::cob::common::{Author, Authorization, Delegates};
+use crate::cob::{store, ActorId, ObjectId, OpId, TypeName};
+use crate::crypto;
+use crate::crypto::{PublicKey, Signature, Signer, Unverified, Verified};
+use crate::git;
+use crate::identity::project::{Doc, DocError, Identity, IdentityError, VerificationError};
+use crate::storage::git as storage;
+
+/// Identity proposal operation.
+pub type Op = crate::cob::Op<Action>;
+
+/// Type name of an identity proposal.
+pub static TYPENAME: Lazy<TypeName> =
+    Lazy::new(|| FromStr::from_str("xyz.radicle.id.proposal").expect("type name is valid"));
+
+/// Identifier for an identity proposal.
+pub type ProposalId = ObjectId;
+
+/// Error applying an operation onto a state.
+#[derive(Error, Debug)]
+pub enum ApplyError {
+    #[error("the first operation must be a proposal")]
+    Uninitialized,
+    #[error("proposal was already made")]
+    Initialized,
+    #[error("invalid proposed document: {0}")]
+    Verification(#[from] VerificationError),
+    #[error("document: {0}")]
+    Doc(#[from] DocError),
+    #[error("invalid signature by {0}: {1}")]
+    InvalidSignature(PublicKey, crypto::Error),
+    #[error("{0} is not authorized to perform this action")]
+    Unauthorized(ActorId),
+}
+
+/// Error updating, creating or committing proposals.
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("apply failed: {0}")]
+    Apply(#[from] ApplyError),
+    #[error("store: {0}")]
+    Store(#[from] store::Error),
+    #[error("identity: {0}")]
+    Identity(#[from] IdentityError),
+    #[error("identity document: {0}")]
+    Doc(#[from] DocError),
+    #[error("proposal is based on document `{0}`, but the current document is `{1}`")]
+    Stale(git::Oid, git::Oid),
+    #[error("quorum not reached: {0} signatures for a threshold of {1}")]
+    QuorumNotReached(usize, usize),
+}
+
+/// Proposal state.
+#[derive(Debug, Default, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "status")]
+pub enum Status {
+    /// The proposal is open for signatures.
+    #[default]
+    Open,
+    /// The proposal was closed without being committed.
+    Closed,
+    /// The proposed document was committed to an identity branch.
+    Committed,
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Open => write!(f, "open"),
+            Self::Closed => write!(f, "closed"),
+            Self::Committed => write!(f, "committed"),
+        }
+    }
+}
+
+/// A proposed change to a project's identity document, along with the signatures of
+/// the delegates who approve of it. Accumulates [`Action`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proposal {
+    /// Author of the proposal.
+    author: Author,
+    /// Proposal title, also used as the identity commit message.
+    title: String,
+    /// Proposal description.
+    description: String,
+    /// Current status of the proposal.
+    status: LWWReg<Max<Status>, clock::Lamport>,
+    /// Blob id of the identity document this proposal is meant to replace.
+    base: git::Oid,
+    /// The proposed identity document.
+    doc: Doc<Verified>,
+    /// The encoded proposed document, which is what is signed.
+    blob: Vec<u8>,
+    /// Signatures over the proposed document.
+    signatures: BTreeMap<PublicKey, Signature>,
+    /// Authorization of privileged actions on the proposal.
+    auth: Authorization,
+}
+
+impl store::FromHistory for Proposal {
+    type Action = Action;
+
+    fn type_name() -> &'static TypeName {
+        &*TYPENAME
+    }
+
+    fn from_history(
+        history: &radicle_cob::History,
+        delegates: &Delegates,
+    ) -> Result<(Self, clock::Lamport), store::Error> {
+        let auth = Authorization::from_history(history, delegates);
+        let obj = history.traverse(None, |acc: Option<Self>, entry| {
+            let Ok(op) = Op::try_from(entry) else {
+                return ControlFlow::Break(acc);
+            };
+            match acc {
+                None => match Self::proposed(op, auth.clone()) {
+                    Ok(proposal) => ControlFlow::Continue(Some(proposal)),
+                    Err(err) => {
+                        log::warn!("Error creating proposal state: {err}");
+                        ControlFlow::Break(None)
+                    }
+                },
+                Some(mut proposal) => {
+                    // Nb. We skip invalid operations instead of stopping, so that an invalid
+                    // signature can't prevent a proposal from being committed.
+                    match proposal.apply(op) {
+                        Ok(()) => {}
+                        Err(ApplyError::Unauthorized(actor)) => {
+                            log::debug!("Skipping unauthorized proposal op by {actor}");
+                        }
+                        Err(err) => {
+                            log::warn!("Error applying op to proposal state: {err}");
+                        }
+                    }
+                    ControlFlow::Continue(Some(proposal))
+                }
+            }
+        });
+        let obj = obj.ok_or_else(|| store::Error::InvalidHistory(TYPENAME.clone()))?;
+
+        Ok((obj, history.clock().into()))
+    }
+}
+
+impl Proposal {
+    /// Create the proposal state from the initial operation.
+    fn proposed(op: Op, auth: Authorization) -> Result<Self, ApplyError> {
+        let Action::Propose {
+            title,
+            description,
+            base,
+            doc,
+        } = op.action else {
+            return Err(ApplyError::Uninitialized);
+        };
+        let doc = doc.verified()?;
+        let (_, blob) = doc.encode()?;
+
+        Ok(Self {
+            author: Author::new(op.author),
+            title,
+            description,
+            status: LWWReg::new(Max::from(Status::default()), op.clock),
+            base,
+            doc,
+            blob,
+            signatures: BTreeMap::new(),
+            auth,
+        })
+    }
+
+    pub fn author(&self) -> &Author {
+        &self.author
+    }
+
+    pub fn title(&self) -> &str {
+        self.title.as_str()
+    }
+
+    pub fn description(&self) -> &str {
+        self.description.as_str()
+    }
+
+    pub fn status(&self) -> &Status {
+        self.status.get()
+    }
+
+    /// Blob id of the identity document this proposal is meant to replace.
+    pub fn base(&self) -> &git::Oid {
+        &self.base
+    }
+
+    /// The proposed identity document.
+    pub fn doc(&self) -> &Doc<Verified> {
+        &self.doc
+    }
+
+    /// All valid signatures over the proposed document.
+    pub fn signatures(&self) -> impl Iterator<Item = (&PublicKey, &Signature)> {
+        self.signatures.iter()
+    }
+
+    /// Signatures over the proposed document by delegates of the given, current document.
+    /// The proposal can be committed once there are at least as many as the current
+    /// document's threshold.
+    pub fn quorum<'a>(
+        &'a self,
+        current: &'a Doc<Verified>,
+    ) -> impl Iterator<Item = (&'a PublicKey, &'a Signature)> {
+        self.signatures
+            .iter()
+            .filter(|(key, _)| current.delegates.iter().any(|d| *d.id == **key))
+    }
+
+    /// Whether the given actor is allowed to carry out the given action. Closing or
+    /// committing a proposal is reserved to its author and to the project delegates.
+    pub fn authorized(&self, action: &Action, actor: &ActorId, identity: &git::Oid) -> bool {
+        if self.auth.is_revoked(actor, identity) {
+            return false;
+        }
+        match action {
+            Action::Lifecycle { .. } => self.auth.is_author_or_delegate(actor, identity),
+            Action::Propose { .. } | Action::Sign { .. } => true,
+        }
+    }
+
+    pub fn apply(&mut self, op: Op) -> Result<(), ApplyError> {
+        if !self.authorized(&op.action, &op.author, &op.identity) {
+            return Err(ApplyError::Unauthorized(op.author));
+        }
+        match op.action {
+            Action::Propose { .. } => {
+                return Err(ApplyError::Initialized);
+            }
+            Action::Sign { signature } => {
+                op.author
+                    .verify(&self.blob, &signature)
+                    .map_err(|e| ApplyError::InvalidSignature(op.author, e))?;
+                self.signatures.insert(op.author, signature);
+            }
+            Action::Lifecycle { status } => {
+                self.status.set(status, op.clock);
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct ProposalMut<'a, 'g> {
+    id: ObjectId,
+    clock: clock::Lamport,
+    proposal: Proposal,
+    store: &'g mut Proposals<'a>,
+}
+
+impl<'a, 'g> ProposalMut<'a, 'g> {
+    /// Get the proposal id.
+    pub fn id(&self) -> &ProposalId {
+        &self.id
+    }
+
+    /// Get the internal logical clock.
+    pub fn clock(&self) -> &clock::Lamport {
+        &self.clock
+    }
+
+    /// Sign the proposed document.
+    pub fn sign<G: Signer>(&mut self, signer: &G) -> Result<OpId, Error> {
+        let (_, signature) = self.proposal.doc.sign(signer)?;
+        let action = Action::Sign { signature };
+
+        self.apply("Sign", action, signer)
+    }
+
+    /// Lifecycle a proposal.
+    pub fn lifecycle<G: Signer>(&mut self, status: Status, signer: &G) -> Result<OpId, Error> {
+        let action = Action::Lifecycle { status };
+        self.apply("Lifecycle", action, signer)
+    }
+
+    /// Commit the proposed document to the signer's identity branch, if a quorum of the
+    /// current delegates signed it. Returns the new head of the identity branch.
+    pub fn commit<G: Signer>(&mut self, signer: &G) -> Result<git::Oid, Error> {
+        let remote = signer.public_key();
+        let action = Action::Lifecycle {
+            status: Status::Committed,
+        };
+        if !self
+            .proposal
+            .authorized(&action, remote, self.store.identity())
+        {
+            return Err(ApplyError::Unauthorized(*remote).into());
+        }
+        let repo: &storage::Repository = self.store.raw.as_ref();
+        let current = Identity::load(remote, repo)?;
+
+        if current.current != self.proposal.base {
+            return Err(Error::Stale(self.proposal.base, current.current));
+        }
+        let signatures = self
+            .proposal
+            .quorum(&current.doc)
+            .map(|(key, sig)| (key, *sig))
+            .collect::<Vec<_>>();
+
+        if signatures.len() < current.doc.threshold {
+            return Err(Error::QuorumNotReached(
+                signatures.len(),
+                current.doc.threshold,
+            ));
+        }
+        let head = self
+            .proposal
+            .doc
+            .update(remote, &self.proposal.title, &signatures, repo)?;
+
+        self.lifecycle(Status::Committed, signer)?;
+
+        Ok(head)
+    }
+
+    /// Apply an op to the proposal.
+    pub fn apply<G: Signer>(
+        &mut self,
+        msg: &'static str,
+        action: Action,
+        signer: &G,
+    ) -> Result<OpId, Error> {
+        if !self
+            .proposal
+            .authorized(&action, signer.public_key(), self.store.identity())
+        {
+            return Err(ApplyError::Unauthorized(*signer.public_key()).into());
+        }
+        let cob = self.store.update(self.id, msg, action.clone(), signer)?;
+        let clock = cob.history().clock().into();
+        let timestamp = cob.history().timestamp().into();
+        let op = Op {
+            action,
+            author: *signer.public_key(),
+            clock,
+            timestamp,
+            identity: *self.store.identity(),
+        };
+        self.proposal.apply(op)?;
+
+        Ok((clock, *signer.public_key()))
+    }
+}
+
+impl<'a, 'g> Deref for ProposalMut<'a, 'g> {
+    type Target = Proposal;
+
+    fn deref(&self) -> &Self::Target {
+        &self.proposal
+    }
+}
+
+pub struct Proposals<'a> {
+    raw: store::Store<'a, Proposal>,
+}
+
+impl<'a> Deref for Proposals<'a> {
+    type Target = store::Store<'a, Proposal>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.raw
+    }
+}
+
+impl<'a> Proposals<'a> {
+    /// Open an identity proposals store.
+    pub fn open(
+        whoami: PublicKey,
+        repository: &'a storage::Repository,
+    ) -> Result<Self, store::Error> {
+        let raw = store::Store::open(whoami, repository)?;
+
+        Ok(Self { raw })
+    }
+
+    /// Get a proposal.
+    pub fn get(&self, id: &ObjectId) -> Result<Option<Proposal>, store::Error> {
+        self.raw.get(id).map(|r| r.map(|(p, _clock)| p))
+    }
+
+    /// Get a proposal mutably.
+    pub fn get_mut<'g>(&'g mut self, id: &ObjectId) -> Result<ProposalMut<'a, 'g>, store::Error> {
+        let (proposal, clock) = self
+            .raw
+            .get(id)?
+            .ok_or_else(move || store::Error::NotFound(TYPENAME.clone(), *id))?;
+
+        Ok(ProposalMut {
+            id: *id,
+            clock,
+            proposal,
+            store: self,
+        })
+    }
+
+    /// Propose a new identity document, to replace the signer's current document.
+    /// The proposal is signed by the proposer.
+    pub fn create<'g, G: Signer>(
+        &'g mut self,
+        title: impl Into<String>,
+        description: impl Into<String>,
+        doc: Doc<Verified>,
+        signer: &G,
+    ) -> Result<ProposalMut<'a, 'g>, Error> {
+        let repo: &storage::Repository = self.raw.as_ref();
+        let base = Identity::load(signer.public_key(), repo)?.current;
+        let action = Action::Propose {
+            title: title.into(),
+            description: description.into(),
+            base,
+            doc: doc.unverified(),
+        };
+        let (id, proposal, clock) = self.raw.create("Propose identity", action, signer)?;
+        let mut proposal = ProposalMut {
+            id,
+            clock,
+            proposal,
+            store: self,
+        };
+        proposal.sign(signer)?;
+
+        Ok(proposal)
+    }
+
+    /// Remove a proposal.
+    pub fn remove(&self, id: &ObjectId) -> Result<(), store::Error> {
+        self.raw.remove(id)
+    }
+}
+
+/// Identity proposal operation.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Action {
+    Propose {
+        title: String,
+        description: String,
+        base: git::Oid,
+        doc: Doc<Unverified>,
+    },
+    Sign {
+        signature: Signature,
+    },
+    Lifecycle {
+        status: Status,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::crypto::test::signer::MockSigner;
+    use crate::rad;
+    use crate::test;
+
+    #[test]
+    fn test_proposal_quorum() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (storage, alice, repo) = test::setup::context(&tmp);
+        let mut rng = fastrand::Rng::new();
+        let bob = MockSigner::new(&mut rng);
+        let eve = MockSigner::new(&mut rng);
+
+        // Add Bob as a delegate, with a threshold of two.
+        let mut doc = repo.project_of(alice.public_key()).unwrap();
+        doc.delegate("bob".to_owned(), *bob.public_key());
+        doc.threshold = 2;
+        let (_, sig) = doc.sign(&alice).unwrap();
+        doc.update(
+            alice.public_key(),
+            "Add bob",
+            &[(alice.public_key(), sig)],
+            &repo,
+        )
+        .unwrap();
+        rad::fork(repo.id, Some(alice.public_key()), &bob, &storage).unwrap();
+
+        // Alice proposes to add Eve as a delegate.
+        let mut proposed = doc.clone();
+        proposed.delegate("eve".to_owned(), *eve.public_key());
+
+        let mut proposals = Proposals::open(*alice.public_key(), &repo).unwrap();
+        let mut proposal = proposals
+            .create("Add eve", "Eve is great", proposed.clone(), &alice)
+            .unwrap();
+        let id = *proposal.id();
+
+        assert_eq!(proposal.quorum(&doc).count(), 1);
+        assert!(matches!(
+            proposal.commit(&alice),
+            Err(Error::QuorumNotReached(1, 2))
+        ));
+
+        // Eve's signature doesn't count, since she isn't a delegate yet.
+        let mut eve_proposals = Proposals::open(*eve.public_key(), &repo).unwrap();
+        let mut eve_proposal = eve_proposals.get_mut(&id).unwrap();
+        eve_proposal.sign(&eve).unwrap();
+
+        // Nor may she close it, since she's neither its author nor a delegate.
+        assert!(matches!(
+            eve_proposal.lifecycle(Status::Closed, &eve),
+            Err(Error::Apply(ApplyError::Unauthorized(_)))
+        ));
+        assert_eq!(eve_proposal.status(), &Status::Open);
+
+        // Bob signs the proposal.
+        let mut bob_proposals = Proposals::open(*bob.public_key(), &repo).unwrap();
+        bob_proposals.get_mut(&id).unwrap().sign(&bob).unwrap();
+
+        let mut proposal = proposals.get_mut(&id).unwrap();
+        assert_eq!(proposal.signatures().count(), 3);
+        assert_eq!(proposal.quorum(&doc).count(), 2);
+
+        let head = proposal.commit(&alice).unwrap();
+        assert_eq!(proposal.status(), &Status::Committed);
+
+        let identity = Identity::load(alice.public_key(), &repo).unwrap();
+        assert_eq!(identity.head, head);
+        assert_eq!(identity.doc, proposed);
+        assert_eq!(identity.signatures.len(), 2);
+    }
+}
@@ -6,7 +6,7 @@ use radicle_crdt::Lamport;
 use serde::Serialize;
 
 use crate::cob;
-use crate::cob::common::Author;
+use crate::cob::common::{Author, Delegates};
 use crate::cob::CollaborativeObject;
 use crate::cob::{Create, History, ObjectId, TypeName, Update};
 use crate::crypto::PublicKey;
@@ -25,8 +25,10 @@ pub trait FromHistory: Sized {
 
     /// The object type name.
     fn type_name() -> &'static TypeName;
-    /// Create an object from a history.
-    fn from_history(history: &History) -> Result<(Self, Lamport), Error>;
+    /// Create an object from a history. Privileged actions are authorized against the
+    /// author of the object, and the project delegates of the identity version each action
+    /// was made against. See [`identity`].
+    fn from_history(history: &History, delegates: &Delegates) -> Result<(Self, Lamport), Error>;
 }
 
 /// Store error.
@@ -43,17 +45,31 @@ pub enum Error {
     #[error(transparent)]
     Identity(#[from] project::IdentityError),
     #[error(transparent)]
+    Project(#[from] storage::ProjectError),
+    #[error(transparent)]
     Serialize(#[from] serde_json::Error),
     #[error("unexpected history type '{0}'")]
     HistoryType(String),
     #[error("object `{1}` of type `{0}` was not found")]
     NotFound(TypeName, ObjectId),
+    #[error("object of type `{0}` has an invalid history")]
+    InvalidHistory(TypeName),
+}
+
+/// Load the canonical identity of a project, ie. the one at the head of the longest identity
+/// branch. Objects are authorized against the history of this identity, and new changes are
+/// made against its current version.
+pub fn identity(repo: &storage::Repository) -> Result<project::Identity<git::Oid>, Error> {
+    let (head, _) = repo.project()?;
+
+    project::Identity::load_at(head, repo).map_err(Error::from)
 }
 
 /// Storage for collaborative objects of a specific type `T` in a single project.
 pub struct Store<'a, T> {
     whoami: PublicKey,
     project: project::Identity<git::Oid>,
+    delegates: Delegates,
     raw: &'a storage::Repository,
     witness: PhantomData<T>,
 }
@@ -67,10 +83,12 @@ impl<'a, T> AsRef<storage::Repository> for Store<'a, T> {
 impl<'a, T> Store<'a, T> {
     /// Open a new generic store.
     pub fn open(whoami: PublicKey, store: &'a storage::Repository) -> Result<Self, Error> {
-        let project = project::Identity::load(&whoami, store)?;
+        let project = identity(store)?;
+        let delegates = Delegates::from(&project);
 
         Ok(Self {
             project,
+            delegates,
             whoami,
             raw: store,
             witness: PhantomData,
@@ -86,6 +104,17 @@ impl<'a, T> Store<'a, T> {
     pub fn public_key(&self) -> &PublicKey {
         &self.whoami
     }
+
+    /// Get the project delegates, against which object actions are authorized. See
+    /// [`FromHistory::from_history`].
+    pub fn delegates(&self) -> &Delegates {
+        &self.delegates
+    }
+
+    /// Get the version of the project identity document that new changes are made against.
+    pub fn identity(&self) -> &git::Oid {
+        &self.project.current
+    }
 }
 
 impl<'a, T: FromHistory> Store<'a, T>
@@ -138,7 +167,7 @@ where
                 contents,
             },
         )?;
-        let (object, clock) = T::from_history(cob.history())?;
+        let (object, clock) = T::from_history(cob.history(), &self.delegates)?;
 
         Ok((*cob.id(), object, clock))
     }
@@ -151,7 +180,7 @@ where
             if cob.manifest().history_type != HISTORY_TYPE {
                 return Err(Error::HistoryType(cob.manifest().history_type.clone()));
             }
-            let (obj, clock) = T::from_history(cob.history())?;
+            let (obj, clock) = T::from_history(cob.history(), &self.delegates)?;
 
             Ok(Some((obj, clock)))
         } else {
@@ -164,9 +193,10 @@ where
         &self,
     ) -> Result<impl Iterator<Item = Result<(ObjectId, T, Lamport), Error>>, Error> {
         let raw = cob::list(self.raw, T::type_name())?;
+        let delegates = self.delegates.clone();
 
-        Ok(raw.into_iter().map(|o| {
-            let (obj, clock) = T::from_history(o.history())?;
+        Ok(raw.into_iter().map(move |o| {
+            let (obj, clock) = T::from_history(o.history(), &delegates)?;
             Ok((*o.id(), obj, clock))
         }))
     }
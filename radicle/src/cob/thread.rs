@@ -9,13 +9,14 @@ use radicle_crdt as crdt;
 use serde::{Deserialize, Serialize};
 
 use crate::cob;
-use crate::cob::common::{Reaction, Timestamp};
+use crate::cob::common::{Delegates, Reaction, Timestamp};
 use crate::cob::store;
 use crate::cob::{ActorId, History, Op, OpId, TypeName};
 use crate::crypto::Signer;
 
 use crdt::clock::Lamport;
 use crdt::lwwset::LWWSet;
+use crdt::ord::Max;
 use crdt::redactable::Redactable;
 use crdt::Semilattice;
 
@@ -29,7 +30,7 @@ pub type CommentId = OpId;
 /// A comment on a discussion thread.
 #[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Comment {
-    /// The comment body.
+    /// The comment body, as of the latest edit.
     pub body: String,
     /// Thread or comment this is a reply to.
     pub reply_to: Option<OpId>,
@@ -48,6 +49,15 @@ impl Comment {
     }
 }
 
+/// A version of a comment body. The first version of a comment is its original body.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Edit {
+    /// The comment body.
+    pub body: String,
+    /// When the edit was authored.
+    pub timestamp: Timestamp,
+}
+
 impl PartialOrd for Comment {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         if self == other {
@@ -69,7 +79,15 @@ pub enum Action {
         /// Another comment this is a reply to.
         reply_to: Option<OpId>,
     },
-    /// Redact a change. Not all changes can be redacted.
+    /// Edit a comment. Only the author of a comment can edit it.
+    Edit {
+        /// Comment being edited.
+        id: CommentId,
+        /// New comment body.
+        body: String,
+    },
+    /// Redact a change. Not all changes can be redacted, and only the author of a change
+    /// can redact it.
     Redact { id: OpId },
     /// React to a change.
     React {
@@ -84,6 +102,8 @@ pub enum Action {
 pub struct Thread {
     /// The comments under the thread.
     comments: BTreeMap<CommentId, Redactable<Comment>>,
+    /// The versions of each comment, including the original.
+    edits: BTreeMap<CommentId, BTreeMap<OpId, Max<Edit>>>,
     /// Reactions to changes.
     reactions: BTreeMap<CommentId, LWWSet<(ActorId, Reaction), Lamport>>,
 }
@@ -95,7 +115,10 @@ impl store::FromHistory for Thread {
         &*TYPENAME
     }
 
-    fn from_history(history: &History) -> Result<(Self, Lamport), store::Error> {
+    fn from_history(
+        history: &History,
+        _delegates: &Delegates,
+    ) -> Result<(Self, Lamport), store::Error> {
         let obj = history.traverse(Thread::default(), |mut acc, entry| {
             if let Ok(change) = Op::try_from(entry) {
                 acc.apply([change]);
@@ -109,7 +132,14 @@ impl store::FromHistory for Thread {
 }
 
 impl Semilattice for Thread {
-    fn merge(&mut self, other: Self) {
+    fn merge(&mut self, mut other: Self) {
+        self.edits.merge(other.edits.clone());
+        // Nb. Comment bodies are derived from the edits, so they have to be brought up to date
+        // on both sides before the comments are merged.
+        other.edits = self.edits.clone();
+        self.refresh_all();
+        other.refresh_all();
+
         self.comments.merge(other.comments);
         self.reactions.merge(other.reactions);
     }
@@ -126,6 +156,7 @@ impl Deref for Thread {
 impl Thread {
     pub fn clear(&mut self) {
         self.comments.clear();
+        self.edits.clear();
     }
 
     pub fn comment(&self, id: &CommentId) -> Option<&Comment> {
@@ -158,6 +189,15 @@ impl Thread {
         })
     }
 
+    /// The versions of a comment body, oldest first. The first version is the original body.
+    pub fn edits<'a>(&'a self, id: &'a CommentId) -> impl Iterator<Item = &Edit> {
+        self.edits
+            .get(id)
+            .into_iter()
+            .flat_map(|edits| edits.values())
+            .map(|e| e.get())
+    }
+
     pub fn reactions<'a>(
         &'a self,
         to: &'a CommentId,
@@ -175,6 +215,14 @@ impl Thread {
 
             match change.action {
                 Action::Comment { body, reply_to } => {
+                    self.edits.entry(id).or_default().insert(
+                        id,
+                        Max::from(Edit {
+                            body,
+                            timestamp: change.timestamp,
+                        }),
+                    );
+                    let body = self.latest(&id).unwrap_or_default().to_owned();
                     let present =
                         Redactable::Present(Comment::new(body, reply_to, change.timestamp));
 
@@ -187,7 +235,25 @@ impl Thread {
                         }
                     }
                 }
+                Action::Edit { id: comment, body } => {
+                    // Only the author of a comment can edit it.
+                    if change.author != comment.1 {
+                        continue;
+                    }
+                    self.edits.entry(comment).or_default().insert(
+                        id,
+                        Max::from(Edit {
+                            body,
+                            timestamp: change.timestamp,
+                        }),
+                    );
+                    self.refresh(&comment);
+                }
                 Action::Redact { id } => {
+                    // Only the author of a comment can redact it.
+                    if change.author != id.1 {
+                        continue;
+                    }
                     self.comments
                         .entry(id)
                         .and_modify(|e| e.merge(Redactable::Redacted))
@@ -221,6 +287,32 @@ impl Thread {
         }
     }
 
+    /// The latest version of a comment body, if any.
+    fn latest(&self, id: &CommentId) -> Option<&str> {
+        self.edits
+            .get(id)
+            .and_then(|edits| edits.values().next_back())
+            .map(|e| e.get().body.as_str())
+    }
+
+    /// Bring the body of a comment up to date with its latest edit.
+    fn refresh(&mut self, id: &CommentId) {
+        let Some(body) = self.latest(id).map(ToOwned::to_owned) else {
+            return;
+        };
+        if let Some(Redactable::Present(comment)) = self.comments.get_mut(id) {
+            comment.body = body;
+        }
+    }
+
+    /// Bring the bodies of all comments up to date.
+    fn refresh_all(&mut self) {
+        let ids = self.comments.keys().copied().collect::<Vec<_>>();
+        for id in ids {
+            self.refresh(&id);
+        }
+    }
+
     pub fn comments(&self) -> impl Iterator<Item = (&CommentId, &Comment)> + '_ {
         self.comments.iter().filter_map(|(id, comment)| {
             if let Redactable::Present(c) = comment {
@@ -265,6 +357,14 @@ impl<G: Signer> Actor<G> {
         })
     }
 
+    /// Create a new comment edit.
+    pub fn edit(&mut self, id: CommentId, body: &str) -> Op<Action> {
+        self.op(Action::Edit {
+            id,
+            body: String::from(body),
+        })
+    }
+
     /// Create a new redaction.
     pub fn redact(&mut self, id: OpId) -> Op<Action> {
         self.op(Action::Redact { id })
@@ -296,6 +396,7 @@ mod tests {
 
     use super::*;
     use crate as radicle;
+    use crate::git;
     use crdt::test::{assert_laws, WeightedGenerator};
 
     #[derive(Clone)]
@@ -355,6 +456,20 @@ mod tests {
                         let id = changes[rng.usize(..changes.len())];
 
                         Some((clock.tick(), Action::Redact { id }))
+                    })
+                    .variant(2, |(clock, changes), rng| {
+                        if changes.is_empty() {
+                            return None;
+                        }
+                        let id = changes[rng.usize(..changes.len())];
+
+                        Some((
+                            clock.tick(),
+                            Action::Edit {
+                                id,
+                                body: iter::repeat_with(|| rng.alphabetic()).take(16).collect(),
+                            },
+                        ))
                     });
 
             let mut changes = Vec::new();
@@ -367,6 +482,7 @@ mod tests {
                     author,
                     clock,
                     timestamp,
+                    identity: git::raw::Oid::zero().into(),
                 });
             }
 
@@ -415,6 +531,42 @@ mod tests {
         assert_eq!(comment1.body, "Third comment"); // Second comment was redacted.
     }
 
+    #[test]
+    fn test_edit_comment() {
+        let mut alice = Actor::<MockSigner>::default();
+        let mut bob = Actor::<MockSigner>::default();
+
+        let a1 = alice.comment("First commnet", None);
+        let b1 = bob.comment("Bob's comment", None);
+        let a2 = alice.edit(a1.id(), "First comment");
+        let b2 = bob.edit(a1.id(), "Bob was here");
+        let a3 = alice.edit(a1.id(), "First comment, edited twice");
+
+        let mut expected = Thread::default();
+        expected.apply([a1.clone(), b1.clone(), a2.clone(), b2.clone(), a3.clone()]);
+
+        let mut actual = Thread::default();
+        actual.apply([a3, b2, a2, b1, a1.clone()]);
+
+        assert_eq!(actual, expected);
+        assert_eq!(
+            actual.comment(&a1.id()).unwrap().body,
+            "First comment, edited twice"
+        );
+        assert_eq!(
+            actual
+                .edits(&a1.id())
+                .map(|e| e.body.as_str())
+                .collect::<Vec<_>>(),
+            // Bob's edit is ignored, since he isn't the author of the comment.
+            vec![
+                "First commnet",
+                "First comment",
+                "First comment, edited twice"
+            ]
+        );
+    }
+
     #[test]
     fn test_storage() {
         let tmp = tempfile::tempdir().unwrap();
@@ -0,0 +1,736 @@
+//! Cross-references between issues, patches and commits.
+//!
+//! References are full object ids mentioned in issue titles and comments, patch titles,
+//! descriptions and comments, and commit messages. Every repository keeps an index of these
+//! references, so that objects can list what mentions them.
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::Write as _;
+use std::str::FromStr;
+use std::{fmt, fs, io};
+
+use thiserror::Error;
+
+use crate::cob;
+use crate::cob::common::Delegates;
+use crate::cob::issue::{CloseReason, Issue, IssueId, Issues, Status};
+use crate::cob::patch::{Patch, PatchId};
+use crate::cob::store::FromHistory as _;
+use crate::cob::{issue, patch, store};
+use crate::crypto::Signer;
+use crate::git;
+use crate::storage;
+use crate::storage::git::Repository;
+use crate::storage::ReadRepository;
+
+/// Name of the index file, in the repository's git directory.
+pub const INDEX_FILE: &str = "xrefs";
+
+/// Keywords that close the issue they precede, when used in a merged patch revision.
+pub const CLOSING_KEYWORDS: [&str; 9] = [
+    "close", "closes", "closed", "fix", "fixes", "fixed", "resolve", "resolves", "resolved",
+];
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("git: {0}")]
+    Git(#[from] git2::Error),
+    #[error("i/o: {0}")]
+    Io(#[from] io::Error),
+    #[error("storage: {0}")]
+    Storage(#[from] storage::Error),
+    #[error("store: {0}")]
+    Store(#[from] store::Error),
+    #[error("retrieve error: {0}")]
+    Retrieve(#[from] cob::error::Retrieve),
+    #[error("issue: {0}")]
+    Issue(#[from] issue::Error),
+    #[error("invalid index entry `{0}`")]
+    InvalidEntry(String),
+}
+
+/// An object that can mention, or be mentioned by other objects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Reference {
+    /// An issue.
+    Issue(IssueId),
+    /// A patch.
+    Patch(PatchId),
+    /// A commit.
+    Commit(git::Oid),
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Issue(id) => write!(f, "issue:{id}"),
+            Self::Patch(id) => write!(f, "patch:{id}"),
+            Self::Commit(oid) => write!(f, "commit:{oid}"),
+        }
+    }
+}
+
+impl FromStr for Reference {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidEntry(s.to_owned());
+        let (kind, id) = s.split_once(':').ok_or_else(invalid)?;
+
+        match kind {
+            "issue" => IssueId::from_str(id)
+                .map(Self::Issue)
+                .map_err(|_| invalid()),
+            "patch" => PatchId::from_str(id)
+                .map(Self::Patch)
+                .map_err(|_| invalid()),
+            "commit" => git::Oid::from_str(id)
+                .map(Self::Commit)
+                .map_err(|_| invalid()),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Extract the object ids mentioned in the given text, in order of appearance.
+pub fn extract(text: &str) -> Vec<git::Oid> {
+    let mut oids = Vec::new();
+
+    for word in text.split(|c: char| !c.is_ascii_alphanumeric()) {
+        if let Some(oid) = oid(word) {
+            if !oids.contains(&oid) {
+                oids.push(oid);
+            }
+        }
+    }
+    oids
+}
+
+/// Extract the object ids preceded by a closing keyword in the given text, eg. `Fixes <id>`.
+pub fn closing(text: &str) -> Vec<git::Oid> {
+    let mut oids = Vec::new();
+    let mut words = text.split_whitespace().peekable();
+
+    while let Some(word) = words.next() {
+        let word = word.trim_end_matches(':').to_lowercase();
+
+        if !CLOSING_KEYWORDS.contains(&word.as_str()) {
+            continue;
+        }
+        if let Some(oid) = words.peek().and_then(|w| extract(w).into_iter().next()) {
+            if !oids.contains(&oid) {
+                oids.push(oid);
+            }
+        }
+    }
+    oids
+}
+
+/// Backlink index of a repository.
+///
+/// The index is updated incrementally: objects are only re-read when their tips change, and
+/// only the commits added to the canonical head since it was last indexed are read.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Index {
+    /// Canonical head whose history was indexed.
+    head: Option<git::Oid>,
+    /// Indexed issues and patches, and a digest of their tips.
+    objects: BTreeMap<Reference, git::Oid>,
+    /// Indexed commits that mention other objects, by the patch they are part of, or `None`
+    /// for the history of the canonical head.
+    commits: BTreeMap<Option<PatchId>, BTreeSet<git::Oid>>,
+    /// Objects, and the object ids they mention.
+    mentions: BTreeMap<Reference, BTreeSet<git::Oid>>,
+    /// Mentioned objects, and the objects that mention them.
+    backlinks: BTreeMap<Reference, BTreeSet<Reference>>,
+}
+
+impl Index {
+    /// Build the index of a repository from scratch.
+    pub fn build(repo: &Repository) -> Result<Self, Error> {
+        let mut index = Self::default();
+
+        index.update(repo)?;
+        index.link(repo);
+
+        Ok(index)
+    }
+
+    /// Load the index of a repository, and bring it up to date with the repository.
+    pub fn refresh(repo: &Repository) -> Result<Self, Error> {
+        let mut index = match Self::load(repo) {
+            Ok(index) => index,
+            Err(Error::InvalidEntry(entry)) => {
+                log::warn!("Rebuilding cross-reference index: invalid entry `{entry}`");
+                Self::default()
+            }
+            Err(err) => return Err(err),
+        };
+        if index.update(repo)? {
+            index.link(repo);
+            index.save(repo)?;
+        }
+        Ok(index)
+    }
+
+    /// Load the index of a repository, as it was last saved.
+    pub fn load(repo: &Repository) -> Result<Self, Error> {
+        let contents = match fs::read_to_string(repo.path().join(INDEX_FILE)) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err.into()),
+        };
+        let mut index = Self::default();
+
+        for line in contents.lines() {
+            let invalid = || Error::InvalidEntry(line.to_owned());
+            let parse_oid = |s: &str| git::Oid::from_str(s).map_err(|_| invalid());
+
+            match line.split(' ').collect::<Vec<_>>().as_slice() {
+                ["head", oid] => {
+                    index.head = Some(parse_oid(oid)?);
+                }
+                ["object", object, tips] => {
+                    index.objects.insert(object.parse()?, parse_oid(tips)?);
+                }
+                ["commit", "head", oid] => {
+                    index
+                        .commits
+                        .entry(None)
+                        .or_default()
+                        .insert(parse_oid(oid)?);
+                }
+                ["commit", patch, oid] => {
+                    let Reference::Patch(id) = patch.parse::<Reference>()? else {
+                        return Err(invalid());
+                    };
+                    index
+                        .commits
+                        .entry(Some(id))
+                        .or_default()
+                        .insert(parse_oid(oid)?);
+                }
+                ["mention", source, oid] => {
+                    index
+                        .mentions
+                        .entry(source.parse()?)
+                        .or_default()
+                        .insert(parse_oid(oid)?);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        index.link(repo);
+
+        Ok(index)
+    }
+
+    /// Save the index in the repository. The index is written to a temporary file first,
+    /// which then replaces the index, so that it is never left partially written.
+    pub fn save(&self, repo: &Repository) -> Result<(), Error> {
+        let mut contents = String::new();
+
+        if let Some(head) = self.head {
+            contents.push_str(&format!("head {head}\n"));
+        }
+        for (object, tips) in &self.objects {
+            contents.push_str(&format!("object {object} {tips}\n"));
+        }
+        for (owner, commits) in &self.commits {
+            let owner = match owner {
+                Some(id) => Reference::Patch(*id).to_string(),
+                None => String::from("head"),
+            };
+            for oid in commits {
+                contents.push_str(&format!("commit {owner} {oid}\n"));
+            }
+        }
+        for (source, oids) in &self.mentions {
+            for oid in oids {
+                contents.push_str(&format!("mention {source} {oid}\n"));
+            }
+        }
+        let mut file = tempfile::NamedTempFile::new_in(repo.path())?;
+        file.write_all(contents.as_bytes())?;
+        file.persist(repo.path().join(INDEX_FILE))
+            .map_err(|e| e.error)?;
+
+        Ok(())
+    }
+
+    /// Objects that mention the given object.
+    pub fn backlinks(&self, target: &Reference) -> impl Iterator<Item = &Reference> {
+        self.backlinks.get(target).into_iter().flatten()
+    }
+
+    /// Re-read the objects whose tips changed, and the commits added to the canonical head,
+    /// and drop what was removed. Objects that can't be decoded are skipped.
+    ///
+    /// Returns whether anything changed.
+    fn update(&mut self, repo: &Repository) -> Result<bool, Error> {
+        let delegates = Delegates::from(&store::identity(repo)?);
+        let objects = tips(repo)?;
+        let mut changed = false;
+
+        let removed = self
+            .objects
+            .keys()
+            .filter(|object| !objects.contains_key(object))
+            .copied()
+            .collect::<Vec<_>>();
+        for object in removed {
+            self.remove(&object);
+            changed = true;
+        }
+        for (object, tips) in objects {
+            if self.objects.get(&object) == Some(&tips) {
+                continue;
+            }
+            self.remove(&object);
+            self.objects.insert(object, tips);
+
+            if let Err(err) = self.index_object(repo, object, &delegates) {
+                log::warn!("Skipping {object} in cross-reference index: {err}");
+            }
+            changed = true;
+        }
+
+        let head = repo.canonical_head().ok().map(|(_, head)| head);
+        if head != self.head {
+            let mut walk = repo.backend.revwalk()?;
+
+            if let Some(head) = head {
+                walk.push(head.into())?;
+            }
+            match (self.head, head) {
+                (Some(old), Some(new))
+                    if repo.backend.graph_descendant_of(new.into(), old.into())? =>
+                {
+                    walk.hide(old.into())?;
+                }
+                _ => {
+                    self.commits.remove(&None);
+                }
+            }
+            if head.is_some() {
+                for oid in walk {
+                    self.index_commit(repo, oid?.into(), None)?;
+                }
+            }
+            self.head = head;
+            changed = true;
+        }
+
+        if changed {
+            // Drop the mentions of commits that are no longer part of a patch or the
+            // canonical history.
+            let commits = self.commits.values().flatten().collect::<HashSet<_>>();
+
+            self.mentions.retain(|source, _| match source {
+                Reference::Commit(oid) => commits.contains(oid),
+                _ => true,
+            });
+        }
+        Ok(changed)
+    }
+
+    /// Index the mentions of an issue or patch, and the commits of a patch's revisions.
+    fn index_object(
+        &mut self,
+        repo: &Repository,
+        object: Reference,
+        delegates: &Delegates,
+    ) -> Result<(), Error> {
+        match object {
+            Reference::Issue(id) => {
+                let Some(obj) = cob::get(repo, &*issue::TYPENAME, &id)? else {
+                    return Ok(());
+                };
+                let (issue, _) = Issue::from_history(obj.history(), delegates)?;
+                let texts = [issue.title()]
+                    .into_iter()
+                    .chain(issue.comments().map(|(_, c)| c.body.as_str()));
+
+                for oid in texts.flat_map(extract) {
+                    self.mention(object, oid);
+                }
+            }
+            Reference::Patch(id) => {
+                let Some(obj) = cob::get(repo, &*patch::TYPENAME, &id)? else {
+                    return Ok(());
+                };
+                let (patch, _) = Patch::from_history(obj.history(), delegates)?;
+                let texts =
+                    [patch.title(), patch.description().unwrap_or_default()]
+                        .into_iter()
+                        .chain(patch.revisions().flat_map(|(_, r)| {
+                            r.discussion.comments().map(|(_, c)| c.body.as_str())
+                        }));
+
+                for oid in texts.flat_map(extract) {
+                    self.mention(object, oid);
+                }
+                for (_, revision) in patch.revisions() {
+                    for oid in revision_commits(repo, &revision.base, &revision.oid)? {
+                        self.index_commit(repo, oid, Some(id))?;
+                    }
+                }
+            }
+            Reference::Commit(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Index the mentions of a commit, found in the given patch or the canonical history.
+    fn index_commit(
+        &mut self,
+        repo: &Repository,
+        oid: git::Oid,
+        owner: Option<PatchId>,
+    ) -> Result<(), Error> {
+        let commit = repo.backend.find_commit(oid.into())?;
+        let mentions = extract(commit.message().unwrap_or_default())
+            .into_iter()
+            .filter(|mentioned| *mentioned != oid)
+            .collect::<Vec<_>>();
+
+        if mentions.is_empty() {
+            return Ok(());
+        }
+        self.commits.entry(owner).or_default().insert(oid);
+
+        for mentioned in mentions {
+            self.mention(Reference::Commit(oid), mentioned);
+        }
+        Ok(())
+    }
+
+    /// Remove an issue or patch from the index.
+    fn remove(&mut self, object: &Reference) {
+        self.objects.remove(object);
+        self.mentions.remove(object);
+
+        if let Reference::Patch(id) = object {
+            self.commits.remove(&Some(*id));
+        }
+    }
+
+    fn mention(&mut self, source: Reference, oid: git::Oid) {
+        self.mentions.entry(source).or_default().insert(oid);
+    }
+
+    /// Resolve the mentioned object ids, and compute the backlinks from them.
+    fn link(&mut self, repo: &Repository) {
+        let resolve = |oid: git::Oid| -> Option<Reference> {
+            let id = cob::ObjectId::from(oid);
+
+            if self.objects.contains_key(&Reference::Issue(id)) {
+                Some(Reference::Issue(id))
+            } else if self.objects.contains_key(&Reference::Patch(id)) {
+                Some(Reference::Patch(id))
+            } else if repo.backend.find_commit(oid.into()).is_ok() {
+                Some(Reference::Commit(oid))
+            } else {
+                None
+            }
+        };
+        let mut backlinks = BTreeMap::<_, BTreeSet<_>>::new();
+
+        for (source, oids) in &self.mentions {
+            for oid in oids {
+                match resolve(*oid) {
+                    Some(target) if target != *source => {
+                        backlinks.entry(target).or_default().insert(*source);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        self.backlinks = backlinks;
+    }
+}
+
+/// Mark the open issues that are closed by a merged revision of the given patch as solved.
+/// Closing keywords are looked for in the patch description, and in the descriptions and
+/// commit messages of merged revisions.
+///
+/// Returns the issues that were solved.
+pub fn solve<G: Signer>(
+    repo: &Repository,
+    issues: &mut Issues,
+    patch: &Patch,
+    signer: &G,
+) -> Result<Vec<IssueId>, Error> {
+    let mut closed = Vec::new();
+
+    for (_, revision) in patch.revisions() {
+        if revision.merges.is_empty() {
+            continue;
+        }
+        closed.extend(closing(patch.description().unwrap_or_default()));
+        closed.extend(closing(revision.description().unwrap_or_default()));
+
+        for oid in revision_commits(repo, &revision.base, &revision.oid)? {
+            let commit = repo.backend.find_commit(oid.into())?;
+            closed.extend(closing(commit.message().unwrap_or_default()));
+        }
+    }
+
+    let mut solved = Vec::new();
+    for oid in closed {
+        let id = IssueId::from(oid);
+
+        if solved.contains(&id) {
+            continue;
+        }
+        let mut issue = match issues.get_mut(&id) {
+            Ok(issue) => issue,
+            Err(store::Error::NotFound(_, _)) => continue,
+            Err(err) => return Err(err.into()),
+        };
+        if *issue.status() != Status::Open {
+            continue;
+        }
+        issue.lifecycle(
+            Status::Closed {
+                reason: CloseReason::Solved,
+            },
+            signer,
+        )?;
+        solved.push(id);
+    }
+    Ok(solved)
+}
+
+/// Commits of a patch revision, ie. the commits reachable from its head but not its base.
+fn revision_commits(
+    repo: &Repository,
+    base: &git::Oid,
+    head: &git::Oid,
+) -> Result<Vec<git::Oid>, Error> {
+    let mut walk = repo.backend.revwalk()?;
+    walk.push((*head).into())?;
+    walk.hide((*base).into())?;
+
+    walk.map(|oid| Ok(oid?.into())).collect()
+}
+
+/// Digests of the tips of every issue and patch in the repository, across all namespaces.
+fn tips(repo: &Repository) -> Result<BTreeMap<Reference, git::Oid>, Error> {
+    let mut refs = BTreeMap::<_, Vec<_>>::new();
+
+    for r in repo
+        .backend
+        .references_glob("refs/namespaces/*/refs/cobs/*")?
+    {
+        let r = r?;
+        let (Some(name), Some(oid)) = (r.name(), r.target()) else {
+            continue;
+        };
+        let object = match name.split('/').collect::<Vec<_>>().as_slice() {
+            [.., "cobs", typename, id] => {
+                let Ok(id) = cob::ObjectId::from_str(id) else {
+                    continue;
+                };
+                if *typename == issue::TYPENAME.to_string() {
+                    Reference::Issue(id)
+                } else if *typename == patch::TYPENAME.to_string() {
+                    Reference::Patch(id)
+                } else {
+                    continue;
+                }
+            }
+            _ => continue,
+        };
+        refs.entry(object)
+            .or_default()
+            .push(format!("{oid} {name}\n"));
+    }
+
+    refs.into_iter()
+        .map(|(object, mut tips)| {
+            tips.sort();
+            let oid = git2::Oid::hash_object(git2::ObjectType::Blob, tips.concat().as_bytes())?;
+
+            Ok((object, oid.into()))
+        })
+        .collect()
+}
+
+/// A full object id.
+fn oid(word: &str) -> Option<git::Oid> {
+    if word.len() != 40 || !word.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    git::Oid::from_str(word).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cob::patch::{MergeTarget, Patches};
+    use crate::test;
+
+    #[test]
+    fn test_extract() {
+        let a = "d96f425412c9f8ad5d9a9a05c9831d0728e2338d";
+        let b = "8d3c2a9f5e6b4d1c0a7e9f8b6c5d4e3f2a1b0c9d";
+
+        assert_eq!(
+            extract(&format!("See {a}, and ({b}). Also {a}.")),
+            vec![a.parse().unwrap(), b.parse().unwrap()]
+        );
+        assert!(extract(&format!("{a}0 d96f425")).is_empty());
+        assert_eq!(
+            closing(&format!("Fixes: {a}\nRelated to {b}\n")),
+            vec![a.parse().unwrap()]
+        );
+        assert_eq!(
+            closing(&format!("This resolves {b}.")),
+            vec![b.parse().unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_backlinks_and_solve() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut issues = Issues::open(*signer.public_key(), &project).unwrap();
+        let mut patches = Patches::open(*signer.public_key(), &project).unwrap();
+
+        let first = issues
+            .create("First issue", "Blah blah blah.", &[], &signer)
+            .unwrap()
+            .id;
+        let second = issues
+            .create("Second issue", format!("Related to {first}."), &[], &signer)
+            .unwrap()
+            .id;
+
+        let (_, head) = project.head().unwrap();
+        let repo = &project.backend;
+        let parent = repo.find_commit(head.into()).unwrap();
+        let oid = repo
+            .commit(
+                None,
+                &parent.author(),
+                &parent.committer(),
+                &format!("Fix the first issue\n\nFixes {first}\n"),
+                &parent.tree().unwrap(),
+                &[&parent],
+            )
+            .unwrap();
+
+        let mut patch = patches
+            .create(
+                "My patch",
+                format!("Also see {second}."),
+                MergeTarget::default(),
+                head,
+                oid,
+                &[],
+                &signer,
+            )
+            .unwrap();
+        let (revision, _) = patch.latest().unwrap();
+        let revision = *revision;
+        let patch_id = patch.id;
+
+        let index = Index::refresh(&project).unwrap();
+        assert_eq!(
+            index
+                .backlinks(&Reference::Issue(first))
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![Reference::Issue(second), Reference::Commit(oid.into())]
+        );
+        assert_eq!(
+            index
+                .backlinks(&Reference::Issue(second))
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![Reference::Patch(patch_id)]
+        );
+        assert_eq!(Index::load(&project).unwrap(), index);
+        assert_eq!(issues.backlinks(&second).unwrap().len(), 1);
+
+        // Nothing is solved until the revision is merged.
+        let solved = solve(&project, &mut issues, &patch, &signer).unwrap();
+        assert!(solved.is_empty());
+
+        patch.merge(revision, oid.into(), &signer).unwrap();
+        let solved = solve(&project, &mut issues, &patch, &signer).unwrap();
+        assert_eq!(solved, vec![first]);
+
+        let issue = issues.get(&first).unwrap().unwrap();
+        assert_eq!(
+            *issue.status(),
+            Status::Closed {
+                reason: CloseReason::Solved
+            }
+        );
+        let issue = issues.get(&second).unwrap().unwrap();
+        assert_eq!(*issue.status(), Status::Open);
+
+        // The index is updated, since issues changed.
+        let index = Index::refresh(&project).unwrap();
+        assert!(index.head.is_some());
+        assert_eq!(patches.backlinks(&patch_id).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_index_incremental() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (_, signer, project) = test::setup::context(&tmp);
+        let mut issues = Issues::open(*signer.public_key(), &project).unwrap();
+
+        let first = issues
+            .create("First issue", "Blah blah blah.", &[], &signer)
+            .unwrap()
+            .id;
+        Index::refresh(&project).unwrap();
+
+        // Nothing changed since the index was saved.
+        let mut index = Index::load(&project).unwrap();
+        assert!(!index.update(&project).unwrap());
+
+        let second = issues
+            .create("Second issue", format!("Related to {first}."), &[], &signer)
+            .unwrap()
+            .id;
+        assert!(index.update(&project).unwrap());
+        index.link(&project);
+        assert_eq!(
+            index
+                .backlinks(&Reference::Issue(first))
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![Reference::Issue(second)]
+        );
+
+        // Objects that can't be decoded are skipped.
+        let (_, head) = project.head().unwrap();
+        project
+            .backend
+            .reference(
+                &format!(
+                    "refs/namespaces/{}/refs/cobs/{}/{}",
+                    signer.public_key(),
+                    *issue::TYPENAME,
+                    head
+                ),
+                head.into(),
+                false,
+                "Create invalid object",
+            )
+            .unwrap();
+
+        let index = Index::refresh(&project).unwrap();
+        assert_eq!(
+            index
+                .backlinks(&Reference::Issue(first))
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![Reference::Issue(second)]
+        );
+    }
+}
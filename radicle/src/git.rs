@@ -98,6 +98,20 @@ pub mod refs {
             Qualified::from_components(name::component!("rad"), name::component!("sigrefs"), None)
         });
 
+        /// Pseudo-reference under which the previous signed references commit is signed,
+        /// to protect against replays of older signed references. It is part of the
+        /// signed payload, but is never written to the repository.
+        ///
+        /// `refs/rad/sigrefs-parent`
+        ///
+        pub static SIGREFS_PARENT: Lazy<Qualified> = Lazy::new(|| {
+            Qualified::from_components(
+                name::component!("rad"),
+                name::component!("sigrefs-parent"),
+                None,
+            )
+        });
+
         /// Create the [`Namespaced`] `branch` under the `remote` namespace, i.e.
         ///
         /// `refs/namespaces/<remote>/refs/heads/<branch>`
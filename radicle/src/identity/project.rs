@@ -1,15 +1,17 @@
 mod id;
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt::Write as _;
-use std::io;
 use std::marker::PhantomData;
 use std::ops::Deref;
 use std::path::Path;
+use std::str::FromStr;
+use std::{fmt, io};
 
 use nonempty::NonEmpty;
 use once_cell::sync::Lazy;
 use radicle_git_ext::Oid;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
@@ -37,6 +39,8 @@ pub static PATH: Lazy<&Path> = Lazy::new(|| Path::new("radicle.json"));
 pub const MAX_STRING_LENGTH: usize = 255;
 /// Maximum number of a delegates in the identity document.
 pub const MAX_DELEGATES: usize = 255;
+/// Default branch of projects that don't specify one.
+pub static DEFAULT_BRANCH: Lazy<BranchName> = Lazy::new(|| git::refname!("master"));
 
 #[derive(Error, Debug)]
 pub enum DocError {
@@ -90,23 +94,140 @@ impl From<Delegate> for PublicKey {
 #[serde(rename_all = "kebab-case")]
 pub struct Payload {
     pub name: String,
-    pub description: String,            // TODO: Make optional.
-    pub default_branch: git::RefString, // TODO: Make optional.
+    /// Project description. See [`Payload::description`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Default branch of the project. See [`Payload::default_branch`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_branch: Option<BranchName>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-#[serde(transparent)]
-// TODO: Restrict values.
+impl Payload {
+    /// The project description, or an empty string if it isn't set.
+    pub fn description(&self) -> &str {
+        self.description.as_deref().unwrap_or_default()
+    }
+
+    /// The default branch of the project, or [`DEFAULT_BRANCH`] if it isn't set.
+    pub fn default_branch(&self) -> &BranchName {
+        self.default_branch.as_ref().unwrap_or(&DEFAULT_BRANCH)
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum NamespaceError {
+    #[error("namespace `{0}` is not of the form `<label>.<label>[.<label>...]`")]
+    Format(String),
+    #[error("namespace `{0}` is reserved")]
+    Reserved(String),
+    #[error("namespace cannot exceed 255 bytes")]
+    TooLong,
+}
+
+/// Namespace of an identity document extension, in reverse domain name notation,
+/// eg. `xyz.radicle.ci`. Labels are made of lowercase ASCII letters, digits and hyphens.
+///
+/// Namespaces are checked when an extension is set or read through [`Extension`]. The keys of
+/// decoded documents aren't checked, so that extensions written by other implementations are
+/// preserved.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Namespace(String);
 
+impl Namespace {
+    /// The namespace of the project payload. Extensions can't use it.
+    pub const PROJECT: &'static str = "xyz.radicle.project";
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl TryFrom<String> for Namespace {
+    type Error = NamespaceError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.len() > MAX_STRING_LENGTH {
+            return Err(NamespaceError::TooLong);
+        }
+        if value == Self::PROJECT {
+            return Err(NamespaceError::Reserved(value));
+        }
+        let labels = value.split('.').collect::<Vec<_>>();
+        let valid = labels.len() >= 2
+            && labels.iter().all(|label| {
+                !label.is_empty()
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            });
+
+        if !valid {
+            return Err(NamespaceError::Format(value));
+        }
+        Ok(Self(value))
+    }
+}
+
+impl FromStr for Namespace {
+    type Err = NamespaceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s.to_owned())
+    }
+}
+
+impl From<Namespace> for String {
+    fn from(ns: Namespace) -> Self {
+        ns.0
+    }
+}
+
+impl fmt::Display for Namespace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ExtensionError {
+    #[error("invalid namespace: {0}")]
+    Namespace(#[from] NamespaceError),
+    #[error("extension `{0}`: {1}")]
+    Json(Namespace, serde_json::Error),
+    #[error("invalid extension `{0}`: {1}")]
+    Invalid(Namespace, String),
+}
+
+/// A typed extension of the identity document, stored under its own [`Namespace`], next to
+/// the project payload. Downstream crates can implement this trait to store their own
+/// payloads, eg. a homepage or a license, in the document.
+///
+/// Extensions that aren't known to a crate are kept as-is in [`Doc::extensions`], and are
+/// preserved when the document is encoded.
+pub trait Extension: Serialize + DeserializeOwned {
+    /// The namespace of the extension, eg. `xyz.radicle.ci`.
+    const NAMESPACE: &'static str;
+
+    /// Validate the extension. This is checked when reading and writing the extension.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Doc<V> {
     #[serde(rename = "xyz.radicle.project")]
     pub payload: Payload,
+    /// Extensions, by namespace. See [`Extension`].
     #[serde(flatten)]
-    pub extensions: BTreeMap<Namespace, serde_json::Value>,
+    pub extensions: BTreeMap<String, serde_json::Value>,
     pub delegates: NonEmpty<Delegate>,
     pub threshold: usize,
+    /// Keys that were revoked, and may never be delegates again.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub revoked: BTreeSet<Did>,
 
     #[serde(skip)]
     verified: PhantomData<V>,
@@ -125,12 +246,16 @@ impl Doc<Verified> {
     }
 
     /// Attempt to add a new delegate to the document. Returns `true` if it wasn't there before.
+    /// Revoked keys can't be added.
     pub fn delegate(&mut self, name: String, key: crypto::PublicKey) -> bool {
         let delegate = Delegate {
             name,
             id: Did::from(key),
         };
 
+        if self.revoked.contains(&delegate.id) {
+            return false;
+        }
         if self.delegates.iter().all(|d| d.id != delegate.id) {
             self.delegates.push(delegate);
             return true;
@@ -138,6 +263,62 @@ impl Doc<Verified> {
         false
     }
 
+    /// Revoke a delegate's key. The delegate is removed, and the key may never be added back.
+    ///
+    /// The last delegate can't be revoked, use [`Doc::rotate`] to replace its key instead.
+    /// The threshold is left as it is, and must not exceed the remaining number of delegates:
+    /// if needed, it should be lowered before revoking.
+    pub fn revoke(&mut self, key: &crypto::PublicKey) -> Result<(), RevokeError> {
+        let remaining = self
+            .delegates
+            .iter()
+            .filter(|d| !d.matches(key))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if remaining.len() == self.delegates.len() {
+            return Err(RevokeError::NotDelegate(*key));
+        }
+        let Some(remaining) = NonEmpty::from_vec(remaining) else {
+            return Err(RevokeError::LastDelegate(*key));
+        };
+        if self.threshold > remaining.len() {
+            return Err(RevokeError::Threshold(self.threshold, remaining.len()));
+        }
+        self.delegates = remaining;
+        self.revoked.insert(Did::from(*key));
+
+        Ok(())
+    }
+
+    /// Replace a delegate's key with a new one, keeping the delegate's name, and revoke the
+    /// old key. Returns `true` if the key was replaced.
+    pub fn rotate(&mut self, old: &crypto::PublicKey, new: crypto::PublicKey) -> bool {
+        let new = Did::from(new);
+
+        if self.revoked.contains(&new) || self.delegates.iter().any(|d| d.id == new) {
+            return false;
+        }
+        let Some(delegate) = self.delegates.iter_mut().find(|d| d.matches(old)) else {
+            return false;
+        };
+        delegate.id = new;
+        self.revoked.insert(Did::from(*old));
+
+        true
+    }
+
+    pub fn unverified(self) -> Doc<Unverified> {
+        Doc {
+            payload: self.payload,
+            extensions: self.extensions,
+            delegates: self.delegates,
+            threshold: self.threshold,
+            revoked: self.revoked,
+            verified: PhantomData,
+        }
+    }
+
     pub fn sign<G: crypto::Signer>(&self, signer: &G) -> Result<(git::Oid, Signature), DocError> {
         let (oid, bytes) = self.encode()?;
         let sig = signer.sign(&bytes);
@@ -238,12 +419,13 @@ impl Doc<Unverified> {
         Self {
             payload: Payload {
                 name,
-                description,
-                default_branch,
+                description: Some(description),
+                default_branch: Some(default_branch),
             },
             extensions: BTreeMap::new(),
             delegates: NonEmpty::new(delegate),
             threshold: 1,
+            revoked: BTreeSet::new(),
             verified: PhantomData,
         }
     }
@@ -258,12 +440,13 @@ impl Doc<Unverified> {
         Self {
             payload: Payload {
                 name,
-                description,
-                default_branch,
+                description: Some(description),
+                default_branch: Some(default_branch),
             },
             extensions: BTreeMap::new(),
             delegates,
             threshold,
+            revoked: BTreeSet::new(),
             verified: PhantomData,
         }
     }
@@ -279,7 +462,7 @@ impl Doc<Unverified> {
         if self.name.len() > MAX_STRING_LENGTH {
             return Err(VerificationError::Name("name cannot exceed 255 bytes"));
         }
-        if self.description.len() > MAX_STRING_LENGTH {
+        if self.description().len() > MAX_STRING_LENGTH {
             return Err(VerificationError::Description(
                 "description cannot exceed 255 bytes",
             ));
@@ -303,16 +486,21 @@ impl Doc<Unverified> {
                 "delegate list cannot be empty",
             ));
         }
-        if self.default_branch.is_empty() {
+        if self.default_branch().is_empty() {
             return Err(VerificationError::DefaultBranch(
                 "default branch cannot be empty",
             ));
         }
-        if self.default_branch.len() > MAX_STRING_LENGTH {
+        if self.default_branch().len() > MAX_STRING_LENGTH {
             return Err(VerificationError::DefaultBranch(
                 "default branch cannot exceed 255 bytes",
             ));
         }
+        if self.delegates.iter().any(|d| self.revoked.contains(&d.id)) {
+            return Err(VerificationError::Delegates(
+                "revoked key cannot be a delegate",
+            ));
+        }
         if self.threshold > self.delegates.len() {
             return Err(VerificationError::Threshold(
                 self.threshold,
@@ -331,6 +519,7 @@ impl Doc<Unverified> {
             extensions: self.extensions,
             delegates: self.delegates,
             threshold: self.threshold,
+            revoked: self.revoked,
             verified: PhantomData,
         })
     }
@@ -355,12 +544,56 @@ impl Doc<Unverified> {
 }
 
 impl<V> Doc<V> {
+    /// Get a typed extension from the document, if it is set.
+    pub fn extension<E: Extension>(&self) -> Result<Option<E>, ExtensionError> {
+        let ns = Namespace::from_str(E::NAMESPACE)?;
+        let Some(value) = self.extensions.get(ns.as_str()) else {
+            return Ok(None);
+        };
+        let ext = E::deserialize(value).map_err(|e| ExtensionError::Json(ns.clone(), e))?;
+
+        ext.validate()
+            .map_err(|e| ExtensionError::Invalid(ns.clone(), e))?;
+
+        Ok(Some(ext))
+    }
+
+    /// Set a typed extension on the document, replacing any previous value.
+    pub fn set_extension<E: Extension>(&mut self, ext: &E) -> Result<(), ExtensionError> {
+        let ns = Namespace::from_str(E::NAMESPACE)?;
+
+        ext.validate()
+            .map_err(|e| ExtensionError::Invalid(ns.clone(), e))?;
+
+        let value = serde_json::to_value(ext).map_err(|e| ExtensionError::Json(ns.clone(), e))?;
+        self.extensions.insert(ns.into(), value);
+
+        Ok(())
+    }
+
+    /// Remove a typed extension from the document. Returns `true` if it was set.
+    pub fn remove_extension<E: Extension>(&mut self) -> Result<bool, ExtensionError> {
+        let ns = Namespace::from_str(E::NAMESPACE)?;
+
+        Ok(self.extensions.remove(ns.as_str()).is_some())
+    }
+
     pub fn head<R: ReadRepository>(remote: &RemoteId, repo: &R) -> Result<Oid, DocError> {
         repo.reference_oid(remote, &git::refs::storage::IDENTITY_BRANCH)
             .map_err(DocError::from)
     }
 }
 
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RevokeError {
+    #[error("{0} is not a delegate")]
+    NotDelegate(PublicKey),
+    #[error("{0} is the last delegate, and can only be rotated")]
+    LastDelegate(PublicKey),
+    #[error("threshold of {0} is higher than the {1} remaining delegate(s)")]
+    Threshold(usize, usize),
+}
+
 #[derive(Error, Debug)]
 pub enum IdentityError {
     #[error("git: {0}")]
@@ -383,6 +616,8 @@ pub enum IdentityError {
     Doc(#[from] DocError),
     #[error("the document root is missing")]
     MissingRoot,
+    #[error("revoked key {0} is no longer revoked in {1}")]
+    Unrevoked(PublicKey, Oid),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -401,6 +636,11 @@ pub struct Identity<I> {
     pub doc: Doc<Verified>,
     /// Signatures over this identity.
     pub signatures: HashMap<PublicKey, Signature>,
+    /// Keys revoked in the history of this identity, along with the identity commit
+    /// that revoked them. Signed refs and objects are only rejected from that commit on.
+    pub revoked: HashMap<PublicKey, Oid>,
+    /// Every version of the document in the history of this identity, keyed by blob.
+    pub versions: HashMap<Oid, Doc<Verified>>,
 }
 
 impl radicle_cob::identity::Identity for Identity<Oid> {
@@ -425,6 +665,8 @@ impl Identity<Oid> {
             revision: self.revision,
             doc: self.doc,
             signatures: self.signatures,
+            revoked: self.revoked,
+            versions: self.versions,
         })
     }
 }
@@ -435,6 +677,12 @@ impl Identity<Untrusted> {
         repo: &R,
     ) -> Result<Identity<Oid>, IdentityError> {
         let head = Doc::<Untrusted>::head(remote, repo)?;
+
+        Self::load_at(head, repo)
+    }
+
+    /// Load the identity whose identity branch head is the given commit.
+    pub fn load_at<R: ReadRepository>(head: Oid, repo: &R) -> Result<Identity<Oid>, IdentityError> {
         let mut history = repo.revwalk(head)?.collect::<Vec<_>>();
 
         // Retrieve root document.
@@ -447,6 +695,12 @@ impl Identity<Untrusted> {
         let mut trusted = trusted.verified()?;
         let mut current = root;
         let mut signatures = Vec::new();
+        let mut revoked = trusted
+            .revoked
+            .iter()
+            .map(|key| (**key, root_oid))
+            .collect::<HashMap<_, _>>();
+        let mut versions = HashMap::from([(root, trusted.clone())]);
 
         // Traverse the history chronologically.
         for oid in history.into_iter().rev() {
@@ -477,6 +731,16 @@ impl Identity<Untrusted> {
                 return Err(IdentityError::QuorumNotReached(quorum, trusted.threshold));
             }
 
+            // Revocations are permanent, and take effect from this version on.
+            if let Some(key) = trusted.revoked.difference(&untrusted.revoked).next() {
+                return Err(IdentityError::Unrevoked(**key, oid.into()));
+            }
+            for key in untrusted.revoked.difference(&trusted.revoked) {
+                revoked.insert(**key, oid.into());
+            }
+
+            versions.insert(blob.id().into(), untrusted.clone());
+
             trusted = untrusted;
             current = blob.id().into();
         }
@@ -488,6 +752,8 @@ impl Identity<Untrusted> {
             revision,
             doc: trusted,
             signatures: signatures.into_iter().collect(),
+            revoked,
+            versions,
         })
     }
 }
@@ -497,6 +763,7 @@ mod test {
     use radicle_crypto::test::signer::MockSigner;
     use radicle_crypto::Signer as _;
 
+    use crate::assert_matches;
     use crate::rad;
     use crate::storage::git::Storage;
     use crate::storage::{ReadStorage, WriteStorage};
@@ -536,8 +803,8 @@ mod test {
             fixtures::project(tempdir.path().join("copy"), &storage, &alice).unwrap();
 
         // Bob and Eve fork the project from Alice.
-        rad::fork_remote(id, alice.public_key(), &bob, &storage).unwrap();
-        rad::fork_remote(id, alice.public_key(), &eve, &storage).unwrap();
+        rad::fork(id, Some(alice.public_key()), &bob, &storage).unwrap();
+        rad::fork(id, Some(alice.public_key()), &eve, &storage).unwrap();
 
         // TODO: In some cases we want to get the repo and the project, but don't
         // want to have to create a repository object twice. Perhaps there should
@@ -546,7 +813,7 @@ mod test {
         let repo = storage.repository(id).unwrap();
 
         // Make a change to the description and sign it.
-        proj.payload.description += "!";
+        proj.payload.description = Some(format!("{}!", proj.description()));
         proj.sign(&alice)
             .and_then(|(_, sig)| {
                 proj.update(
@@ -588,7 +855,7 @@ mod test {
             .unwrap();
 
         // Update description again with signatures by Eve and Bob.
-        proj.payload.description += "?";
+        proj.payload.description = Some(format!("{}?", proj.description()));
         let (current, head) = proj
             .sign(&bob)
             .and_then(|(_, bob_sig)| {
@@ -617,7 +884,228 @@ mod test {
         assert_eq!(identity.doc, proj);
 
         let proj = storage.get(alice.public_key(), id).unwrap().unwrap();
-        assert_eq!(proj.description, "Acme's repository!?");
+        assert_eq!(proj.description(), "Acme's repository!?");
+    }
+
+    #[test]
+    fn test_revocation() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let mut rng = fastrand::Rng::new();
+
+        let alice = MockSigner::new(&mut rng);
+        let bob = MockSigner::new(&mut rng);
+        let eve = MockSigner::new(&mut rng);
+
+        let storage = Storage::open(tempdir.path().join("storage")).unwrap();
+        let (id, _, _, _) =
+            fixtures::project(tempdir.path().join("copy"), &storage, &alice).unwrap();
+        let mut proj = storage.get(alice.public_key(), id).unwrap().unwrap();
+        let repo = storage.repository(id).unwrap();
+
+        // Add Bob as a delegate.
+        assert!(proj.delegate("bob".to_owned(), *bob.public_key()));
+        let (_, sig) = proj.sign(&alice).unwrap();
+        proj.update(
+            alice.public_key(),
+            "Add bob",
+            &[(alice.public_key(), sig)],
+            &repo,
+        )
+        .unwrap();
+
+        // Bob's key is lost, and rotated to a new key by Alice.
+        assert!(proj.rotate(bob.public_key(), *eve.public_key()));
+        assert!(!proj.delegate("bob".to_owned(), *bob.public_key()));
+        let (_, sig) = proj.sign(&alice).unwrap();
+        let rotation = proj
+            .update(
+                alice.public_key(),
+                "Rotate bob",
+                &[(alice.public_key(), sig)],
+                &repo,
+            )
+            .unwrap();
+
+        let identity = Identity::load(alice.public_key(), &repo).unwrap();
+        assert_eq!(identity.doc, proj);
+        assert_eq!(identity.doc.delegates.len(), 2);
+        assert_eq!(
+            identity.doc.revoked,
+            BTreeSet::from_iter([Did::from(*bob.public_key())])
+        );
+        assert_eq!(
+            identity.revoked,
+            HashMap::from_iter([(*bob.public_key(), rotation)])
+        );
+
+        // Revoking doesn't change the threshold: it has to be lowered first.
+        let mut revoked = proj.clone();
+        revoked.threshold = 2;
+        assert_eq!(
+            revoked.revoke(eve.public_key()),
+            Err(RevokeError::Threshold(2, 1))
+        );
+        revoked.threshold = 1;
+        assert_eq!(revoked.revoke(eve.public_key()), Ok(()));
+        assert_eq!(
+            revoked.revoke(eve.public_key()),
+            Err(RevokeError::NotDelegate(*eve.public_key()))
+        );
+        assert_eq!(
+            revoked.revoke(alice.public_key()),
+            Err(RevokeError::LastDelegate(*alice.public_key()))
+        );
+
+        // A revoked key can't be a delegate.
+        let mut invalid = proj.clone().unverified();
+        invalid.delegates.push(Delegate {
+            name: "bob".to_owned(),
+            id: Did::from(*bob.public_key()),
+        });
+        assert!(matches!(
+            invalid.verified(),
+            Err(VerificationError::Delegates(_))
+        ));
+
+        // Revocations can't be undone.
+        proj.revoked.clear();
+        let (_, sig) = proj.sign(&alice).unwrap();
+        let head = proj
+            .update(
+                alice.public_key(),
+                "Unrevoke bob",
+                &[(alice.public_key(), sig)],
+                &repo,
+            )
+            .unwrap();
+
+        assert!(matches!(
+            Identity::load(alice.public_key(), &repo),
+            Err(IdentityError::Unrevoked(key, oid)) if key == *bob.public_key() && oid == head
+        ));
+    }
+
+    #[test]
+    fn test_namespace() {
+        for ns in ["xyz.radicle.ci", "org.example", "com.example-1.license"] {
+            assert!(Namespace::from_str(ns).is_ok(), "{ns}");
+        }
+        for ns in [
+            "radicle",
+            "xyz..ci",
+            "xyz.Radicle",
+            "xyz.-ci",
+            "xyz.ci.",
+            "xyz_ci.ci",
+        ] {
+            assert_eq!(
+                Namespace::from_str(ns),
+                Err(NamespaceError::Format(ns.to_owned()))
+            );
+        }
+        assert_eq!(
+            Namespace::from_str(Namespace::PROJECT),
+            Err(NamespaceError::Reserved(Namespace::PROJECT.to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_optional_payload() {
+        let doc = Doc::<Unverified>::from_json(
+            br#"{
+                "xyz.radicle.project": { "name": "acme" },
+                "delegates": [{
+                    "name": "alice",
+                    "id": "did:key:z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK"
+                }],
+                "threshold": 1
+            }"#,
+        )
+        .unwrap()
+        .verified()
+        .unwrap();
+
+        assert_eq!(doc.description(), "");
+        assert_eq!(*doc.default_branch(), *DEFAULT_BRANCH);
+
+        // Unset fields are not added when encoding.
+        let (_, bytes) = doc.encode().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(
+            json["xyz.radicle.project"],
+            serde_json::json!({ "name": "acme" })
+        );
+    }
+
+    #[test]
+    fn test_extensions() {
+        #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+        struct Homepage {
+            url: String,
+        }
+
+        impl Extension for Homepage {
+            const NAMESPACE: &'static str = "xyz.radicle.homepage";
+
+            fn validate(&self) -> Result<(), String> {
+                if !self.url.starts_with("https://") {
+                    return Err(String::from("homepage must be an https URL"));
+                }
+                Ok(())
+            }
+        }
+
+        let mut doc = arbitrary::gen::<Doc<Verified>>(1);
+        let homepage = Homepage {
+            url: String::from("https://radicle.xyz"),
+        };
+        let unknown = serde_json::json!({ "pipeline": ["build", "test"], "timeout": 60 });
+
+        assert!(doc.extension::<Homepage>().unwrap().is_none());
+        assert_matches!(
+            doc.set_extension(&Homepage {
+                url: String::from("ftp://radicle.xyz")
+            }),
+            Err(ExtensionError::Invalid(_, _))
+        );
+        doc.set_extension(&homepage).unwrap();
+        doc.extensions
+            .insert(String::from("org.example.ci"), unknown.clone());
+
+        // Extensions survive a round-trip, including the ones we don't know about.
+        let (_, bytes) = doc.encode().unwrap();
+        let decoded = Doc::from_json(&bytes).unwrap().verified().unwrap();
+
+        assert_eq!(decoded, doc);
+        assert_eq!(decoded.extension::<Homepage>().unwrap(), Some(homepage));
+        assert_eq!(decoded.extensions["org.example.ci"], unknown);
+        assert_eq!(decoded.encode().unwrap().1, bytes);
+
+        // Extensions that are modified by hand are validated when read.
+        doc.extensions.insert(
+            String::from(Homepage::NAMESPACE),
+            serde_json::json!({ "url": "radicle.xyz" }),
+        );
+        assert_matches!(
+            doc.extension::<Homepage>(),
+            Err(ExtensionError::Invalid(_, _))
+        );
+        assert!(doc.remove_extension::<Homepage>().unwrap());
+
+        // Keys that aren't valid namespaces are kept as they are when decoding.
+        let mut json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        json["Invalid"] = serde_json::json!(true);
+
+        let decoded = Doc::from_json(json.to_string().as_bytes())
+            .unwrap()
+            .verified()
+            .unwrap();
+        assert_eq!(decoded.extensions["Invalid"], serde_json::json!(true));
+        assert_eq!(
+            serde_json::to_value(&decoded).unwrap()["Invalid"],
+            serde_json::json!(true)
+        );
     }
 
     #[quickcheck]
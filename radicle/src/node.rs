@@ -1,3 +1,4 @@
+pub mod config;
 mod features;
 
 use std::fmt;
@@ -5,6 +6,9 @@ use std::io;
 use std::io::{BufRead, BufReader, Write};
 use std::os::unix::net::UnixStream;
 use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
 
 use crate::crypto::PublicKey;
 use crate::identity::Id;
@@ -28,6 +32,84 @@ pub enum Error {
     EmptyResponse { cmd: &'static str },
 }
 
+/// Maximum length in bytes of a node alias.
+pub const MAX_ALIAS_LENGTH: usize = 32;
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum AliasError {
+    #[error("alias cannot be empty")]
+    Empty,
+    #[error("alias cannot be longer than {MAX_ALIAS_LENGTH} bytes")]
+    TooLong,
+    #[error("alias cannot contain null characters")]
+    Null,
+}
+
+/// Non-unique node alias, announced to the network. Aliases are at most
+/// [`MAX_ALIAS_LENGTH`] bytes long, so that they fit in a node announcement.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Alias(String);
+
+impl Alias {
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl TryFrom<String> for Alias {
+    type Error = AliasError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            return Err(AliasError::Empty);
+        }
+        if value.len() > MAX_ALIAS_LENGTH {
+            return Err(AliasError::TooLong);
+        }
+        if value.contains('\0') {
+            return Err(AliasError::Null);
+        }
+        Ok(Self(value))
+    }
+}
+
+impl FromStr for Alias {
+    type Err = AliasError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s.to_owned())
+    }
+}
+
+impl From<Alias> for String {
+    fn from(alias: Alias) -> Self {
+        alias.0
+    }
+}
+
+impl fmt::Display for Alias {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Update to the information a node announces about itself, as sent to the node's control
+/// socket. Fields that are `None` are left unchanged.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeUpdate {
+    /// New node alias.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<Alias>,
+    /// New advertized features.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub features: Option<Features>,
+    /// New external addresses, eg. `203.0.113.1:8776`. They are parsed by the node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_addresses: Option<Vec<String>>,
+}
+
 pub trait Handle {
     /// Fetch a project from the network. Fails if the project isn't tracked.
     fn fetch(&self, id: &Id) -> Result<(), Error>;
@@ -38,6 +120,9 @@ pub trait Handle {
     fn untrack(&self, id: &Id) -> Result<bool, Error>;
     /// Notify the network that we have new refs.
     fn announce_refs(&self, id: &Id) -> Result<(), Error>;
+    /// Update the alias, features or addresses announced by the node, and persist them.
+    /// Returns whether the node announcement changed.
+    fn update_node(&self, update: &NodeUpdate) -> Result<bool, Error>;
     /// Ask the node to shutdown.
     fn shutdown(self) -> Result<(), Error>;
 }
@@ -122,6 +207,25 @@ impl Handle for Node {
         Ok(())
     }
 
+    fn update_node(&self, update: &NodeUpdate) -> Result<bool, Error> {
+        let update = serde_json::to_string(update).expect("Node::update_node: update serializes");
+        let mut line = self.call("update-node", &update)?;
+        let line = line
+            .next()
+            .ok_or(Error::EmptyResponse { cmd: "update-node" })??;
+
+        log::debug!("node: {}", line);
+
+        match line.as_str() {
+            RESPONSE_OK => Ok(true),
+            RESPONSE_NOOP => Ok(false),
+            _ => Err(Error::InvalidResponse {
+                cmd: "update-node",
+                response: line,
+            }),
+        }
+    }
+
     fn shutdown(self) -> Result<(), Error> {
         todo!();
     }
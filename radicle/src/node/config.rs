@@ -0,0 +1,132 @@
+//! Node configuration that is persisted in the node directory, so that it is shared by the
+//! node and the tools operating on its storage, eg. `rad gc`.
+use std::path::Path;
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::collections::HashSet;
+use crate::crypto::PublicKey;
+use crate::node::{Alias, Features};
+
+/// Name of the configuration file, in the node directory.
+pub const CONFIG_FILE: &str = "config.json";
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("i/o: {0}")]
+    Io(#[from] io::Error),
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Persisted node configuration.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    /// Node alias. Set with `radicle-node --alias`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<Alias>,
+    /// Features advertized to the network.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub features: Option<Features>,
+    /// External addresses advertized to the network, eg. `203.0.113.1:8776`.
+    /// They are parsed by the node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_addresses: Option<Vec<String>>,
+    /// Project remote tracking policy.
+    #[serde(default)]
+    pub remote_tracking: RemoteTracking,
+}
+
+impl Config {
+    /// Load the configuration from the given node directory. If it was never saved, the
+    /// default configuration is returned.
+    pub fn load(dir: &Path) -> Result<Self, Error> {
+        match fs::read(dir.join(CONFIG_FILE)) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Save the configuration in the given node directory.
+    pub fn save(&self, dir: &Path) -> Result<(), Error> {
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(CONFIG_FILE), serde_json::to_vec_pretty(self)?)?;
+
+        Ok(())
+    }
+}
+
+/// Project remote tracking policy.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RemoteTracking {
+    /// Only track remotes of project delegates.
+    #[default]
+    DelegatesOnly,
+    /// Track all remotes.
+    All { blocked: HashSet<PublicKey> },
+    /// Track a specific list of users as well as the project delegates.
+    Allowed(HashSet<PublicKey>),
+}
+
+impl RemoteTracking {
+    /// Whether the given remote of a project with the given delegates is tracked.
+    pub fn is_tracked(&self, remote: &PublicKey, delegates: &[PublicKey]) -> bool {
+        match self {
+            Self::DelegatesOnly => delegates.contains(remote),
+            Self::All { blocked } => !blocked.contains(remote),
+            Self::Allowed(remotes) => remotes.contains(remote) || delegates.contains(remote),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::node::{AliasError, MAX_ALIAS_LENGTH};
+    use crate::test::arbitrary;
+
+    #[test]
+    fn test_config_load_save() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("node");
+
+        assert_eq!(Config::load(&dir).unwrap(), Config::default());
+
+        let remote = arbitrary::gen::<PublicKey>(1);
+        let config = Config {
+            alias: Some(Alias::from_str("alice").unwrap()),
+            features: Some(Features::NONE),
+            external_addresses: Some(vec!["203.0.113.1:8776".to_owned()]),
+            remote_tracking: RemoteTracking::Allowed(HashSet::from_iter([remote])),
+        };
+        config.save(&dir).unwrap();
+
+        assert_eq!(Config::load(&dir).unwrap(), config);
+
+        // Aliases are validated when loading.
+        fs::write(
+            dir.join(CONFIG_FILE),
+            serde_json::json!({ "alias": "a".repeat(MAX_ALIAS_LENGTH + 1) }).to_string(),
+        )
+        .unwrap();
+        assert!(Config::load(&dir).is_err());
+    }
+
+    #[test]
+    fn test_alias() {
+        assert!(Alias::from_str(&"a".repeat(MAX_ALIAS_LENGTH)).is_ok());
+        assert_eq!(
+            Alias::from_str(&"a".repeat(MAX_ALIAS_LENGTH + 1)),
+            Err(AliasError::TooLong)
+        );
+        assert_eq!(Alias::from_str(""), Err(AliasError::Empty));
+        assert_eq!(Alias::from_str("al\0ice"), Err(AliasError::Null));
+    }
+}
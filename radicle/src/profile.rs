@@ -9,6 +9,7 @@
 //!       radicle.pub                            # Public key (PKCS 8)
 //!     node/
 //!       radicle.sock                           # Node control socket
+//!       config.json                            # Node configuration
 //!
 use std::io;
 use std::path::{Path, PathBuf};
@@ -13,7 +13,7 @@ use crate::identity::Id;
 use crate::node;
 use crate::node::NodeId;
 use crate::storage::git::transport::{self, remote};
-use crate::storage::git::{ProjectError, Storage};
+use crate::storage::git::{alternates, paths, ProjectError, Storage};
 use crate::storage::refs::SignedRefs;
 use crate::storage::{BranchName, ReadRepository as _, RemoteId, WriteRepository as _};
 use crate::{identity, storage};
@@ -100,6 +100,8 @@ pub enum ForkError {
     Git(#[from] git::Error),
     #[error("storage: {0}")]
     Storage(#[from] storage::Error),
+    #[error("signed refs: {0}")]
+    Refs(#[from] storage::refs::Error),
     #[error("project `{0}` was not found in storage")]
     NotFound(Id),
     #[error("project identity error: {0}")]
@@ -110,78 +112,75 @@ pub enum ForkError {
     InvalidReference,
 }
 
-/// Create a local tree for an existing project, from an existing remote.
-pub fn fork_remote<G: Signer, S: storage::WriteStorage>(
-    proj: Id,
-    remote: &RemoteId,
-    signer: &G,
-    storage: S,
-) -> Result<(), ForkError> {
-    // TODO: Copy tags over?
-
-    // Creates or copies the following references:
-    //
-    // refs/namespaces/<pk>/refs/heads/master
-    // refs/namespaces/<pk>/refs/rad/id
-    // refs/namespaces/<pk>/refs/rad/sigrefs
-    // refs/namespaces/<pk>/refs/tags/*
-
-    let me = signer.public_key();
-    let project = storage
-        .get(remote, proj)?
-        .ok_or(ForkError::NotFound(proj))?;
-    let repository = storage.repository(proj)?;
-
-    let raw = repository.raw();
-    let remote_head =
-        raw.refname_to_id(&git::refs::storage::branch(remote, &project.default_branch))?;
-    raw.reference(
-        &git::refs::storage::branch(me, &project.default_branch),
-        remote_head,
-        false,
-        &format!("creating default branch for {me}"),
-    )?;
-
-    let remote_id = raw.refname_to_id(&git::refs::storage::id(remote))?;
-    raw.reference(
-        &git::refs::storage::id(me),
-        remote_id,
-        false,
-        &format!("creating identity branch for {me}"),
-    )?;
-
-    repository.sign_refs(signer)?;
-
-    Ok(())
-}
-
+/// Fork a project into our own namespace, and sign the resulting refs.
+///
+/// If a source remote is given, its branches, tags and identity branch are copied. This allows
+/// continuing the work of a specific peer. Otherwise, the canonical head of the default branch,
+/// the canonical tags and the canonical identity branch are copied.
+///
+/// Creates the following references:
+///
+/// refs/namespaces/<pk>/refs/heads/*
+/// refs/namespaces/<pk>/refs/tags/*
+/// refs/namespaces/<pk>/refs/rad/id
+/// refs/namespaces/<pk>/refs/rad/sigrefs
 pub fn fork<G: Signer, S: storage::WriteStorage>(
     proj: Id,
+    from: Option<&RemoteId>,
     signer: &G,
     storage: &S,
-) -> Result<(), ForkError> {
+) -> Result<SignedRefs<Verified>, ForkError> {
     let me = signer.public_key();
     let repository = storage.repository(proj)?;
-    // TODO: We should get the id branch pointer from a stored canonical reference.
-    let (canonical_id, _) = repository.project_identity()?;
-    let (canonical_branch, canonical_head) = repository.head()?;
     let raw = repository.raw();
 
-    raw.reference(
-        &canonical_branch.with_namespace(me.into()),
-        *canonical_head,
-        false,
-        &format!("creating default branch for {me}"),
-    )?;
+    let (id, refs) = if let Some(remote) = from {
+        if storage.get(remote, proj)?.is_none() {
+            return Err(ForkError::NotFound(proj));
+        }
+        // Only refs signed by the remote are copied, since we're signing them as our own.
+        let signed = repository.remote(remote)?.refs;
+        let id = signed
+            .get(&git::refs::storage::IDENTITY_BRANCH)
+            .ok_or(ForkError::InvalidReference)?;
+        let refs = signed
+            .iter()
+            .filter(|(name, _)| name.starts_with("refs/heads/") || name.starts_with("refs/tags/"))
+            .map(|(name, oid)| (name.clone(), *oid))
+            .collect::<Vec<_>>();
+
+        (id, refs)
+    } else {
+        // TODO: We should get the id branch pointer from a stored canonical reference.
+        let (canonical_id, _) = repository.project_identity()?;
+        let (canonical_branch, canonical_head) = repository.head()?;
+        let mut refs = vec![(canonical_branch.to_ref_string(), canonical_head)];
+
+        refs.extend(repository.canonical_tags()?);
+
+        (canonical_id, refs)
+    };
+
+    for (name, oid) in refs {
+        let name =
+            git::Qualified::from_refstr(name.as_refstr()).ok_or(ForkError::InvalidReference)?;
+
+        raw.reference(
+            &name.with_namespace(me.into()),
+            *oid,
+            false,
+            &format!("creating {name} for {me}"),
+        )?;
+    }
     raw.reference(
         &git::refs::storage::id(me),
-        canonical_id.into(),
+        *id,
         false,
         &format!("creating identity branch for {me}"),
     )?;
-    repository.sign_refs(signer)?;
+    let signed = repository.sign_refs(signer)?;
 
-    Ok(())
+    Ok(signed)
 }
 
 #[derive(Error, Debug)]
@@ -205,7 +204,7 @@ pub fn clone<P: AsRef<Path>, G: Signer, S: storage::WriteStorage, H: node::Handl
 ) -> Result<git2::Repository, CloneError> {
     let _ = handle.track(&proj)?;
     let _ = handle.fetch(&proj)?;
-    let _ = fork(proj, signer, storage)?;
+    let _ = fork(proj, None, signer, storage)?;
     let working = checkout(proj, signer.public_key(), path, storage)?;
 
     Ok(working)
@@ -234,7 +233,7 @@ pub fn clone_url<P: AsRef<Path>, G: Signer, S: storage::WriteStorage>(
     let namespace = url.namespace.ok_or(CloneUrlError::MissingNamespace)?;
     let mut project = storage.repository(url.repo)?;
     let _updates = project.fetch(&url.node, namespace)?;
-    let _ = fork(url.repo, signer, storage)?;
+    let _ = fork(url.repo, None, signer, storage)?;
     let working = checkout(url.repo, signer.public_key(), path, storage)?;
 
     Ok(working)
@@ -252,10 +251,15 @@ pub enum CheckoutError {
     NotFound(Id),
     #[error("project error: {0}")]
     Project(#[from] ProjectError),
+    #[error("failed to share objects with storage: {0}")]
+    Share(#[from] alternates::Error),
 }
 
 /// Checkout a project from storage as a working copy.
 /// This effectively does a `git-clone` from storage.
+///
+/// The working copy borrows the objects of the storage repository, so that they aren't
+/// duplicated on disk. See [`alternates`].
 pub fn checkout<P: AsRef<Path>, S: storage::ReadStorage>(
     proj: Id,
     remote: &RemoteId,
@@ -263,17 +267,20 @@ pub fn checkout<P: AsRef<Path>, S: storage::ReadStorage>(
     storage: &S,
 ) -> Result<git2::Repository, CheckoutError> {
     // TODO: Decide on whether we can use `clone_local`
-    // TODO: Look into sharing object databases.
     let project = storage
         .get(remote, proj)?
         .ok_or(CheckoutError::NotFound(proj))?;
 
     let mut opts = git2::RepositoryInitOptions::new();
-    opts.no_reinit(true).description(&project.description);
+    opts.no_reinit(true).description(project.description());
 
     let repo = git2::Repository::init_opts(path.as_ref().join(&project.name), &opts)?;
     let url = git::Url::from(proj).with_namespace(*remote);
 
+    // Borrow objects from storage. Since the objects we fetch are then found locally, they
+    // aren't transferred.
+    alternates::share(&repo, &paths::repository(storage, &proj))?;
+
     // Configure and fetch all refs from remote.
     git::configure_remote(&repo, &REMOTE_NAME, &url)?;
     git::fetch(&repo, &REMOTE_NAME).map_err(CheckoutError::Fetch)?;
@@ -281,20 +288,30 @@ pub fn checkout<P: AsRef<Path>, S: storage::ReadStorage>(
     {
         // Setup default branch.
         let remote_head_ref =
-            git::refs::workdir::remote_branch(&REMOTE_NAME, &project.default_branch);
+            git::refs::workdir::remote_branch(&REMOTE_NAME, project.default_branch());
 
         let remote_head_commit = repo.find_reference(&remote_head_ref)?.peel_to_commit()?;
-        let _ = repo.branch(&project.default_branch, &remote_head_commit, true)?;
+        let _ = repo.branch(project.default_branch(), &remote_head_commit, true)?;
 
         // Setup remote tracking for default branch.
         git::set_upstream(
             &repo,
             &REMOTE_NAME,
-            &project.default_branch,
-            &git::refs::workdir::branch(&project.default_branch),
+            project.default_branch(),
+            &git::refs::workdir::branch(project.default_branch()),
         )?;
     }
 
+    {
+        // Mirror the canonical tags, which are set at the top-level of storage.
+        let mut opts = git2::FetchOptions::new();
+        opts.download_tags(git2::AutotagOption::None);
+
+        repo.remote_anonymous(git::Url::from(proj).to_string().as_str())?
+            .fetch(&["+refs/tags/*:refs/tags/*"], Some(&mut opts), None)
+            .map_err(CheckoutError::Fetch)?;
+    }
+
     Ok(repo)
 }
 
@@ -401,8 +418,8 @@ mod tests {
 
         assert_eq!(remotes[&public_key].refs, refs);
         assert_eq!(project.name, "acme");
-        assert_eq!(project.description, "Acme's repo");
-        assert_eq!(project.default_branch, git::refname!("master"));
+        assert_eq!(project.description(), "Acme's repo");
+        assert_eq!(*project.default_branch(), git::refname!("master"));
         assert_eq!(
             project.delegates.first(),
             &Delegate {
@@ -436,7 +453,7 @@ mod tests {
         .unwrap();
 
         // Bob forks it and creates a checkout.
-        fork(id, &bob, &storage).unwrap();
+        fork(id, None, &bob, &storage).unwrap();
         checkout(id, bob_id, tempdir.path().join("copy"), &storage).unwrap();
 
         let bob_remote = storage.repository(id).unwrap().remote(bob_id).unwrap();
@@ -450,6 +467,77 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fork_tags_and_remote() {
+        let mut rng = fastrand::Rng::new();
+        let tempdir = tempfile::tempdir().unwrap();
+        let alice = MockSigner::new(&mut rng);
+        let alice_id = alice.public_key();
+        let bob = MockSigner::new(&mut rng);
+        let eve = MockSigner::new(&mut rng);
+        let storage = Storage::open(tempdir.path().join("storage")).unwrap();
+
+        transport::local::register(storage.clone());
+
+        // Alice creates a project, with a feature branch and a tag.
+        let (original, _) = fixtures::repository(tempdir.path().join("original"));
+        let (id, _, _) = init(
+            &original,
+            "acme",
+            "Acme's repo",
+            git::refname!("master"),
+            &alice,
+            &storage,
+        )
+        .unwrap();
+        let repo = storage.repository(id).unwrap();
+        let head = repo
+            .raw()
+            .refname_to_id(&git::refs::storage::branch(
+                alice_id,
+                &git::refname!("master"),
+            ))
+            .unwrap();
+
+        for name in ["refs/heads/feature", "refs/tags/v1"] {
+            repo.raw()
+                .reference(
+                    &format!("refs/namespaces/{alice_id}/{name}"),
+                    head,
+                    false,
+                    "",
+                )
+                .unwrap();
+        }
+        repo.sign_refs(&alice).unwrap();
+
+        // Bob forks the canonical branch, and gets the canonical tags.
+        let bob_refs = fork(id, None, &bob, &storage).unwrap();
+
+        assert_eq!(
+            bob_refs.refs.keys().map(|k| k.as_str()).collect::<Vec<_>>(),
+            vec!["refs/heads/master", "refs/rad/id", "refs/tags/v1"]
+        );
+
+        // Alice creates a branch she doesn't sign.
+        repo.raw()
+            .reference(
+                &format!("refs/namespaces/{alice_id}/refs/heads/unsigned"),
+                head,
+                false,
+                "",
+            )
+            .unwrap();
+
+        // Eve forks Alice's remote, and gets all of Alice's signed branches and tags.
+        fork(id, Some(alice_id), &eve, &storage).unwrap();
+
+        let repo = storage.repository(id).unwrap();
+        let eve_refs = repo.remote(eve.public_key()).unwrap().refs;
+        assert_eq!(eve_refs, repo.remote(alice_id).unwrap().refs);
+        assert!(eve_refs.head("unsigned").is_none());
+    }
+
     #[test]
     fn test_checkout() {
         let tempdir = tempfile::tempdir().unwrap();
@@ -501,5 +589,30 @@ mod tests {
                 .map(|r| r.bytes().to_vec())
                 .collect::<Vec<_>>(),
         );
+
+        // Objects are borrowed from storage, rather than copied.
+        let repo = paths::repository(&storage, &id);
+        let head = copy.head().unwrap().target().unwrap();
+
+        assert_eq!(
+            alternates::borrowers(&repo).unwrap(),
+            vec![copy.path().canonicalize().unwrap()]
+        );
+        assert!(std::fs::read_dir(copy.path().join("objects").join("pack"))
+            .unwrap()
+            .next()
+            .is_none());
+
+        // Once dissociated, the working copy no longer depends on storage, and survives its
+        // removal.
+        alternates::dissociate(copy.path()).unwrap();
+
+        assert!(alternates::borrowers(&repo).unwrap().is_empty());
+
+        std::fs::remove_dir_all(&repo).unwrap();
+        assert!(git2::Repository::open(copy.path())
+            .unwrap()
+            .find_commit(head)
+            .is_ok());
     }
 }
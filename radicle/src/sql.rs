@@ -4,6 +4,7 @@ use std::str::FromStr;
 use sqlite as sql;
 use sqlite::Value;
 
+use crate::cob::ObjectId;
 use crate::identity::Id;
 use crate::node;
 
@@ -36,3 +37,38 @@ impl sql::ValueInto for node::Features {
         }
     }
 }
+
+impl sql::ValueInto for ObjectId {
+    fn into(value: &Value) -> Option<Self> {
+        match value {
+            Value::String(id) => ObjectId::from_str(id).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl sqlite::Bindable for &ObjectId {
+    fn bind(self, stmt: &mut sql::Statement<'_>, i: usize) -> sql::Result<()> {
+        self.to_string().as_str().bind(stmt, i)
+    }
+}
+
+/// Run an SQL query inside a transaction.
+/// Commits the transaction on success, and rolls back on error.
+pub fn transaction<T>(
+    db: &sql::Connection,
+    query: impl FnOnce(&sql::Connection) -> Result<T, sql::Error>,
+) -> Result<T, sql::Error> {
+    db.execute("BEGIN")?;
+
+    match query(db) {
+        Ok(result) => {
+            db.execute("COMMIT")?;
+            Ok(result)
+        }
+        Err(err) => {
+            db.execute("ROLLBACK")?;
+            Err(err)
+        }
+    }
+}
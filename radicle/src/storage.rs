@@ -1,7 +1,7 @@
 pub mod git;
 pub mod refs;
 
-use std::collections::hash_map;
+use std::collections::{hash_map, BTreeMap};
 use std::ops::Deref;
 use std::path::Path;
 use std::{fmt, io};
@@ -70,6 +70,10 @@ pub enum FetchError {
     Refs(#[from] refs::Error),
     #[error("verify: {0}")]
     Verify(#[from] git::VerifyError),
+    #[error("reference `{0}` was modified during fetch")]
+    Conflict(RefString),
+    #[error("signed refs `{0}` regress the remote's history")]
+    Regression(RefString),
     #[error(transparent)]
     Storage(#[from] Error),
     // TODO: This should wrap a more specific error.
@@ -264,13 +268,28 @@ pub trait ReadRepository {
     /// Returns the [`Oid`] as well as the qualified reference name.
     fn head(&self) -> Result<(Qualified, Oid), ProjectError>;
 
-    /// Compute the canonical head of this repository.
+    /// Compute the canonical head of this repository, ie. the newest commit of the default
+    /// branch that at least `threshold` delegates have in their history.
     ///
     /// Ignores any existing `HEAD` reference.
     ///
     /// Returns the [`Oid`] as well as the qualified reference name.
     fn canonical_head(&self) -> Result<(Qualified, Oid), ProjectError>;
 
+    /// Compute the canonical head of the given branch, ie. the newest commit of the branch
+    /// that at least `threshold` delegates have in their history.
+    ///
+    /// Returns the [`Oid`] as well as the qualified reference name.
+    fn canonical_branch_head(&self, branch: &BranchName) -> Result<(Qualified, Oid), ProjectError>;
+
+    /// Compute the canonical tags of this repository.
+    ///
+    /// A tag is canonical if at least `threshold` delegates point it to the same object.
+    /// Tags that more than one object qualify for are ambiguous, and are not canonical.
+    ///
+    /// Returns the tag reference names, eg. `refs/tags/v1.0`, along with their target.
+    fn canonical_tags(&self) -> Result<BTreeMap<RefString, Oid>, ProjectError>;
+
     /// Get the `reference` for the given `remote`.
     ///
     /// Returns `None` is the reference did not exist.
@@ -306,6 +325,9 @@ pub trait WriteRepository: ReadRepository {
         namespaces: impl Into<Namespaces>,
     ) -> Result<Vec<RefUpdate>, FetchError>;
     fn set_head(&self) -> Result<Oid, ProjectError>;
+    /// Set the top-level `refs/tags/*` references to the canonical tags, removing the ones that
+    /// are no longer canonical.
+    fn set_canonical_tags(&self) -> Result<BTreeMap<RefString, Oid>, ProjectError>;
     fn sign_refs<G: Signer>(&self, signer: &G) -> Result<SignedRefs<Verified>, Error>;
     fn raw(&self) -> &git2::Repository;
 }
@@ -1,6 +1,11 @@
+pub mod alternates;
+pub mod bundle;
+pub mod maintenance;
+pub mod mirror;
 pub mod transport;
 
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::{fs, io};
 
@@ -49,6 +54,8 @@ pub enum ProjectError {
     GitExt(#[from] git::Error),
     #[error("refs: {0}")]
     Refs(#[from] refs::Error),
+    #[error("no commit of the default branch is agreed on by {0} delegate(s)")]
+    NoQuorum(usize),
 }
 
 impl ProjectError {
@@ -163,6 +170,20 @@ impl Storage {
     }
 }
 
+/// Prefix of the temporary directories used to quarantine fetched objects.
+pub const QUARANTINE_PREFIX: &str = "quarantine-";
+/// Where the canonical references are copied to in a quarantine repository.
+const QUARANTINE_HAVES: &str = "refs/haves";
+
+/// Move a file to the given target path, unless the target already exists. Since objects are
+/// content-addressed, an existing target is always identical to the source.
+fn migrate_file(source: &Path, target: &Path) -> Result<(), io::Error> {
+    if target.exists() {
+        return Ok(());
+    }
+    fs::rename(source, target)
+}
+
 pub struct Repository {
     pub id: Id,
     pub(crate) backend: git2::Repository,
@@ -186,6 +207,8 @@ pub enum VerifyError {
     UnknownRef(RemoteId, git::RefString),
     #[error("missing reference `{1}` in remote `{0}`")]
     MissingRef(RemoteId, git::RefString),
+    #[error("invalid reference in remote `{0}`: {1}")]
+    Category(RemoteId, refs::CategoryError),
     #[error("git: {0}")]
     Git(#[from] git2::Error),
 }
@@ -206,6 +229,7 @@ impl Repository {
                 // TODO: Get ahold of user name and/or key.
                 config.set_str("user.name", "radicle")?;
                 config.set_str("user.email", "radicle@localhost")?;
+                alternates::disable_autogc(&mut config)?;
 
                 Ok(backend)
             }
@@ -220,21 +244,36 @@ impl Repository {
     /// as part of 'sigrefs'. Also verify that no signed reference is missing
     /// from the repository.
     pub fn verify(&self) -> Result<(), VerifyError> {
-        let mut remotes: HashMap<RemoteId, Refs> = self
-            .remotes()?
-            .map(|remote| {
-                let (id, remote) = remote?;
-                Ok((id, remote.refs.into()))
-            })
-            .collect::<Result<_, VerifyError>>()?;
+        let remotes = self.remote_ids()?.collect::<Result<BTreeSet<_>, _>>()?;
+
+        // Namespaces without 'sigrefs' can't be verified.
+        for entry in self.namespaced_references()? {
+            let (remote_id, _, _) = entry?;
+
+            if !remotes.contains(&remote_id) {
+                return Err(VerifyError::InvalidRemote(remote_id));
+            }
+        }
+        for remote in &remotes {
+            self.verify_remote(remote)?;
+        }
+        Ok(())
+    }
+
+    /// Verify the references of a single remote against its 'sigrefs', as well as the
+    /// remote's identity history.
+    pub fn verify_remote(&self, remote: &RemoteId) -> Result<(), VerifyError> {
+        let mut signed: Refs = self.remote(remote)?.refs.into();
 
         for entry in self.namespaced_references()? {
             let (remote_id, refname, oid) = entry?;
-            let remote = remotes
-                .get_mut(&remote_id)
-                .ok_or(VerifyError::InvalidRemote(remote_id))?;
+            if remote_id != *remote {
+                continue;
+            }
             let refname = RefString::from(refname);
-            let signed_oid = remote
+
+            refs::check_category(&refname).map_err(|e| VerifyError::Category(remote_id, e))?;
+            let signed_oid = signed
                 .remove(&refname)
                 .ok_or_else(|| VerifyError::UnknownRef(remote_id, refname.clone()))?;
 
@@ -242,18 +281,81 @@ impl Repository {
                 return Err(VerifyError::InvalidRefTarget(remote_id, refname, *oid));
             }
         }
+        if let Some((name, _)) = signed.into_iter().next() {
+            return Err(VerifyError::MissingRef(*remote, name));
+        }
+        self.identity(remote)?.verified(self.id)?;
+
+        Ok(())
+    }
+
+    /// Compute the canonical head of the default branch, along with the delegates that
+    /// agreed on it. See [`quorum`].
+    pub fn canonical_quorum(&self) -> Result<(Qualified, CanonicalHead), ProjectError> {
+        // TODO: In the `fork` function for example, we call Repository::project_identity again,
+        // This should only be necessary once.
+        let (_, project) = self.project_identity()?;
 
-        for (remote, refs) in remotes.into_iter() {
-            // The refs that are left in the map, are ones that were signed, but are not
-            // in the repository.
-            if let Some((name, _)) = refs.into_iter().next() {
-                return Err(VerifyError::MissingRef(remote, name));
+        self.branch_quorum(&project, project.default_branch())
+    }
+
+    /// Compute the canonical head of the given branch, along with the delegates that
+    /// agreed on it. See [`quorum`].
+    pub fn canonical_branch_quorum(
+        &self,
+        branch: &BranchName,
+    ) -> Result<(Qualified, CanonicalHead), ProjectError> {
+        let (_, project) = self.project_identity()?;
+
+        self.branch_quorum(&project, branch)
+    }
+
+    fn branch_quorum(
+        &self,
+        project: &Doc<Unverified>,
+        branch: &BranchName,
+    ) -> Result<(Qualified, CanonicalHead), ProjectError> {
+        let branch_ref = Qualified::from(lit::refs_heads(branch));
+
+        let mut heads = BTreeMap::new();
+        // Nb. The document isn't verified, so we make sure to skip revoked keys.
+        for delegate in project
+            .delegates
+            .iter()
+            .filter(|d| !project.revoked.contains(&d.id))
+        {
+            match self.reference_oid(&delegate.id, &branch_ref) {
+                Ok(oid) => {
+                    heads.insert(*delegate.id, oid);
+                }
+                Err(git::Error::NotFound(_)) => {
+                    log::debug!("Delegate {} is missing {:?}", delegate.id, branch_ref);
+                }
+                Err(git::Error::Git(e)) if git::is_not_found_err(&e) => {
+                    log::debug!("Delegate {} is missing {:?}", delegate.id, branch_ref);
+                }
+                Err(e) => return Err(e.into()),
             }
-            // Verify identity history of remote.
-            self.identity(&remote)?.verified(self.id)?;
         }
+        let head = quorum(&heads, project.threshold, &self.backend)?
+            .ok_or(ProjectError::NoQuorum(project.threshold))?;
 
-        Ok(())
+        Ok((branch_ref, head))
+    }
+
+    /// Return all namespaces that have at least one reference in this repository.
+    /// Unlike [`Repository::remote_ids`], this includes namespaces without signed refs.
+    pub fn namespaces(&self) -> Result<BTreeSet<RemoteId>, refs::Error> {
+        let mut namespaces = BTreeSet::new();
+
+        for r in self.backend.references_glob(NAMESPACES_GLOB.as_str())? {
+            let r = r?;
+            let name = r.name().ok_or(refs::Error::InvalidRef)?;
+            let (namespace, _) = git::parse_ref_namespaced::<RemoteId>(name)?;
+
+            namespaces.insert(namespace);
+        }
+        Ok(namespaces)
     }
 
     pub fn inspect(&self) -> Result<(), Error> {
@@ -364,6 +466,374 @@ impl Repository {
         Ok(remotes)
     }
 
+    /// Create a staging repository at the given path, to be used for fetching.
+    ///
+    /// The staging repository's object database is a quarantine: new objects are written
+    /// to it, while existing objects are read from this repository via an alternate. All
+    /// namespaced references of this repository are copied under [`QUARANTINE_HAVES`], so
+    /// that they can be used for negotiation, without being subject to verification.
+    fn quarantine(&self, path: &Path) -> Result<Repository, FetchError> {
+        let backend = git2::Repository::init_opts(
+            path,
+            git2::RepositoryInitOptions::new()
+                .bare(true)
+                .no_reinit(true)
+                .external_template(false),
+        )?;
+        let objects = self.backend.path().join("objects");
+        let objects = objects.to_str().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid object database path {}", objects.display()),
+            )
+        })?;
+        backend.odb()?.add_disk_alternate(objects)?;
+
+        for r in self.backend.references_glob("refs/namespaces/*")? {
+            let r = r?;
+            let (Some(name), Some(oid)) = (r.name(), r.target()) else {
+                // Ignore symbolic refs, eg. `HEAD`.
+                continue;
+            };
+            let name = name.strip_prefix("refs/").unwrap_or(name);
+
+            backend.reference(
+                &format!("{QUARANTINE_HAVES}/{name}"),
+                oid,
+                true,
+                "quarantine (radicle)",
+            )?;
+        }
+
+        Ok(Repository {
+            id: self.id,
+            backend,
+        })
+    }
+
+    /// Fetch into a quarantine with the given function, verify the quarantine as if it was
+    /// the canonical copy, and apply the resulting reference updates to this repository.
+    /// If `namespaces` is set, only the references of those namespaces are updated.
+    ///
+    /// See [`WriteRepository::fetch`] for details.
+    fn fetch_quarantined<F>(
+        &self,
+        namespaces: Option<&BTreeSet<RemoteId>>,
+        fetch: F,
+    ) -> Result<Vec<RefUpdate>, FetchError>
+    where
+        F: FnOnce(&Repository) -> Result<(), FetchError>,
+    {
+        // The quarantine lives inside the canonical repository, so that it is on the same
+        // file-system, and objects can be migrated by simply moving them.
+        let quarantine = tempfile::Builder::new()
+            .prefix(QUARANTINE_PREFIX)
+            .tempdir_in(self.backend.path())?;
+        let staging = self.quarantine(quarantine.path())?;
+
+        fetch(&staging)?;
+
+        // Verify the staging repo as if it was the canonical copy.
+        staging.verify()?;
+
+        // Compute the reference changes to be applied to the canonical copy. Since the
+        // staging repo is verified, so are the references we're about to update.
+        let updates = self.ref_updates(&staging, namespaces)?;
+
+        // Remotes whose keys were revoked aren't updated anymore.
+        let updates = self.skip_revoked(&staging, updates)?;
+
+        // Make sure no remote is rolled back to older signed refs.
+        self.verify_sigrefs_history(&staging, &updates)?;
+
+        if !updates.is_empty() {
+            self.migrate(quarantine.path())?;
+            self.apply_ref_updates(&updates)?;
+        }
+        // Set repository HEAD and tags for git cloning support.
+        self.set_head()?;
+        self.set_canonical_tags()?;
+
+        Ok(updates)
+    }
+
+    /// Migrate the objects of a quarantine created with [`Repository::quarantine`] into
+    /// this repository's object database.
+    fn migrate(&self, quarantine: &Path) -> Result<(), FetchError> {
+        let source = quarantine.join("objects");
+        let target = self.backend.path().join("objects");
+
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+
+            if name == "pack" {
+                // Move packs before their indexes, so that an index is never found
+                // without its pack.
+                let mut files = fs::read_dir(entry.path())?.collect::<Result<Vec<_>, _>>()?;
+                files.sort_by_key(|f| f.path().extension() != Some(OsStr::new("pack")));
+
+                for file in files {
+                    migrate_file(&file.path(), &target.join("pack").join(file.file_name()))?;
+                }
+            } else if name.len() == 2 && name.chars().all(|c| c.is_ascii_hexdigit()) {
+                // Loose objects.
+                let dir = target.join(&*name);
+                fs::create_dir_all(&dir)?;
+
+                for file in fs::read_dir(entry.path())? {
+                    let file = file?;
+                    migrate_file(&file.path(), &dir.join(file.file_name()))?;
+                }
+            }
+        }
+        // Make sure the new packs are picked up.
+        self.backend.odb()?.refresh()?;
+
+        Ok(())
+    }
+
+    /// Compute the reference updates that would make the direct namespaced references
+    /// in this repository identical to the ones in `staging`, optionally restricted to the
+    /// given `namespaces`. References that are only found in this repository are deleted,
+    /// ie. pruned, but only within namespaces that were fetched into `staging`: a namespace
+    /// the remote doesn't have is left untouched.
+    fn ref_updates(
+        &self,
+        staging: &Repository,
+        namespaces: Option<&BTreeSet<RemoteId>>,
+    ) -> Result<Vec<RefUpdate>, FetchError> {
+        let targets = |repo: &git2::Repository| -> Result<BTreeMap<RefString, Oid>, FetchError> {
+            let mut refs = BTreeMap::new();
+
+            for r in repo.references_glob(NAMESPACES_GLOB.as_str())? {
+                let r = r?;
+                let name = r.name().ok_or(Error::InvalidRef)?;
+                let Some(oid) = r.target() else {
+                    // Ignore symbolic refs, eg. `HEAD`.
+                    continue;
+                };
+                let name = RefString::try_from(name).map_err(|_| Error::InvalidRef)?;
+
+                let Ok((namespace, _)) = git::parse_ref_namespaced::<RemoteId>(name.as_str())
+                else {
+                    log::warn!("Invalid ref `{}` detected; aborting fetch", name);
+                    return Err(Error::InvalidRef.into());
+                };
+                if namespaces.map_or(false, |ns| !ns.contains(&namespace)) {
+                    continue;
+                }
+                refs.insert(name, oid.into());
+            }
+            Ok(refs)
+        };
+        let old = targets(&self.backend)?;
+        let new = targets(&staging.backend)?;
+        let mut updates = Vec::new();
+
+        for (name, oid) in &new {
+            match old.get(name) {
+                Some(prev) if prev == oid => {}
+                Some(prev) => updates.push(RefUpdate::Updated {
+                    name: name.clone(),
+                    old: *prev,
+                    new: *oid,
+                }),
+                None => updates.push(RefUpdate::Created {
+                    name: name.clone(),
+                    oid: *oid,
+                }),
+            }
+        }
+        let fetched = new
+            .keys()
+            .map(|name| {
+                git::parse_ref_namespaced::<RemoteId>(name.as_str())
+                    .map(|(namespace, _)| namespace)
+                    .map_err(Error::from)
+            })
+            .collect::<Result<BTreeSet<_>, _>>()?;
+
+        for (name, oid) in old {
+            if new.contains_key(&name) {
+                continue;
+            }
+            let (namespace, _) =
+                git::parse_ref_namespaced::<RemoteId>(name.as_str()).map_err(Error::from)?;
+
+            if fetched.contains(&namespace) {
+                updates.push(RefUpdate::Deleted { name, oid });
+            }
+        }
+        Ok(updates)
+    }
+
+    /// Skip the reference updates of remotes whose keys are revoked in the project identity
+    /// of `staging`, if their new signed refs were made from the revocation on. Their
+    /// references are kept as they were. Signed refs made before the revocation are still
+    /// accepted.
+    fn skip_revoked(
+        &self,
+        staging: &Repository,
+        updates: Vec<RefUpdate>,
+    ) -> Result<Vec<RefUpdate>, FetchError> {
+        let (head, _) = staging.project_identity()?;
+        let identity = Identity::load_at(head, staging).map_err(VerifyError::from)?;
+        if identity.revoked.is_empty() {
+            return Ok(updates);
+        }
+        let mut revoked = BTreeMap::new();
+        let mut skipped = Vec::with_capacity(updates.len());
+
+        for update in updates {
+            let name = match &update {
+                RefUpdate::Updated { name, .. }
+                | RefUpdate::Created { name, .. }
+                | RefUpdate::Deleted { name, .. }
+                | RefUpdate::Skipped { name, .. } => name,
+            };
+            let (remote, _) =
+                git::parse_ref_namespaced::<RemoteId>(name.as_str()).map_err(Error::from)?;
+
+            let Some(revocation) = identity.revoked.get(&remote) else {
+                skipped.push(update);
+                continue;
+            };
+            let is_revoked = match revoked.get(&remote) {
+                Some(is_revoked) => *is_revoked,
+                None => {
+                    let is_revoked = staging.is_signed_since(&remote, *revocation)?;
+                    revoked.insert(remote, is_revoked);
+                    is_revoked
+                }
+            };
+            if !is_revoked {
+                skipped.push(update);
+                continue;
+            }
+            log::warn!("Skipping update `{update}` of remote {remote}, revoked in {revocation}");
+
+            match update {
+                RefUpdate::Updated { name, old: oid, .. } | RefUpdate::Deleted { name, oid } => {
+                    skipped.push(RefUpdate::Skipped { name, oid });
+                }
+                RefUpdate::Created { .. } | RefUpdate::Skipped { .. } => {}
+            }
+        }
+        Ok(skipped)
+    }
+
+    /// Whether the signed refs of the given remote were made against the given identity
+    /// commit or a later one, going by the identity branch they sign. Signed refs of an
+    /// identity branch that diverged from the given commit are considered later.
+    fn is_signed_since(&self, remote: &RemoteId, commit: Oid) -> Result<bool, FetchError> {
+        let id = match self.backend.refname_to_id(&git::refs::storage::id(remote)) {
+            Ok(id) => id,
+            // Without an identity branch, the signed refs can't be placed in history.
+            Err(e) if ext::is_not_found_err(&e) => return Ok(true),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(!self.backend.graph_descendant_of(*commit, id)?)
+    }
+
+    /// Check that the signed refs updates of the given reference updates don't regress
+    /// the signed refs history of any remote, ie. that the new signed refs descend from the
+    /// current ones, and that a signed parent isn't dropped.
+    fn verify_sigrefs_history(
+        &self,
+        staging: &Repository,
+        updates: &[RefUpdate],
+    ) -> Result<(), FetchError> {
+        for update in updates {
+            let RefUpdate::Updated { name, old, new } = update else {
+                continue;
+            };
+            let (remote, refname) =
+                git::parse_ref_namespaced::<RemoteId>(name.as_str()).map_err(Error::from)?;
+            if refname != *refs::SIGREFS_BRANCH {
+                continue;
+            }
+            if !staging.backend.graph_descendant_of(**new, **old)? {
+                return Err(FetchError::Regression(name.clone()));
+            }
+            let current = SignedRefs::load_at(*old, &remote, self)?;
+            let updated = SignedRefs::load_at(*new, &remote, staging)?;
+
+            if current.parent.is_some() && updated.parent.is_none() {
+                return Err(FetchError::Regression(name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply reference updates atomically. All references are locked and checked against
+    /// their expected previous value before any of them is written. If writing fails,
+    /// the references that were already written are restored.
+    fn apply_ref_updates(&self, updates: &[RefUpdate]) -> Result<(), FetchError> {
+        const REFLOG_MESSAGE: &str = "fetch (radicle)";
+
+        let mut tx = self.backend.transaction()?;
+
+        for update in updates {
+            let (name, expected) = match update {
+                RefUpdate::Updated { name, old, .. } => (name, Some(*old)),
+                RefUpdate::Deleted { name, oid } => (name, Some(*oid)),
+                RefUpdate::Created { name, .. } => (name, None),
+                RefUpdate::Skipped { .. } => continue,
+            };
+            tx.lock_ref(name.as_str())?;
+
+            // Make sure the reference wasn't modified since we computed the update.
+            let current = match self.backend.refname_to_id(name.as_str()) {
+                Ok(oid) => Some(Oid::from(oid)),
+                Err(e) if ext::is_not_found_err(&e) => None,
+                Err(e) => return Err(e.into()),
+            };
+            if current != expected {
+                return Err(FetchError::Conflict(name.clone()));
+            }
+        }
+        for update in updates {
+            match update {
+                RefUpdate::Updated { name, new: oid, .. } | RefUpdate::Created { name, oid } => {
+                    tx.set_target(name.as_str(), (*oid).into(), None, REFLOG_MESSAGE)?;
+                }
+                RefUpdate::Deleted { name, .. } => {
+                    tx.remove(name.as_str())?;
+                }
+                RefUpdate::Skipped { .. } => {}
+            }
+        }
+
+        if let Err(err) = tx.commit() {
+            log::error!("Error applying fetched ref updates: {err}; rolling back..");
+
+            for update in updates {
+                let result = match update {
+                    RefUpdate::Updated { name, old: oid, .. }
+                    | RefUpdate::Deleted { name, oid } => self
+                        .backend
+                        .reference(name.as_str(), (*oid).into(), true, REFLOG_MESSAGE)
+                        .map(|_| ()),
+                    RefUpdate::Created { name, .. } => {
+                        match self.backend.find_reference(name.as_str()) {
+                            Ok(mut r) => r.delete(),
+                            Err(e) if ext::is_not_found_err(&e) => Ok(()),
+                            Err(e) => Err(e),
+                        }
+                    }
+                    RefUpdate::Skipped { .. } => Ok(()),
+                };
+                if let Err(e) = result {
+                    log::error!("Error rolling back ref update `{update}`: {e}");
+                }
+            }
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
     /// Return all references that are namespaced, ie. that are signed by a node and verified.
     fn namespaced_references(
         &self,
@@ -392,6 +862,106 @@ impl Repository {
     }
 }
 
+/// The canonical head of a branch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalHead {
+    /// The canonical commit.
+    pub oid: Oid,
+    /// The delegates that have the canonical commit in the history of their branch.
+    pub delegates: BTreeSet<RemoteId>,
+}
+
+/// Compute the newest commit that at least `threshold` of the given delegate heads have in
+/// their history. Returns `None` if there is no such commit.
+///
+/// Since the commits that are in the history of enough heads are the common ancestors of
+/// subsets of these heads, the candidates are the heads and their merge bases. If the newest
+/// candidates diverge, which can happen when more than one subset of delegates reaches the
+/// threshold, their merge base is used.
+pub fn quorum(
+    heads: &BTreeMap<RemoteId, Oid>,
+    threshold: usize,
+    repo: &git2::Repository,
+) -> Result<Option<CanonicalHead>, git2::Error> {
+    // Whether `ancestor` is in the history of `head`.
+    let contains = |head: Oid, ancestor: Oid| -> Result<bool, git2::Error> {
+        Ok(head == ancestor || repo.graph_descendant_of(*head, *ancestor)?)
+    };
+    let merge_base = |a: Oid, b: Oid| -> Result<Option<Oid>, git2::Error> {
+        match repo.merge_base(*a, *b) {
+            Ok(base) => Ok(Some(base.into())),
+            Err(e) if ext::is_not_found_err(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    };
+
+    let mut candidates = heads.values().copied().collect::<BTreeSet<_>>();
+    loop {
+        let mut bases = BTreeSet::new();
+        for (i, a) in candidates.iter().enumerate() {
+            for b in candidates.iter().skip(i + 1) {
+                if let Some(base) = merge_base(*a, *b)? {
+                    if !candidates.contains(&base) {
+                        bases.insert(base);
+                    }
+                }
+            }
+        }
+        if bases.is_empty() {
+            break;
+        }
+        candidates.extend(bases);
+    }
+
+    let mut agreed = Vec::new();
+    for candidate in candidates {
+        let mut votes = 0;
+        for head in heads.values() {
+            if contains(*head, candidate)? {
+                votes += 1;
+            }
+        }
+        if votes >= threshold {
+            agreed.push(candidate);
+        }
+    }
+
+    let mut newest = Vec::new();
+    for candidate in &agreed {
+        let mut is_newest = true;
+        for other in &agreed {
+            if other != candidate && contains(*other, *candidate)? {
+                is_newest = false;
+                break;
+            }
+        }
+        if is_newest {
+            newest.push(*candidate);
+        }
+    }
+
+    let oid = match newest.as_slice() {
+        [] => return Ok(None),
+        [oid] => *oid,
+        oids => {
+            let oids = oids.iter().map(|oid| **oid).collect::<Vec<_>>();
+            match repo.merge_base_many(&oids) {
+                Ok(oid) => oid.into(),
+                Err(e) if ext::is_not_found_err(&e) => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        }
+    };
+
+    let mut delegates = BTreeSet::new();
+    for (delegate, head) in heads {
+        if contains(*head, oid)? {
+            delegates.insert(*delegate);
+        }
+    }
+    Ok(Some(CanonicalHead { oid, delegates }))
+}
+
 impl ReadRepository for Repository {
     fn is_empty(&self) -> Result<bool, git2::Error> {
         Ok(self.remotes()?.next().is_none())
@@ -448,7 +1018,6 @@ impl ReadRepository for Repository {
     }
 
     fn references(&self, remote: &RemoteId) -> Result<Refs, Error> {
-        // TODO: Only return known refs, eg. heads/ rad/ tags/ etc..
         let entries = self
             .backend
             .references_glob(format!("refs/namespaces/{remote}/*").as_str())?;
@@ -460,6 +1029,12 @@ impl ReadRepository for Repository {
             let (_, refname) = git::parse_ref::<RemoteId>(name)?;
             let oid = e.target().ok_or(Error::InvalidRef)?;
 
+            // Only references of known categories may be signed. Others are skipped, so that
+            // they don't prevent the rest of the namespace from being signed.
+            if let Err(err) = refs::check_category(&refname) {
+                log::warn!("Skipping reference `{name}` of remote {remote}: {err}");
+                continue;
+            }
             refs.insert(refname.into(), oid.into());
         }
         Ok(refs.into())
@@ -492,26 +1067,60 @@ impl ReadRepository for Repository {
     }
 
     fn canonical_head(&self) -> Result<(Qualified, Oid), ProjectError> {
-        // TODO: In the `fork` function for example, we call Repository::project_identity again,
-        // This should only be necessary once.
+        self.canonical_quorum()
+            .map(|(branch, head)| (branch, head.oid))
+    }
+
+    fn canonical_branch_head(&self, branch: &BranchName) -> Result<(Qualified, Oid), ProjectError> {
+        self.canonical_branch_quorum(branch)
+            .map(|(branch, head)| (branch, head.oid))
+    }
+
+    fn canonical_tags(&self) -> Result<BTreeMap<RefString, Oid>, ProjectError> {
         let (_, project) = self.project_identity()?;
-        let branch_ref = Qualified::from(lit::refs_heads(&project.default_branch));
-        let raw = self.raw();
+        // Number of delegates pointing each tag to a given object.
+        let mut votes: BTreeMap<RefString, BTreeMap<Oid, usize>> = BTreeMap::new();
 
-        let mut heads = Vec::new();
-        for delegate in project.delegates.iter() {
-            let r = self.reference_oid(&delegate.id, &branch_ref)?.into();
+        for delegate in project
+            .delegates
+            .iter()
+            .filter(|d| !project.revoked.contains(&d.id))
+        {
+            let remote: &RemoteId = &delegate.id;
+            let glob = format!("refs/namespaces/{remote}/refs/tags/*");
 
-            heads.push(r);
+            for r in self.backend.references_glob(&glob)? {
+                let r = r?;
+                let Some(oid) = r.target() else {
+                    continue;
+                };
+                let name = r.name().ok_or(Error::InvalidRef)?;
+                let (_, refname) =
+                    git::parse_ref_namespaced::<RemoteId>(name).map_err(Error::from)?;
+
+                *votes
+                    .entry(refname.to_ref_string())
+                    .or_default()
+                    .entry(oid.into())
+                    .or_default() += 1;
+            }
         }
 
-        let oid = match heads.as_slice() {
-            [head] => Ok(*head),
-            // FIXME: This branch is not tested.
-            heads => raw.merge_base_many(heads),
-        }?;
+        let mut tags = BTreeMap::new();
+        for (name, targets) in votes {
+            let mut agreed = targets.into_iter().filter(|(_, n)| *n >= project.threshold);
 
-        Ok((branch_ref, oid.into()))
+            match (agreed.next(), agreed.next()) {
+                (Some((oid, _)), None) => {
+                    tags.insert(name, oid);
+                }
+                (Some(_), Some(_)) => {
+                    log::warn!("Delegates of {} disagree on tag `{name}`", self.id);
+                }
+                (None, _) => {}
+            }
+        }
+        Ok(tags)
     }
 }
 
@@ -524,21 +1133,26 @@ impl WriteRepository for Repository {
     /// repository because if the updates were to be invalid, we'd be allowing others to
     /// read this invalid state. We also don't want to lock our repositories during the fetch
     /// or verification, as this will make the repositories unavailable. Therefore, we choose
-    /// to perform the fetch into a "staging" copy of the given repository we're fetching, and
-    /// then transfer the changes to the canonical, public copy of the repository.
+    /// to perform the fetch into a "staging" repository, and then transfer the changes to the
+    /// canonical, public copy of the repository.
     ///
-    /// To do this, we first create a temporary directory, and clone the canonical repo into it.
-    /// This local clone takes advantage of the fact that both repositories live on the same
-    /// host (or even file-system). We now have a "staging" copy and the canonical copy.
+    /// The staging repository is created in a temporary directory inside the canonical
+    /// repository. Instead of holding a copy of the canonical repository, its object database
+    /// acts as a *quarantine*: objects we already have are read from the canonical repository
+    /// through an alternate, while new objects fetched from the network are only ever written
+    /// to the quarantine. The canonical references are copied over so that objects we already
+    /// have aren't fetched again.
     ///
-    /// We then fetch the *remote* repo into the *staging* copy. We turn off pruning because we
-    /// don't want to accidentally delete any objects before verification is complete.
+    /// We then fetch the *remote* repo into the *staging* repo, and verify it through the
+    /// usual verification process.
     ///
-    /// We proceed to verify the staging copy through the usual verification process.
-    ///
-    /// If verification succeeds, we fetch from the staging copy into the canonical repo,
-    /// with pruning *on*, and discard the staging copy. If it fails, we just discard the
-    /// staging copy.
+    /// If verification succeeds, we compute the set of reference changes between the staging
+    /// and canonical repositories, migrate the quarantined objects into the canonical object
+    /// database, and apply all reference changes, including deletions, in a single locked
+    /// transaction. References are only ever deleted from namespaces that the remote has;
+    /// namespaces it doesn't have, eg. our own, are left as they are. If anything fails along
+    /// the way, the canonical references are left unchanged, and the quarantine is discarded
+    /// along with the objects in it.
     ///
     fn fetch(
         &mut self,
@@ -547,45 +1161,29 @@ impl WriteRepository for Repository {
     ) -> Result<Vec<RefUpdate>, FetchError> {
         // The steps are summarized in the following diagram:
         //
-        //     staging <- git-clone -- local (canonical) # create staging copy
+        //     staging -- alternate -> local (canonical) # create quarantine
         //     staging <- git-fetch -- remote            # fetch from remote
         //
         //     ... verify ...
         //
-        //     local <- git-fetch -- staging             # fetch from staging copy
+        //     local <- objects -- staging               # migrate quarantined objects
+        //     local <- ref-transaction                  # apply ref updates atomically
         //
 
         let namespace = match namespaces.into() {
             Namespaces::All => None,
             Namespaces::One(ns) => Some(ns),
         };
+        let filter = namespace.map(|ns| BTreeSet::from([ns]));
 
-        let mut updates = Vec::new();
-        let mut callbacks = git2::RemoteCallbacks::new();
-        let tempdir = tempfile::tempdir()?;
-
-        // Create staging copy.
-        let staging = {
-            let mut builder = git2::build::RepoBuilder::new();
-            let path = tempdir.path().join("git");
-            let staging_repo = builder
-                .bare(true)
-                // Using `clone_local` will try to hard-link the ODBs for better performance.
-                // TODO: Due to this, I think we'll have to run GC when there is a failure.
-                .clone_local(git2::build::CloneLocal::Local)
-                .clone(
-                    git::url::File::new(self.backend.path().to_path_buf())
-                        .to_string()
-                        .as_str(),
-                    &path,
-                )?;
-
+        self.fetch_quarantined(filter.as_ref(), |staging| {
             // In case we fetch an invalid update, we want to make sure nothing is deleted.
             let mut opts = git2::FetchOptions::default();
             opts.prune(git2::FetchPrune::Off);
 
-            // Fetch from the remote into the staging copy.
-            staging_repo
+            // Fetch from the remote into the staging repo.
+            staging
+                .backend
                 .remote_anonymous(
                     remote::Url {
                         node: *node,
@@ -595,74 +1193,74 @@ impl WriteRepository for Repository {
                     .to_string()
                     .as_str(),
                 )?
-                .fetch(&["refs/*:refs/*"], Some(&mut opts), None)?;
-
-            // Verify the staging copy as if it was the canonical copy.
-            Repository {
-                id: self.id,
-                backend: staging_repo,
-            }
-            .verify()?;
-
-            path
-        };
-
-        callbacks.update_tips(|name, old, new| {
-            if let Ok(name) = git::RefString::try_from(name) {
-                if name.to_namespaced().is_some() {
-                    updates.push(RefUpdate::from(name, old, new));
-                    // Returning `true` ensures the process is not aborted.
-                    return true;
-                }
-            }
-            log::warn!("Invalid ref `{}` detected; aborting fetch", name);
-
-            false
-        });
-
-        {
-            let mut remote = self
-                .backend
-                .remote_anonymous(git::url::File::new(staging).to_string().as_str())?;
-            let mut opts = git2::FetchOptions::default();
-            opts.remote_callbacks(callbacks);
-
-            let refspec = if let Some(namespace) = namespace {
-                format!("refs/namespaces/{namespace}/refs/*:refs/namespaces/{namespace}/refs/*")
-            } else {
-                "refs/namespaces/*:refs/namespaces/*".to_owned()
-            };
-            // TODO: Make sure we verify before pruning, as pruning may get us into
-            // a state we can't roll back.
-            opts.prune(git2::FetchPrune::On);
-            // Fetch from the staging copy into the canonical repo.
-            remote.fetch(&[refspec], Some(&mut opts), None)?;
-        }
-        // Set repository HEAD for git cloning support.
-        self.set_head()?;
+                .fetch(
+                    &["refs/namespaces/*:refs/namespaces/*"],
+                    Some(&mut opts),
+                    None,
+                )?;
 
-        Ok(updates)
+            Ok(())
+        })
     }
 
     fn set_head(&self) -> Result<Oid, ProjectError> {
         let head_ref = refname!("HEAD");
-        let (branch_ref, head) = self.canonical_head()?;
+        let (branch_ref, head) = self.canonical_quorum()?;
 
-        log::debug!("Setting ref {:?} -> {:?}", &branch_ref, head);
+        log::debug!(
+            "Setting ref {:?} -> {:?}, agreed on by {:?}",
+            &branch_ref,
+            head.oid,
+            head.delegates
+        );
         self.raw()
-            .reference(&branch_ref, *head, true, "set-local-branch (radicle)")?;
+            .reference(&branch_ref, *head.oid, true, "set-local-branch (radicle)")?;
 
         log::debug!("Setting ref {:?} -> {:?}", head_ref, branch_ref);
         self.raw()
             .reference_symbolic(&head_ref, &branch_ref, true, "set-head (radicle)")?;
 
-        Ok(head)
+        Ok(head.oid)
+    }
+
+    fn set_canonical_tags(&self) -> Result<BTreeMap<RefString, Oid>, ProjectError> {
+        let tags = self.canonical_tags()?;
+        let stale = self
+            .backend
+            .references_glob("refs/tags/*")?
+            .map(|r| r.map(|r| r.name().map(ToOwned::to_owned)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for name in stale.into_iter().flatten() {
+            if tags.keys().all(|tag| tag.as_str() != name) {
+                log::debug!("Removing non-canonical tag {:?}", name);
+                self.backend.find_reference(&name)?.delete()?;
+            }
+        }
+        for (name, oid) in &tags {
+            log::debug!("Setting canonical tag {:?} -> {:?}", name, oid);
+            self.backend.reference(
+                name.as_str(),
+                (*oid).into(),
+                true,
+                "set-canonical-tag (radicle)",
+            )?;
+        }
+        Ok(tags)
     }
 
     fn sign_refs<G: Signer>(&self, signer: &G) -> Result<SignedRefs<Verified>, Error> {
         let remote = signer.public_key();
         let refs = self.references(remote)?;
-        let signed = refs.signed(signer)?;
+        let parent = match self
+            .backend
+            .refname_to_id(&refs::SIGREFS_BRANCH.with_namespace(remote.into()))
+        {
+            Ok(oid) => Some(oid.into()),
+            Err(e) if ext::is_not_found_err(&e) => None,
+            Err(e) => return Err(e.into()),
+        };
+        let signed = refs.signed(parent, signer)?;
 
         signed.save(remote, self)?;
 
@@ -940,6 +1538,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fetch_known_objects() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice_signer = MockSigner::default();
+        let alice_pk = *alice_signer.public_key();
+        let alice = fixtures::storage(tmp.path().join("alice"), &alice_signer).unwrap();
+        let bob = Storage::open(tmp.path().join("bob")).unwrap();
+        let proj = *alice.inventory().unwrap().first().unwrap();
+        let mut bob_repo = bob.repository(proj).unwrap();
+        // Number of objects in the object database, counting duplicates.
+        let objects = |repo: &Repository| {
+            let mut count = 0;
+            repo.backend
+                .odb()
+                .unwrap()
+                .foreach(|_| {
+                    count += 1;
+                    true
+                })
+                .unwrap();
+            count
+        };
+
+        bob_repo.fetch(&alice_pk, alice_pk).unwrap();
+        let fetched = objects(&bob_repo);
+        assert!(fetched > 0);
+
+        // Fetching again doesn't transfer the objects Bob already has.
+        bob_repo.fetch(&alice_pk, alice_pk).unwrap();
+        assert_eq!(objects(&bob_repo), fetched);
+    }
+
     #[test]
     fn test_fetch_update() {
         let tmp = tempfile::tempdir().unwrap();
@@ -997,6 +1627,363 @@ mod tests {
         assert_eq!(bob_master.target().unwrap(), alice_head);
     }
 
+    #[test]
+    fn test_fetch_all_keeps_unfetched_namespaces() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = Storage::open(tmp.path().join("alice/storage")).unwrap();
+        let bob = Storage::open(tmp.path().join("bob/storage")).unwrap();
+        let alice_signer = MockSigner::new(&mut fastrand::Rng::new());
+        let alice_id = alice_signer.public_key();
+        let bob_signer = MockSigner::new(&mut fastrand::Rng::new());
+        let bob_id = bob_signer.public_key();
+        let (proj_id, _, _, _) =
+            fixtures::project(tmp.path().join("alice/project"), &alice, &alice_signer).unwrap();
+
+        transport::remote::mock::register(alice_id, alice.path());
+
+        // Bob fetches Alice's refs, and forks the project.
+        bob.repository(proj_id)
+            .unwrap()
+            .fetch(alice_id, *alice_id)
+            .unwrap();
+        rad::fork(proj_id, None, &bob_signer, &bob).unwrap();
+
+        let bob_refs = bob.repository(proj_id).unwrap().references(bob_id).unwrap();
+        assert!(!bob_refs.is_empty());
+
+        // Bob fetches all namespaces from Alice, who doesn't have Bob's.
+        let updates = bob
+            .repository(proj_id)
+            .unwrap()
+            .fetch(alice_id, Namespaces::All)
+            .unwrap();
+
+        assert!(updates
+            .iter()
+            .all(|u| !matches!(u, RefUpdate::Deleted { .. })));
+        assert_eq!(
+            bob.repository(proj_id).unwrap().references(bob_id).unwrap(),
+            bob_refs
+        );
+    }
+
+    #[test]
+    fn test_fetch_sigrefs_regression() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = Storage::open(tmp.path().join("alice/storage")).unwrap();
+        let bob = Storage::open(tmp.path().join("bob/storage")).unwrap();
+        let alice_signer = MockSigner::new(&mut fastrand::Rng::new());
+        let alice_id = alice_signer.public_key();
+        let (proj_id, _, proj_repo, alice_head) =
+            fixtures::project(tmp.path().join("alice/project"), &alice, &alice_signer).unwrap();
+        let refname = Qualified::from_refstr(git::refname!("refs/heads/master")).unwrap();
+        let sigrefs = SIGREFS_BRANCH.with_namespace(alice_id.into());
+        let master = refname.with_namespace(alice_id.into());
+        let alice_repo = alice.repository(proj_id).unwrap();
+        let old_sigrefs = alice_repo.backend.refname_to_id(&sigrefs).unwrap();
+        let old_master = alice_repo.backend.refname_to_id(&master).unwrap();
+
+        transport::remote::mock::register(alice_id, alice.path());
+
+        bob.repository(proj_id)
+            .unwrap()
+            .fetch(alice_id, *alice_id)
+            .unwrap();
+
+        // Alice makes a change, which is signed along with the previous signed refs.
+        let alice_head = proj_repo.find_commit(alice_head).unwrap();
+        let alice_sig = git2::Signature::now("Alice", "alice@radicle.xyz").unwrap();
+        git::commit(
+            &proj_repo,
+            &alice_head,
+            &refname,
+            "Making changes",
+            &alice_sig,
+        )
+        .unwrap();
+        git::push(&proj_repo, "rad", [(&refname, &refname)]).unwrap();
+
+        let signed = alice_repo.sign_refs(&alice_signer).unwrap();
+        assert_eq!(signed.parent, Some(old_sigrefs.into()));
+
+        let new_sigrefs = alice_repo.backend.refname_to_id(&sigrefs).unwrap();
+        let new_master = alice_repo.backend.refname_to_id(&master).unwrap();
+
+        bob.repository(proj_id)
+            .unwrap()
+            .fetch(alice_id, *alice_id)
+            .unwrap();
+
+        // Alice's older, validly signed refs are served to Bob.
+        alice_repo
+            .backend
+            .reference(&sigrefs, old_sigrefs, true, "")
+            .unwrap();
+        alice_repo
+            .backend
+            .reference(&master, old_master, true, "")
+            .unwrap();
+
+        assert_matches!(
+            bob.repository(proj_id).unwrap().fetch(alice_id, *alice_id),
+            Err(FetchError::Regression(_))
+        );
+
+        // The older signed refs are replayed on top of the newer ones.
+        let tree = alice_repo
+            .backend
+            .find_commit(old_sigrefs)
+            .unwrap()
+            .tree()
+            .unwrap();
+        let parent = alice_repo.backend.find_commit(new_sigrefs).unwrap();
+        let replay = alice_repo
+            .backend
+            .commit(None, &alice_sig, &alice_sig, "Replay", &tree, &[&parent])
+            .unwrap();
+        alice_repo
+            .backend
+            .reference(&sigrefs, replay, true, "")
+            .unwrap();
+
+        assert_matches!(
+            bob.repository(proj_id).unwrap().fetch(alice_id, *alice_id),
+            Err(FetchError::Regression(_))
+        );
+
+        // Bob's storage is left untouched.
+        let bob_repo = bob.repository(proj_id).unwrap();
+        assert_eq!(
+            bob_repo.backend.refname_to_id(&sigrefs).unwrap(),
+            new_sigrefs
+        );
+        assert_eq!(bob_repo.backend.refname_to_id(&master).unwrap(), new_master);
+    }
+
+    #[test]
+    fn test_fetch_revoked() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = Storage::open(tmp.path().join("alice/storage")).unwrap();
+        let eve = Storage::open(tmp.path().join("eve/storage")).unwrap();
+        let alice_signer = MockSigner::new(&mut fastrand::Rng::new());
+        let bob_signer = MockSigner::new(&mut fastrand::Rng::new());
+        let alice_id = alice_signer.public_key();
+        let bob_id = bob_signer.public_key();
+        let (proj_id, _, _, _) =
+            fixtures::project(tmp.path().join("alice/project"), &alice, &alice_signer).unwrap();
+        let alice_repo = alice.repository(proj_id).unwrap();
+        let bob_sigrefs = SIGREFS_BRANCH.with_namespace(bob_id.into());
+
+        transport::remote::mock::register(alice_id, alice.path());
+
+        // Bob forks the project, and is made a delegate.
+        rad::fork(proj_id, Some(alice_id), &bob_signer, &alice).unwrap();
+        let mut doc = alice.get(alice_id, proj_id).unwrap().unwrap();
+        assert!(doc.delegate("bob".to_owned(), *bob_id));
+        let (_, sig) = doc.sign(&alice_signer).unwrap();
+        doc.update(alice_id, "Add bob", &[(alice_id, sig)], &alice_repo)
+            .unwrap();
+
+        // Bob's key is revoked.
+        doc.revoke(bob_id).unwrap();
+        let (_, sig) = doc.sign(&alice_signer).unwrap();
+        let revocation = doc
+            .update(alice_id, "Revoke bob", &[(alice_id, sig)], &alice_repo)
+            .unwrap();
+        alice_repo.sign_refs(&alice_signer).unwrap();
+
+        // Bob's refs were signed before the revocation, and are still fetched.
+        eve.repository(proj_id)
+            .unwrap()
+            .fetch(alice_id, Namespaces::All)
+            .unwrap();
+
+        let signed = alice_repo.backend.refname_to_id(&bob_sigrefs).unwrap();
+        let eve_repo = eve.repository(proj_id).unwrap();
+        assert_eq!(
+            eve_repo.backend.refname_to_id(&bob_sigrefs).unwrap(),
+            signed
+        );
+
+        // Bob's refs are signed again, against the identity that revoked him.
+        alice_repo
+            .backend
+            .reference(&git::refs::storage::id(bob_id), *revocation, true, "")
+            .unwrap();
+        alice_repo.sign_refs(&bob_signer).unwrap();
+
+        // They aren't fetched anymore.
+        let updates = eve
+            .repository(proj_id)
+            .unwrap()
+            .fetch(alice_id, Namespaces::All)
+            .unwrap();
+        assert!(!updates.is_empty());
+        assert!(updates
+            .iter()
+            .all(|u| matches!(u, RefUpdate::Skipped { .. })));
+        assert_eq!(
+            eve_repo.backend.refname_to_id(&bob_sigrefs).unwrap(),
+            signed
+        );
+    }
+
+    #[test]
+    fn test_fetch_verify_failure() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = Storage::open(tmp.path().join("alice/storage")).unwrap();
+        let bob = Storage::open(tmp.path().join("bob/storage")).unwrap();
+        let alice_signer = MockSigner::new(&mut fastrand::Rng::new());
+        let alice_id = alice_signer.public_key();
+        let (proj_id, _, proj_repo, alice_head) =
+            fixtures::project(tmp.path().join("alice/project"), &alice, &alice_signer).unwrap();
+        let refname = Qualified::from_refstr(git::refname!("refs/heads/master")).unwrap();
+
+        transport::remote::mock::register(alice_id, alice.path());
+
+        // Have Bob fetch Alice's refs.
+        bob.repository(proj_id)
+            .unwrap()
+            .fetch(alice_id, *alice_id)
+            .unwrap();
+
+        let bob_refs = bob
+            .repository(proj_id)
+            .unwrap()
+            .references(alice_id)
+            .unwrap();
+
+        // Alice makes a signed change, and then adds a reference that isn't signed.
+        let alice_proj_storage = alice.repository(proj_id).unwrap();
+        let alice_head = proj_repo.find_commit(alice_head).unwrap();
+        let alice_sig = git2::Signature::now("Alice", "alice@radicle.xyz").unwrap();
+        let alice_head = git::commit(
+            &proj_repo,
+            &alice_head,
+            &refname,
+            "Making changes",
+            &alice_sig,
+        )
+        .unwrap()
+        .id();
+        git::push(&proj_repo, "rad", [(&refname, &refname)]).unwrap();
+        alice_proj_storage.sign_refs(&alice_signer).unwrap();
+        alice_proj_storage
+            .raw()
+            .reference(
+                &format!("refs/namespaces/{alice_id}/refs/heads/unsigned"),
+                alice_head,
+                false,
+                "",
+            )
+            .unwrap();
+
+        // Bob's fetch fails verification.
+        assert_matches!(
+            bob.repository(proj_id).unwrap().fetch(alice_id, *alice_id),
+            Err(FetchError::Verify(VerifyError::UnknownRef(..)))
+        );
+
+        // Bob's storage is left untouched.
+        let bob_repo = bob.repository(proj_id).unwrap();
+        assert_eq!(bob_repo.references(alice_id).unwrap(), bob_refs);
+        // The fetched objects were discarded along with the quarantine.
+        assert!(bob_repo.backend.find_commit(alice_head).is_err());
+        assert!(!fs::read_dir(bob_repo.path()).unwrap().any(|e| e
+            .unwrap()
+            .file_name()
+            .to_string_lossy()
+            .starts_with(QUARANTINE_PREFIX)));
+    }
+
+    #[test]
+    fn test_apply_ref_updates_conflict() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = Storage::open(tmp.path().join("alice/storage")).unwrap();
+        let bob = Storage::open(tmp.path().join("bob/storage")).unwrap();
+        let alice_signer = MockSigner::new(&mut fastrand::Rng::new());
+        let alice_id = alice_signer.public_key();
+        let (proj_id, _, proj_repo, alice_head) =
+            fixtures::project(tmp.path().join("alice/project"), &alice, &alice_signer).unwrap();
+        let refname = Qualified::from_refstr(git::refname!("refs/heads/master")).unwrap();
+
+        transport::remote::mock::register(alice_id, alice.path());
+
+        // Have Bob fetch Alice's refs.
+        bob.repository(proj_id)
+            .unwrap()
+            .fetch(alice_id, *alice_id)
+            .unwrap();
+
+        // Alice makes a signed change.
+        let alice_proj_storage = alice.repository(proj_id).unwrap();
+        let alice_head = proj_repo.find_commit(alice_head).unwrap();
+        let alice_sig = git2::Signature::now("Alice", "alice@radicle.xyz").unwrap();
+        let alice_head = git::commit(
+            &proj_repo,
+            &alice_head,
+            &refname,
+            "Making changes",
+            &alice_sig,
+        )
+        .unwrap()
+        .id();
+        git::push(&proj_repo, "rad", [(&refname, &refname)]).unwrap();
+        alice_proj_storage.sign_refs(&alice_signer).unwrap();
+
+        // Bob stages Alice's change, and computes the updates to apply.
+        let bob_repo = bob.repository(proj_id).unwrap();
+        let quarantine = tempfile::Builder::new()
+            .prefix(QUARANTINE_PREFIX)
+            .tempdir_in(bob_repo.path())
+            .unwrap();
+        let staging = bob_repo.quarantine(quarantine.path()).unwrap();
+        staging
+            .backend
+            .remote_anonymous(
+                remote::Url {
+                    node: *alice_id,
+                    repo: proj_id,
+                    namespace: None,
+                }
+                .to_string()
+                .as_str(),
+            )
+            .unwrap()
+            .fetch(&["refs/namespaces/*:refs/namespaces/*"], None, None)
+            .unwrap();
+        staging.verify().unwrap();
+
+        let updates = bob_repo.ref_updates(&staging, None).unwrap();
+        assert_matches!(
+            updates.as_slice(),
+            &[RefUpdate::Updated { .. }, RefUpdate::Updated { .. }]
+        );
+
+        // Before the updates are applied, the last of the references is changed.
+        let RefUpdate::Updated { name, .. } = updates.last().unwrap() else {
+            unreachable!();
+        };
+        let bob_head = bob_repo.reference_oid(alice_id, &refname).unwrap();
+        bob_repo
+            .backend
+            .reference(name.as_str(), bob_head.into(), true, "")
+            .unwrap();
+        let bob_refs = bob_repo.references(alice_id).unwrap();
+
+        // Applying the updates fails, and none of the references are updated.
+        bob_repo.migrate(quarantine.path()).unwrap();
+        assert_matches!(
+            bob_repo.apply_ref_updates(&updates),
+            Err(FetchError::Conflict(conflict)) if &conflict == name
+        );
+        assert_eq!(bob_repo.references(alice_id).unwrap(), bob_refs);
+        assert_ne!(
+            bob_repo.reference_oid(alice_id, &refname).unwrap(),
+            Oid::from(alice_head)
+        );
+    }
+
     #[test]
     fn test_namespaced_references() {
         let tmp = tempfile::tempdir().unwrap();
@@ -1020,6 +2007,142 @@ mod tests {
         assert_eq!(refs, vec!["refs/heads/master", "refs/rad/id"]);
     }
 
+    #[test]
+    fn test_quorum() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, base) =
+            fixtures::project(tmp.path().join("project"), &storage, &signer).unwrap();
+        let repo = storage.repository(id).unwrap();
+        let raw = &repo.backend;
+        let sig = git2::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let commit = |parent: git2::Oid, msg: &str| -> Oid {
+            let parent = raw.find_commit(parent).unwrap();
+            let tree = parent.tree().unwrap();
+            raw.commit(None, &sig, &sig, msg, &tree, &[&parent])
+                .unwrap()
+                .into()
+        };
+        let mut rng = fastrand::Rng::new();
+        let alice = *MockSigner::new(&mut rng).public_key();
+        let bob = *MockSigner::new(&mut rng).public_key();
+        let eve = *MockSigner::new(&mut rng).public_key();
+
+        //   a2
+        //   |
+        //   a1  e1  b1
+        //   | /    /
+        //   base --
+        let a1 = commit(base, "a1");
+        let a2 = commit(*a1, "a2");
+        let e1 = commit(base, "e1");
+        let b1 = commit(base, "b1");
+        let base = Oid::from(base);
+
+        // Bob lags behind Alice.
+        let heads = BTreeMap::from_iter([(alice, a2), (bob, a1), (eve, base)]);
+        let head = quorum(&heads, 2, raw).unwrap().unwrap();
+        assert_eq!(head.oid, a1);
+        assert_eq!(head.delegates, BTreeSet::from_iter([alice, bob]));
+
+        let head = quorum(&heads, 1, raw).unwrap().unwrap();
+        assert_eq!(head.oid, a2);
+        assert_eq!(head.delegates, BTreeSet::from_iter([alice]));
+
+        let head = quorum(&heads, 3, raw).unwrap().unwrap();
+        assert_eq!(head.oid, base);
+        assert_eq!(head.delegates, BTreeSet::from_iter([alice, bob, eve]));
+
+        // Eve diverges, and doesn't hold back Alice and Bob.
+        let heads = BTreeMap::from_iter([(alice, a2), (bob, a1), (eve, e1)]);
+        let head = quorum(&heads, 2, raw).unwrap().unwrap();
+        assert_eq!(head.oid, a1);
+        assert_eq!(head.delegates, BTreeSet::from_iter([alice, bob]));
+
+        // Everyone diverges: only the base is agreed on.
+        let heads = BTreeMap::from_iter([(alice, a2), (bob, b1), (eve, e1)]);
+        let head = quorum(&heads, 2, raw).unwrap().unwrap();
+        assert_eq!(head.oid, base);
+        assert_eq!(head.delegates, BTreeSet::from_iter([alice, bob, eve]));
+
+        // Divergent heads that each reach the threshold are settled on their merge base.
+        let heads = BTreeMap::from_iter([(alice, a2), (bob, b1)]);
+        let head = quorum(&heads, 1, raw).unwrap().unwrap();
+        assert_eq!(head.oid, base);
+
+        // Eve's branch is missing.
+        let heads = BTreeMap::from_iter([(alice, a2), (bob, a1)]);
+        let head = quorum(&heads, 2, raw).unwrap().unwrap();
+        assert_eq!(head.oid, a1);
+        assert_eq!(quorum(&heads, 3, raw).unwrap(), None);
+        assert_eq!(quorum(&BTreeMap::new(), 1, raw).unwrap(), None);
+    }
+
+    #[test]
+    fn test_canonical_tags() {
+        let tmp = tempfile::tempdir().unwrap();
+        let alice = MockSigner::default();
+        let bob = MockSigner::new(&mut fastrand::Rng::new());
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (id, _, _, head) =
+            fixtures::project(tmp.path().join("project"), &storage, &alice).unwrap();
+        let repo = storage.repository(id).unwrap();
+        let parent = repo
+            .backend
+            .find_commit(head)
+            .unwrap()
+            .parent_id(0)
+            .unwrap();
+        let tag = git::refname!("refs/tags/v1.0");
+        let tag_of = |remote: &RemoteId| format!("refs/namespaces/{remote}/refs/tags/v1.0");
+
+        // With a single delegate, its tags are canonical.
+        repo.backend
+            .reference(&tag_of(alice.public_key()), head, false, "")
+            .unwrap();
+        assert_eq!(
+            repo.canonical_tags().unwrap(),
+            BTreeMap::from_iter([(tag.clone(), head.into())])
+        );
+        repo.set_canonical_tags().unwrap();
+        assert_eq!(repo.backend.refname_to_id(tag.as_str()).unwrap(), head);
+
+        // Add Bob as a delegate, with a threshold of two.
+        let mut doc = repo.project_of(alice.public_key()).unwrap();
+        doc.delegate("bob".to_owned(), *bob.public_key());
+        doc.threshold = 2;
+        let (_, sig) = doc.sign(&alice).unwrap();
+        doc.update(
+            alice.public_key(),
+            "Add bob",
+            &[(alice.public_key(), sig)],
+            &repo,
+        )
+        .unwrap();
+        assert!(repo.canonical_tags().unwrap().is_empty());
+
+        // Bob disagrees with Alice.
+        repo.backend
+            .reference(&tag_of(bob.public_key()), parent, false, "")
+            .unwrap();
+        assert!(repo.canonical_tags().unwrap().is_empty());
+
+        // Tags that are no longer canonical are removed.
+        repo.set_canonical_tags().unwrap();
+        assert!(repo.backend.find_reference(tag.as_str()).is_err());
+
+        // Bob agrees with Alice.
+        repo.backend
+            .reference(&tag_of(bob.public_key()), head, true, "")
+            .unwrap();
+        assert_eq!(
+            repo.set_canonical_tags().unwrap(),
+            BTreeMap::from_iter([(tag.clone(), head.into())])
+        );
+        assert_eq!(repo.backend.refname_to_id(tag.as_str()).unwrap(), head);
+    }
+
     #[test]
     #[ignore]
     // Test the remote transport using `git-upload-pack` and TCP streams.
@@ -1161,5 +2284,19 @@ mod tests {
 
         assert_eq!(remote.refs, signed);
         assert_eq!(*remote.refs, unsigned);
+
+        // References outside of the known categories are neither signed nor verified.
+        backend
+            .reference(
+                &format!("refs/namespaces/{alice}/refs/junk/master"),
+                head.id(),
+                false,
+                "",
+            )
+            .unwrap();
+
+        let signed = project.sign_refs(&signer).unwrap();
+        assert!(!signed.contains_key(&git::refname!("refs/junk/master")));
+        assert_matches!(project.verify(), Err(VerifyError::Category(_, _)));
     }
 }
@@ -0,0 +1,144 @@
+//! Object sharing between storage and working copies.
+//!
+//! Working copies checked out from storage borrow its objects through git alternates, instead
+//! of holding a copy of them. Storage keeps track of the working copies that borrow from it,
+//! so that a repository isn't removed while they still depend on it.
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+use thiserror::Error;
+
+use crate::git;
+
+/// Configuration key under which borrowing working copies are recorded in storage.
+pub const BORROWER_KEY: &str = "rad.borrower";
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("git: {0}")]
+    Git(#[from] git2::Error),
+    #[error("i/o: {0}")]
+    Io(#[from] io::Error),
+    #[error("path `{0}` is not valid UTF-8")]
+    InvalidPath(PathBuf),
+}
+
+/// Share the objects of the storage repository at `storage` with the `working` copy, and
+/// record the working copy as a borrower of the storage repository.
+pub fn share(working: &git2::Repository, storage: &Path) -> Result<(), Error> {
+    let storage = storage.canonicalize()?;
+    let objects = storage.join("objects");
+    let objects = objects
+        .to_str()
+        .ok_or_else(|| Error::InvalidPath(objects.clone()))?;
+
+    let info = working.path().join("objects").join("info");
+    let mut contents = match fs::read_to_string(info.join("alternates")) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err.into()),
+    };
+    if !contents.lines().any(|l| l == objects) {
+        contents.push_str(objects);
+        contents.push('\n');
+
+        fs::create_dir_all(&info)?;
+        fs::write(info.join("alternates"), contents)?;
+    }
+    // The object database may already be loaded, in which case it won't pick up the
+    // alternates file we just wrote.
+    working.odb()?.add_disk_alternate(objects)?;
+
+    let borrower = working.path().canonicalize()?;
+    let borrower = borrower
+        .to_str()
+        .ok_or_else(|| Error::InvalidPath(borrower.clone()))?;
+    let mut config = git2::Config::open(&storage.join("config"))?;
+
+    if !multivar(&config, BORROWER_KEY)?
+        .iter()
+        .any(|b| b == borrower)
+    {
+        // Nb. A pattern that matches no existing value adds a new one.
+        config.set_multivar(BORROWER_KEY, "^$", borrower)?;
+    }
+    disable_autogc(&mut config)?;
+
+    Ok(())
+}
+
+/// Disable automatic garbage collection, eg. by `git-receive-pack` or `git fetch`, on a
+/// storage repository. Automatic collection prunes unreferenced objects without consulting
+/// [`borrowers`], so pruning is left to maintenance, which does.
+pub fn disable_autogc(config: &mut git2::Config) -> Result<(), git2::Error> {
+    config.set_i32("gc.auto", 0)?;
+    config.set_bool("receive.autogc", false)?;
+
+    Ok(())
+}
+
+/// Working copies that currently borrow objects from the storage repository at `storage`.
+///
+/// Working copies that were deleted or dissociated since they were recorded are left out.
+pub fn borrowers(storage: &Path) -> Result<Vec<PathBuf>, Error> {
+    let storage = storage.canonicalize()?;
+    let objects = storage.join("objects");
+    let config = git2::Config::open(&storage.join("config"))?;
+    let mut borrowers = Vec::new();
+
+    for borrower in multivar(&config, BORROWER_KEY)? {
+        let borrower = PathBuf::from(borrower);
+
+        match alternates(&borrower) {
+            Ok(alternates) if alternates.contains(&objects) => borrowers.push(borrower),
+            Ok(_) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(borrowers)
+}
+
+/// Stop borrowing objects, by copying all borrowed objects into the working copy at `working`,
+/// which is the path to its git directory. This is what `git clone --dissociate` does.
+pub fn dissociate(working: &Path) -> Result<(), Error> {
+    git::run::<_, _, &str, &str>(working, ["repack", "-a", "-d", "-q"], [])?;
+    fs::remove_file(working.join("objects").join("info").join("alternates"))?;
+
+    Ok(())
+}
+
+/// The alternate object directories of the repository at the given git directory.
+fn alternates(git_dir: &Path) -> Result<Vec<PathBuf>, io::Error> {
+    if !git_dir.exists() {
+        return Err(io::ErrorKind::NotFound.into());
+    }
+    let contents = match fs::read_to_string(git_dir.join("objects").join("info").join("alternates"))
+    {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+    Ok(contents
+        .lines()
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// All the values of a multi-valued configuration key.
+fn multivar(config: &git2::Config, key: &str) -> Result<Vec<String>, git2::Error> {
+    let mut values = Vec::new();
+    let mut entries = match config.multivar(key, None) {
+        Ok(entries) => entries,
+        Err(err) if err.code() == git2::ErrorCode::NotFound => return Ok(values),
+        Err(err) => return Err(err),
+    };
+
+    while let Some(entry) = entries.next() {
+        if let Some(value) = entry?.value() {
+            values.push(value.to_owned());
+        }
+    }
+    Ok(values)
+}
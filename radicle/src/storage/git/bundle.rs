@@ -0,0 +1,260 @@
+//! Offline bundles.
+//!
+//! A bundle is a file holding the namespaced references of a repository, including the signed
+//! refs and identity branch of every remote, along with the objects they point to. Bundles can
+//! be carried to machines without network access, and imported into their storage. Importing
+//! a bundle goes through the same verification as a fetch.
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::{env, io};
+
+use crate::git;
+use crate::storage::git::{Repository, NAMESPACES_GLOB};
+use crate::storage::refs::SIGREFS_BRANCH;
+use crate::storage::{Error, FetchError, Oid, ReadRepository, RefUpdate, RemoteId};
+
+/// Create a bundle of the repository at the given path.
+///
+/// Objects reachable from the `known` tips, eg. the references of a previously imported
+/// bundle, are left out of the bundle, and must be present when it is imported. References
+/// pointing to a known tip are left out as well: they are taken to be unchanged.
+pub fn create<P: AsRef<Path>>(
+    repo: &Repository,
+    path: P,
+    known: impl IntoIterator<Item = Oid>,
+) -> Result<(), io::Error> {
+    let path = env::current_dir()?.join(path);
+    let mut args = vec![
+        "bundle".into(),
+        "create".into(),
+        "--quiet".into(),
+        path.into_os_string(),
+        format!("--glob={}", NAMESPACES_GLOB.as_str()).into(),
+    ];
+    args.extend(known.into_iter().map(|oid| format!("^{oid}").into()));
+
+    git::run::<_, _, &str, &str>(repo.path(), args, [])?;
+
+    Ok(())
+}
+
+/// Import a bundle created with [`create`] into the repository.
+///
+/// The bundle is fetched into a quarantine and verified like any fetch. If verification
+/// fails, the repository is left untouched. Since a bundle may only hold the references that
+/// changed, the quarantine is seeded with the repository's own references, and only the
+/// namespaces carried by the bundle are updated. Seeded references that are no longer signed
+/// were deleted by the remote, and are removed, while any reference carried by the bundle
+/// must be signed.
+pub fn import<P: AsRef<Path>>(repo: &Repository, path: P) -> Result<Vec<RefUpdate>, FetchError> {
+    let path = env::current_dir()?.join(path);
+    let objects = repo.path().join("objects");
+    let heads = heads(&path)?;
+    let namespaces = heads
+        .iter()
+        .map(|name| {
+            git::parse_ref_namespaced::<RemoteId>(name)
+                .map(|(namespace, _)| namespace)
+                .map_err(Error::from)
+        })
+        .collect::<Result<BTreeSet<_>, _>>()?;
+
+    repo.fetch_quarantined(Some(&namespaces), |staging| {
+        for r in repo.backend.references_glob(NAMESPACES_GLOB.as_str())? {
+            let r = r?;
+            let (Some(name), Some(oid)) = (r.name(), r.target()) else {
+                // Ignore symbolic refs, eg. `HEAD`.
+                continue;
+            };
+            staging
+                .backend
+                .reference(name, oid, true, "import (radicle)")?;
+        }
+        // Nb. The quarantine's alternate isn't persisted, so we have to pass it on to `git`,
+        // for the objects we already have to be found.
+        git::run(
+            staging.path(),
+            [
+                OsStr::new("fetch"),
+                OsStr::new("--quiet"),
+                OsStr::new("--no-tags"),
+                path.as_os_str(),
+                OsStr::new("+refs/namespaces/*:refs/namespaces/*"),
+            ],
+            [("GIT_ALTERNATE_OBJECT_DIRECTORIES", objects.as_os_str())],
+        )?;
+        // Make sure the packs written by `git` are picked up.
+        staging.backend.odb()?.refresh()?;
+
+        // Seeded references that are no longer signed were deleted by the remote. Unsigned
+        // references carried by the bundle are left in place, for verification to fail.
+        for namespace in &namespaces {
+            let signed = staging.remote(namespace)?.refs;
+            let glob = format!("refs/namespaces/{namespace}/refs/*");
+
+            let mut unsigned = Vec::new();
+
+            for r in staging.backend.references_glob(&glob)? {
+                let r = r?;
+                let name = r.name().ok_or(Error::InvalidRef)?;
+                let (_, refname) =
+                    git::parse_ref_namespaced::<RemoteId>(name).map_err(Error::from)?;
+
+                if refname != *SIGREFS_BRANCH
+                    && !signed.contains_key(&refname.to_ref_string())
+                    && !heads.contains(name)
+                {
+                    unsigned.push(name.to_owned());
+                }
+            }
+            for name in unsigned {
+                staging.backend.find_reference(&name)?.delete()?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Get the names of the references carried by a bundle.
+fn heads(path: &Path) -> Result<BTreeSet<String>, FetchError> {
+    let heads = git::run::<_, _, &str, &str>(
+        env::current_dir()?,
+        [
+            OsStr::new("bundle"),
+            OsStr::new("list-heads"),
+            path.as_os_str(),
+        ],
+        [],
+    )?;
+    let names = heads
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(_, name)| name.to_owned())
+        .collect();
+
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto::test::signer::MockSigner;
+    use crypto::Signer;
+
+    use super::*;
+    use crate::assert_matches;
+    use crate::rad;
+    use crate::storage::git::{Storage, VerifyError};
+    use crate::storage::{ReadRepository, WriteRepository, WriteStorage};
+    use crate::test::fixtures;
+
+    #[test]
+    fn test_bundle() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let remote = *signer.public_key();
+        let alice = Storage::open(tmp.path().join("alice")).unwrap();
+        let bob = Storage::open(tmp.path().join("bob")).unwrap();
+        let eve = Storage::open(tmp.path().join("eve")).unwrap();
+        let (proj, _, _, head) =
+            fixtures::project(tmp.path().join("project"), &alice, &signer).unwrap();
+        let alice_repo = alice.repository(proj).unwrap();
+        let bob_repo = bob.repository(proj).unwrap();
+        let master = format!("refs/namespaces/{remote}/refs/heads/master");
+
+        // Bob imports a full bundle.
+        let path = tmp.path().join("full.bundle");
+        create(&alice_repo, &path, []).unwrap();
+
+        let updates = import(&bob_repo, &path).unwrap();
+        assert_eq!(updates.len(), 3);
+        assert_eq!(
+            bob_repo.references(&remote).unwrap(),
+            alice_repo.references(&remote).unwrap()
+        );
+        bob_repo.verify().unwrap();
+
+        // Bob forks the project.
+        let bob_signer = MockSigner::new(&mut fastrand::Rng::new());
+        let bob_remote = *bob_signer.public_key();
+        rad::fork(proj, None, &bob_signer, &bob).unwrap();
+        let bob_refs = bob_repo.references(&bob_remote).unwrap();
+
+        // Alice makes a change, and creates an incremental bundle.
+        let known = alice_repo
+            .references(&remote)
+            .unwrap()
+            .values()
+            .copied()
+            .collect::<Vec<_>>();
+        let parent = alice_repo.backend.find_commit(head).unwrap();
+        let sig = git2::Signature::now("Alice", "alice@radicle.xyz").unwrap();
+        alice_repo
+            .backend
+            .commit(
+                Some(&master),
+                &sig,
+                &sig,
+                "Making changes",
+                &parent.tree().unwrap(),
+                &[&parent],
+            )
+            .unwrap();
+        alice_repo.sign_refs(&signer).unwrap();
+
+        let path = tmp.path().join("incremental.bundle");
+        create(&alice_repo, &path, known).unwrap();
+
+        // The incremental bundle can't be imported without the objects it's based on.
+        let eve_repo = eve.repository(proj).unwrap();
+        assert_matches!(import(&eve_repo, &path), Err(FetchError::Io(_)));
+
+        let updates = import(&bob_repo, &path).unwrap();
+        assert_matches!(
+            updates.as_slice(),
+            &[RefUpdate::Updated { .. }, RefUpdate::Updated { .. }]
+        );
+        assert_eq!(
+            bob_repo.references(&remote).unwrap(),
+            alice_repo.references(&remote).unwrap()
+        );
+        // Bob's own namespace, which isn't in the bundle, is left untouched.
+        assert_eq!(bob_repo.references(&bob_remote).unwrap(), bob_refs);
+        bob_repo.verify().unwrap();
+
+        // A bundle carrying unsigned references is rejected.
+        let unsigned = format!("refs/namespaces/{remote}/refs/heads/unsigned");
+        alice_repo
+            .backend
+            .reference(&unsigned, head, false, "")
+            .unwrap();
+
+        let path = tmp.path().join("unsigned.bundle");
+        create(&alice_repo, &path, []).unwrap();
+
+        assert_matches!(
+            import(&bob_repo, &path),
+            Err(FetchError::Verify(VerifyError::UnknownRef(..)))
+        );
+        assert!(bob_repo.backend.find_reference(&unsigned).is_err());
+
+        alice_repo
+            .backend
+            .find_reference(&unsigned)
+            .unwrap()
+            .delete()
+            .unwrap();
+
+        // A tampered bundle is rejected.
+        alice_repo
+            .backend
+            .reference(&master, head, true, "")
+            .unwrap();
+
+        let path = tmp.path().join("tampered.bundle");
+        create(&alice_repo, &path, []).unwrap();
+
+        assert_matches!(import(&eve_repo, &path), Err(FetchError::Verify(_)));
+        assert!(eve_repo.remote_ids().unwrap().next().is_none());
+    }
+}
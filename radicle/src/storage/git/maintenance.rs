@@ -0,0 +1,345 @@
+//! Storage maintenance.
+//!
+//! Verifies the remotes of every repository in storage, removes the references of remotes
+//! that are blocked, untracked or fail verification, cleans up abandoned fetch quarantines
+//! and runs garbage collection, so that unreferenced objects can be reclaimed.
+//!
+//! Objects of repositories that are borrowed by working copies are never pruned, since the
+//! working copies may depend on objects that are no longer referenced in storage.
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use std::{fmt, fs, io};
+
+use thiserror::Error;
+
+use crate::crypto::PublicKey;
+use crate::git;
+use crate::identity::Id;
+use crate::node::config::RemoteTracking;
+use crate::storage;
+use crate::storage::git::{alternates, Repository, Storage, VerifyError, QUARANTINE_PREFIX};
+use crate::storage::{refs, ReadRepository, RemoteId, WriteStorage};
+
+/// Quarantine directories that haven't been modified for this long are considered abandoned,
+/// eg. by a fetch that was interrupted.
+pub const QUARANTINE_EXPIRY: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("storage: {0}")]
+    Storage(#[from] storage::Error),
+    #[error("refs: {0}")]
+    Refs(#[from] refs::Error),
+    #[error("git: {0}")]
+    Git(#[from] git2::Error),
+    #[error("alternates: {0}")]
+    Alternates(#[from] alternates::Error),
+    #[error("i/o: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Maintenance options.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The local node. Its remote is never removed.
+    pub local: RemoteId,
+    /// Only report what would be done, without modifying storage.
+    pub dry_run: bool,
+    /// Remove the remotes that fail verification.
+    pub repair: bool,
+    /// Run garbage collection on each repository.
+    pub gc: bool,
+    /// Remotes that should be removed from all repositories.
+    pub blocked: HashSet<RemoteId>,
+    /// The node's remote tracking policy. If set, remotes that aren't tracked are removed.
+    pub tracking: Option<RemoteTracking>,
+}
+
+impl Options {
+    /// Default options for the given local node: verify, and garbage collect.
+    pub fn new(local: RemoteId) -> Self {
+        Self {
+            local,
+            dry_run: false,
+            repair: false,
+            gc: true,
+            blocked: HashSet::default(),
+            tracking: None,
+        }
+    }
+}
+
+/// Reason for removing a remote.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Reason {
+    /// The remote is blocked.
+    Blocked,
+    /// The remote isn't tracked.
+    Untracked,
+    /// The remote failed verification.
+    Unverified,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Blocked => write!(f, "blocked"),
+            Self::Untracked => write!(f, "untracked"),
+            Self::Unverified => write!(f, "unverified"),
+        }
+    }
+}
+
+/// The outcome of running maintenance on a repository.
+#[derive(Debug)]
+pub struct Report {
+    /// The repository.
+    pub id: Id,
+    /// Remotes that failed verification.
+    pub unverified: Vec<(RemoteId, VerifyError)>,
+    /// Remotes that were removed, or would have been on a dry run.
+    pub removed: Vec<(RemoteId, Reason)>,
+    /// Number of abandoned quarantines that were removed, or would have been on a dry run.
+    pub quarantines: usize,
+    /// Whether garbage collection was run.
+    pub gc: bool,
+    /// Working copies borrowing objects from the repository. If there are any, unreferenced
+    /// objects are not pruned.
+    pub borrowers: Vec<PathBuf>,
+}
+
+impl Report {
+    fn new(id: Id) -> Self {
+        Self {
+            id,
+            unverified: Vec::new(),
+            removed: Vec::new(),
+            quarantines: 0,
+            gc: false,
+            borrowers: Vec::new(),
+        }
+    }
+
+    /// Whether the repository was found to be in a good state.
+    pub fn is_clean(&self) -> bool {
+        self.unverified.is_empty() && self.removed.is_empty() && self.quarantines == 0
+    }
+}
+
+/// Run maintenance on all repositories in storage.
+pub fn run(storage: &Storage, options: &Options) -> Result<Vec<Report>, Error> {
+    let mut reports = Vec::new();
+
+    for id in storage.projects()? {
+        let repo = storage.repository(id)?;
+        reports.push(repository(&repo, options)?);
+    }
+    Ok(reports)
+}
+
+/// Run maintenance on a single repository.
+pub fn repository(repo: &Repository, options: &Options) -> Result<Report, Error> {
+    let mut report = Report::new(repo.id);
+    // If the identity can't be loaded, we can't tell who the delegates are, and so we don't
+    // remove untracked remotes.
+    let delegates = repo.project_identity().ok().map(|(_, doc)| {
+        doc.delegates
+            .into_iter()
+            .map(PublicKey::from)
+            .collect::<Vec<_>>()
+    });
+
+    for remote in repo.namespaces()? {
+        let reason = if remote == options.local {
+            None
+        } else if options.blocked.contains(&remote) {
+            Some(Reason::Blocked)
+        } else if let (Some(tracking), Some(delegates)) = (&options.tracking, &delegates) {
+            if tracking.is_tracked(&remote, delegates) {
+                None
+            } else {
+                Some(Reason::Untracked)
+            }
+        } else {
+            None
+        };
+
+        if let Some(reason) = reason {
+            if !options.dry_run {
+                remove(repo, &remote)?;
+            }
+            report.removed.push((remote, reason));
+            continue;
+        }
+
+        if let Err(err) = repo.verify_remote(&remote) {
+            log::warn!("Remote {remote} of {} failed verification: {err}", repo.id);
+
+            if options.repair && remote != options.local {
+                if !options.dry_run {
+                    remove(repo, &remote)?;
+                }
+                report.removed.push((remote, Reason::Unverified));
+            }
+            report.unverified.push((remote, err));
+        }
+    }
+
+    for entry in fs::read_dir(repo.path())? {
+        let entry = entry?;
+        if !entry
+            .file_name()
+            .to_string_lossy()
+            .starts_with(QUARANTINE_PREFIX)
+        {
+            continue;
+        }
+        let modified = entry.metadata()?.modified()?;
+        let elapsed = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+
+        if elapsed >= QUARANTINE_EXPIRY {
+            if !options.dry_run {
+                fs::remove_dir_all(entry.path())?;
+            }
+            report.quarantines += 1;
+        }
+    }
+
+    report.borrowers = alternates::borrowers(repo.path())?;
+
+    if options.gc && !options.dry_run {
+        // Nb. We use the default prune expiry, so that objects of a concurrent fetch, which are
+        // not yet referenced, are not pruned. Objects are kept altogether while working copies
+        // borrow from the repository, as they may still reference them.
+        let args = if report.borrowers.is_empty() {
+            vec!["gc", "--quiet"]
+        } else {
+            log::debug!(
+                "Not pruning {}: borrowed by {} working copies",
+                repo.id,
+                report.borrowers.len()
+            );
+            vec!["gc", "--quiet", "--prune=never"]
+        };
+        git::run::<_, _, &str, &str>(repo.path(), args, [])?;
+        report.gc = true;
+    }
+    Ok(report)
+}
+
+/// Remove all references of a remote.
+fn remove(repo: &Repository, remote: &RemoteId) -> Result<(), git2::Error> {
+    let glob = format!("refs/namespaces/{remote}/*");
+    let names = repo
+        .backend
+        .references_glob(&glob)?
+        .map(|r| r.map(|r| r.name().map(ToOwned::to_owned)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for name in names.into_iter().flatten() {
+        repo.backend.find_reference(&name)?.delete()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crypto::test::signer::MockSigner;
+    use crypto::Signer;
+
+    use super::*;
+    use crate::storage::WriteRepository;
+    use crate::test::arbitrary;
+    use crate::test::fixtures;
+
+    #[test]
+    fn test_maintenance() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let alice = *signer.public_key();
+        let eve = arbitrary::gen::<RemoteId>(1);
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (proj, _, _, head) =
+            fixtures::project(tmp.path().join("project"), &storage, &signer).unwrap();
+        let repo = storage.repository(proj).unwrap();
+        let eve_ref = format!("refs/namespaces/{eve}/refs/heads/master");
+
+        // Eve's namespace has no signed refs.
+        repo.raw().reference(&eve_ref, head, false, "").unwrap();
+
+        let mut options = Options::new(alice);
+        options.gc = false;
+
+        let report = repository(&repo, &options).unwrap();
+        assert_eq!(report.unverified.len(), 1);
+        assert_eq!(report.unverified[0].0, eve);
+        assert!(report.removed.is_empty());
+
+        // Nothing is removed on a dry run.
+        options.dry_run = true;
+        options.blocked.insert(eve);
+
+        let report = repository(&repo, &options).unwrap();
+        assert_eq!(report.removed, vec![(eve, Reason::Blocked)]);
+        assert!(repo.raw().find_reference(&eve_ref).is_ok());
+
+        // Alice is never removed, and Eve is untracked.
+        options.dry_run = false;
+        options.blocked.clear();
+        options.tracking = Some(RemoteTracking::DelegatesOnly);
+
+        let report = repository(&repo, &options).unwrap();
+        assert_eq!(report.removed, vec![(eve, Reason::Untracked)]);
+        assert!(report.unverified.is_empty());
+        assert_eq!(repo.namespaces().unwrap(), BTreeSet::from_iter([alice]));
+        assert!(repo.verify().is_ok());
+    }
+
+    #[test]
+    fn test_maintenance_borrowers() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (proj, _, _, _) =
+            fixtures::project(tmp.path().join("project"), &storage, &signer).unwrap();
+        let repo = storage.repository(proj).unwrap();
+        let working = git2::Repository::init(tmp.path().join("working")).unwrap();
+        let options = Options::new(*signer.public_key());
+
+        // Prune unreferenced objects right away.
+        repo.raw()
+            .config()
+            .unwrap()
+            .set_str("gc.pruneExpire", "now")
+            .unwrap();
+        let blob = repo.raw().blob(b"unreferenced").unwrap();
+
+        // The unreferenced object is kept while a working copy borrows from storage.
+        alternates::share(&working, repo.path()).unwrap();
+
+        // Automatic collection, which doesn't know about borrowers, is disabled.
+        let config = repo.raw().config().unwrap().snapshot().unwrap();
+        assert_eq!(config.get_i32("gc.auto").unwrap(), 0);
+        assert!(!config.get_bool("receive.autogc").unwrap());
+
+        let report = repository(&repo, &options).unwrap();
+        assert!(report.gc);
+        assert_eq!(report.borrowers.len(), 1);
+        assert!(repo.raw().find_blob(blob).is_ok());
+
+        // Once the working copy is dissociated, it's pruned.
+        alternates::dissociate(working.path()).unwrap();
+
+        let report = repository(&repo, &options).unwrap();
+        assert!(report.borrowers.is_empty());
+        assert!(git2::Repository::open_bare(repo.path())
+            .unwrap()
+            .find_blob(blob)
+            .is_err());
+    }
+}
@@ -0,0 +1,526 @@
+//! Mirroring of projects to external git remotes.
+//!
+//! Repositories in storage can have mirror targets, given as git URLs. Mirrors are read-only
+//! copies of a project's canonical refs: the canonical head of the default branch, and the
+//! canonical tags. Targets are only pushed to when these refs change. Targets, along with the
+//! status of their last sync, are recorded in the repository's git configuration, under
+//! `mirror.<url>.*`.
+use std::collections::BTreeMap;
+use std::io::Read as _;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{fmt, io, thread};
+
+use thiserror::Error;
+
+use crate::git;
+use crate::identity::Id;
+use crate::storage;
+use crate::storage::git::{ProjectError, Repository, Storage};
+use crate::storage::{Oid, ReadRepository, WriteRepository, WriteStorage};
+
+/// Configuration section under which mirror targets are recorded.
+pub const SECTION: &str = "mirror";
+/// How often a running push is checked for completion.
+const PUSH_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("git: {0}")]
+    Git(#[from] git2::Error),
+    #[error("storage: {0}")]
+    Storage(#[from] storage::Error),
+    #[error("project: {0}")]
+    Project(#[from] ProjectError),
+}
+
+/// Mirroring options.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Number of times a failed push is retried.
+    pub retries: u32,
+    /// Time to wait before retrying a failed push. Doubles with every attempt.
+    pub backoff: Duration,
+    /// Push to targets even if they are up to date.
+    pub force: bool,
+    /// Time after which a push that hasn't completed is aborted, and counts as failed.
+    pub timeout: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            retries: 2,
+            backoff: Duration::from_secs(1),
+            force: false,
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Status of a mirror target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// The target was never pushed to.
+    Pending,
+    /// The canonical refs were pushed to the target.
+    Synced {
+        /// Digest of the canonical refs that were pushed.
+        state: Oid,
+        /// When the refs were pushed, in seconds since the epoch.
+        timestamp: u64,
+    },
+    /// The last push to the target failed.
+    Failed {
+        /// The error returned by the last attempt.
+        error: String,
+        /// Number of attempts that were made.
+        attempts: u32,
+        /// When the last attempt was made, in seconds since the epoch.
+        timestamp: u64,
+    },
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending => write!(f, "pending"),
+            Self::Synced { .. } => write!(f, "synced"),
+            Self::Failed {
+                error, attempts, ..
+            } => write!(f, "failed after {attempts} attempt(s): {error}"),
+        }
+    }
+}
+
+/// A mirror target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    /// The git URL of the target.
+    pub url: String,
+    /// The status of the target.
+    pub status: Status,
+}
+
+/// The outcome of syncing a mirror target.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The target already had the canonical refs.
+    UpToDate,
+    /// The canonical refs were pushed to the target.
+    Pushed,
+    /// The canonical refs couldn't be pushed to the target.
+    Failed(String),
+}
+
+/// Add a mirror target to the repository. Returns `false` if it was already added.
+pub fn add(repo: &Repository, url: &str) -> Result<bool, Error> {
+    let mut config = config(repo)?;
+
+    if get(&config, url, "status")?.is_some() {
+        return Ok(false);
+    }
+    config.set_str(&key(url, "status"), "pending")?;
+
+    Ok(true)
+}
+
+/// Remove a mirror target from the repository. Returns `false` if it wasn't found.
+pub fn remove(repo: &Repository, url: &str) -> Result<bool, Error> {
+    let mut config = config(repo)?;
+
+    if get(&config, url, "status")?.is_none() {
+        return Ok(false);
+    }
+    for field in ["status", "state", "error", "attempts", "timestamp"] {
+        match config.remove(&key(url, field)) {
+            Err(e) if e.code() == git2::ErrorCode::NotFound => {}
+            result => result?,
+        }
+    }
+    Ok(true)
+}
+
+/// Get the mirror targets of the repository.
+pub fn targets(repo: &Repository) -> Result<Vec<Target>, Error> {
+    let config = config(repo)?;
+    let mut urls = Vec::new();
+    let mut entries = config.entries(Some(&format!(r"^{SECTION}\..*\.status$")))?;
+
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        let url = entry
+            .name()
+            .and_then(|n| n.strip_prefix(&format!("{SECTION}.")))
+            .and_then(|n| n.strip_suffix(".status"));
+
+        if let Some(url) = url {
+            urls.push(url.to_owned());
+        }
+    }
+
+    let mut targets = Vec::new();
+    for url in urls {
+        let status = status(&config, &url)?;
+        targets.push(Target { url, status });
+    }
+    Ok(targets)
+}
+
+/// Push the canonical refs of the repository to all its mirror targets that don't have them
+/// yet. Failed pushes are retried according to the given options. The status of each target
+/// is recorded.
+pub fn sync(repo: &Repository, options: &Options) -> Result<Vec<(String, Outcome)>, Error> {
+    let targets = targets(repo)?;
+    if targets.is_empty() {
+        return Ok(vec![]);
+    }
+    // Make sure the canonical refs at the top-level of storage are up to date, since this is
+    // what we push.
+    let head = repo.set_head()?;
+    let tags = repo.set_canonical_tags()?;
+    let (branch, _) = repo.head()?;
+    let state = digest(head, &tags)?;
+
+    let mut config = config(repo)?;
+    let mut outcomes = Vec::new();
+
+    for target in targets {
+        if let Status::Synced { state: synced, .. } = target.status {
+            if synced == state && !options.force {
+                outcomes.push((target.url, Outcome::UpToDate));
+                continue;
+            }
+        }
+
+        let mut attempts = 0;
+        let result = loop {
+            attempts += 1;
+
+            match push(repo, &target.url, &branch, options.timeout) {
+                Ok(()) => break Ok(()),
+                Err(err) if attempts > options.retries => break Err(err),
+                Err(err) => {
+                    log::warn!(
+                        "Failed to push {} to mirror {} (attempt {}): {}",
+                        repo.id,
+                        target.url,
+                        attempts,
+                        err
+                    );
+                    thread::sleep(options.backoff * 2u32.pow(attempts - 1));
+                }
+            }
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        match result {
+            Ok(()) => {
+                set_status(
+                    &mut config,
+                    &target.url,
+                    &Status::Synced { state, timestamp },
+                )?;
+                outcomes.push((target.url, Outcome::Pushed));
+            }
+            Err(err) => {
+                let error = err.to_string().trim().to_owned();
+
+                set_status(
+                    &mut config,
+                    &target.url,
+                    &Status::Failed {
+                        error: error.clone(),
+                        attempts,
+                        timestamp,
+                    },
+                )?;
+                outcomes.push((target.url, Outcome::Failed(error)));
+            }
+        }
+    }
+    Ok(outcomes)
+}
+
+/// Sync the mirror targets of all repositories in storage. Repositories without targets are
+/// skipped.
+pub fn run(
+    storage: &Storage,
+    options: &Options,
+) -> Result<Vec<(Id, Vec<(String, Outcome)>)>, Error> {
+    let mut results = Vec::new();
+
+    for id in storage.projects()? {
+        let repo = storage.repository(id)?;
+        let outcomes = sync(&repo, options)?;
+
+        if !outcomes.is_empty() {
+            results.push((id, outcomes));
+        }
+    }
+    Ok(results)
+}
+
+/// Push the canonical branch and tags to the given URL. Tags that are no longer canonical
+/// are removed from the target. The push is aborted if it doesn't complete within the given
+/// timeout.
+fn push(
+    repo: &Repository,
+    url: &str,
+    branch: &git::Qualified,
+    timeout: Duration,
+) -> Result<(), io::Error> {
+    let refspec = format!("{branch}:{branch}");
+    let mut child = Command::new("git")
+        .current_dir(repo.path())
+        .args([
+            "push",
+            "--quiet",
+            "--force",
+            "--prune",
+            url,
+            refspec.as_str(),
+            "refs/tags/*:refs/tags/*",
+        ])
+        // Never prompt for credentials.
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Nb. The error output is read on its own thread, so that the push can't block on a full
+    // pipe while we're waiting for it.
+    let stderr = child.stderr.take().map(|mut stderr| {
+        thread::spawn(move || {
+            let mut output = String::new();
+            stderr.read_to_string(&mut output).ok();
+            output
+        })
+    });
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            child.kill()?;
+            child.wait()?;
+
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("push timed out after {} second(s)", timeout.as_secs()),
+            ));
+        }
+        thread::sleep(PUSH_POLL_INTERVAL);
+    };
+
+    if status.success() {
+        return Ok(());
+    }
+    let output = stderr
+        .and_then(|reader| reader.join().ok())
+        .unwrap_or_default();
+
+    Err(io::Error::new(io::ErrorKind::Other, output))
+}
+
+/// Digest of the canonical refs, used to tell whether a target is up to date.
+fn digest(head: Oid, tags: &BTreeMap<git::RefString, Oid>) -> Result<Oid, git2::Error> {
+    let mut refs = format!("{head} HEAD\n");
+    for (name, oid) in tags {
+        refs.push_str(&format!("{oid} {name}\n"));
+    }
+    git2::Oid::hash_object(git2::ObjectType::Blob, refs.as_bytes()).map(Oid::from)
+}
+
+/// The repository's own configuration, without the user or system configuration.
+fn config(repo: &Repository) -> Result<git2::Config, git2::Error> {
+    repo.backend.config()?.open_level(git2::ConfigLevel::Local)
+}
+
+fn key(url: &str, field: &str) -> String {
+    format!("{SECTION}.{url}.{field}")
+}
+
+fn get(config: &git2::Config, url: &str, field: &str) -> Result<Option<String>, git2::Error> {
+    match config.get_string(&key(url, field)) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn status(config: &git2::Config, url: &str) -> Result<Status, git2::Error> {
+    let timestamp = get(config, url, "timestamp")?
+        .and_then(|t| t.parse().ok())
+        .unwrap_or_default();
+    let status = match get(config, url, "status")?.as_deref() {
+        Some("synced") => match get(config, url, "state")?.map(|s| s.parse()) {
+            Some(Ok(state)) => Status::Synced { state, timestamp },
+            _ => Status::Pending,
+        },
+        Some("failed") => Status::Failed {
+            error: get(config, url, "error")?.unwrap_or_default(),
+            attempts: get(config, url, "attempts")?
+                .and_then(|a| a.parse().ok())
+                .unwrap_or_default(),
+            timestamp,
+        },
+        _ => Status::Pending,
+    };
+    Ok(status)
+}
+
+fn set_status(config: &mut git2::Config, url: &str, status: &Status) -> Result<(), git2::Error> {
+    match status {
+        Status::Pending => {
+            config.set_str(&key(url, "status"), "pending")?;
+        }
+        Status::Synced { state, timestamp } => {
+            config.set_str(&key(url, "status"), "synced")?;
+            config.set_str(&key(url, "state"), &state.to_string())?;
+            config.set_str(&key(url, "timestamp"), &timestamp.to_string())?;
+        }
+        Status::Failed {
+            error,
+            attempts,
+            timestamp,
+        } => {
+            config.set_str(&key(url, "status"), "failed")?;
+            config.set_str(&key(url, "error"), error)?;
+            config.set_str(&key(url, "attempts"), &attempts.to_string())?;
+            config.set_str(&key(url, "timestamp"), &timestamp.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crypto::test::signer::MockSigner;
+    use crypto::Signer;
+
+    use super::*;
+    use crate::assert_matches;
+    use crate::test::fixtures;
+
+    #[test]
+    fn test_mirror() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let remote = *signer.public_key();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (proj, _, _, head) =
+            fixtures::project(tmp.path().join("project"), &storage, &signer).unwrap();
+        let repo = storage.repository(proj).unwrap();
+        let mirror = git2::Repository::init_bare(tmp.path().join("mirror.git")).unwrap();
+        let url = mirror.path().to_str().unwrap().to_owned();
+        let missing = tmp.path().join("missing.git").to_str().unwrap().to_owned();
+        let options = Options {
+            retries: 1,
+            backoff: Duration::from_millis(1),
+            force: false,
+            timeout: Duration::from_secs(60),
+        };
+
+        assert!(add(&repo, &url).unwrap());
+        assert!(!add(&repo, &url).unwrap());
+        assert!(add(&repo, &missing).unwrap());
+        assert_eq!(
+            targets(&repo).unwrap(),
+            vec![
+                Target {
+                    url: url.clone(),
+                    status: Status::Pending
+                },
+                Target {
+                    url: missing.clone(),
+                    status: Status::Pending
+                }
+            ]
+        );
+
+        // The canonical refs are pushed, and failures are recorded.
+        let outcomes = sync(&repo, &options).unwrap();
+        assert_eq!(outcomes[0], (url.clone(), Outcome::Pushed));
+        assert_matches!(&outcomes[1], (u, Outcome::Failed(_)) if *u == missing);
+        assert_eq!(mirror.refname_to_id("refs/heads/master").unwrap(), head);
+        assert_matches!(
+            targets(&repo).unwrap()[1].status,
+            Status::Failed { attempts: 2, .. }
+        );
+
+        // Nothing is pushed if the canonical refs didn't change.
+        assert!(remove(&repo, &missing).unwrap());
+        assert_eq!(
+            sync(&repo, &options).unwrap(),
+            vec![(url.clone(), Outcome::UpToDate)]
+        );
+
+        // A new canonical tag is pushed.
+        repo.backend
+            .reference(
+                &format!("refs/namespaces/{remote}/refs/tags/v1"),
+                head,
+                false,
+                "",
+            )
+            .unwrap();
+        repo.sign_refs(&signer).unwrap();
+
+        assert_eq!(
+            sync(&repo, &options).unwrap(),
+            vec![(url.clone(), Outcome::Pushed)]
+        );
+        assert_eq!(mirror.refname_to_id("refs/tags/v1").unwrap(), head);
+
+        // Tags that are no longer canonical are removed from the mirror.
+        repo.backend
+            .find_reference(&format!("refs/namespaces/{remote}/refs/tags/v1"))
+            .unwrap()
+            .delete()
+            .unwrap();
+        repo.sign_refs(&signer).unwrap();
+
+        assert_eq!(sync(&repo, &options).unwrap(), vec![(url, Outcome::Pushed)]);
+        assert!(mirror.refname_to_id("refs/tags/v1").is_err());
+    }
+
+    #[test]
+    fn test_mirror_timeout() {
+        let tmp = tempfile::tempdir().unwrap();
+        let signer = MockSigner::default();
+        let storage = Storage::open(tmp.path().join("storage")).unwrap();
+        let (proj, _, _, _) =
+            fixtures::project(tmp.path().join("project"), &storage, &signer).unwrap();
+        let repo = storage.repository(proj).unwrap();
+        let url = "ssh://mirror.example.com/project.git";
+        let options = Options {
+            retries: 0,
+            backoff: Duration::from_millis(1),
+            force: false,
+            timeout: Duration::from_millis(500),
+        };
+
+        // The target never answers.
+        repo.raw()
+            .config()
+            .unwrap()
+            .set_str("core.sshCommand", "sleep 30 &&")
+            .unwrap();
+        add(&repo, url).unwrap();
+
+        let started = Instant::now();
+        let outcomes = sync(&repo, &options).unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(30));
+        assert_matches!(
+            &outcomes[..],
+            [(u, Outcome::Failed(err))] if u == url && err.contains("timed out")
+        );
+    }
+}
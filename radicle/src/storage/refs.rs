@@ -11,6 +11,7 @@ use crypto::{PublicKey, Signature, Signer, SignerError, Unverified, Verified};
 use serde::Serialize;
 use thiserror::Error;
 
+use crate::cob;
 use crate::git;
 use crate::git::ext as git_ext;
 use crate::git::Oid;
@@ -23,6 +24,9 @@ pub use crate::git::refs::storage::*;
 pub const REFS_BLOB_PATH: &str = "refs";
 /// File in which the signature over the references is stored in the `refs/rad/sigrefs` branch.
 pub const SIGNATURE_BLOB_PATH: &str = "signature";
+/// Categories of references that can be published under a remote's namespace, eg.
+/// `refs/heads/*`. References outside of these categories are not signed or replicated.
+pub const CATEGORIES: [&str; 5] = ["heads", "tags", "notes", "cobs", "rad"];
 
 #[derive(Debug)]
 pub enum Updated {
@@ -46,6 +50,13 @@ pub enum Error {
     #[error("invalid reference: {0}")]
     Ref(#[from] git::RefError),
     #[error(transparent)]
+    Category(#[from] CategoryError),
+    #[error("signed parent {signed:?} does not match actual parent {actual:?}")]
+    InvalidParent {
+        signed: Option<Oid>,
+        actual: Option<Oid>,
+    },
+    #[error(transparent)]
     Git(#[from] git2::Error),
     #[error(transparent)]
     GitExt(#[from] git_ext::Error),
@@ -62,23 +73,55 @@ impl Error {
     }
 }
 
+/// Error returned when a reference is outside of the known [`CATEGORIES`].
+#[derive(Debug, Error)]
+pub enum CategoryError {
+    #[error(
+        "reference `{0}` is not in a known category, expected one of \
+        `refs/heads/*`, `refs/tags/*`, `refs/notes/*`, `refs/cobs/*` or `refs/rad/*`"
+    )]
+    Unknown(git::RefString),
+    #[error("reference `{0}` is not of the form `refs/cobs/<typename>/<object-id>`")]
+    InvalidCob(git::RefString),
+}
+
+/// Check that a reference, relative to a remote's namespace, belongs to one of the known
+/// [`CATEGORIES`]. Collaborative object references must also name a valid type and object.
+pub fn check_category(name: &git::RefStr) -> Result<(), CategoryError> {
+    let components = name.as_str().split('/').collect::<Vec<_>>();
+
+    match components.as_slice() {
+        ["refs", "cobs", typename, id] => {
+            if cob::TypeName::from_str(typename).is_err() || cob::ObjectId::from_str(id).is_err() {
+                return Err(CategoryError::InvalidCob(name.to_owned()));
+            }
+        }
+        ["refs", "cobs", ..] => return Err(CategoryError::InvalidCob(name.to_owned())),
+        ["refs", category, _, ..] if CATEGORIES.contains(category) => {}
+        _ => return Err(CategoryError::Unknown(name.to_owned())),
+    }
+    Ok(())
+}
+
 /// The published state of a local repository.
 #[derive(Default, Clone, Debug, PartialEq, Eq, Serialize)]
 pub struct Refs(BTreeMap<git::RefString, Oid>);
 
 impl Refs {
-    /// Verify the given signature on these refs, and return [`SignedRefs`] on success.
+    /// Verify the given signature on these refs and parent, and return [`SignedRefs`] on success.
     pub fn verified(
         self,
+        parent: Option<Oid>,
         signer: &PublicKey,
         signature: Signature,
     ) -> Result<SignedRefs<Verified>, Error> {
         let refs = self;
-        let msg = refs.canonical();
+        let msg = canonical::payload(&refs, parent);
 
         match signer.verify(&msg, &signature) {
             Ok(()) => Ok(SignedRefs {
                 refs,
+                parent,
                 signature,
                 _verified: PhantomData,
             }),
@@ -86,17 +129,19 @@ impl Refs {
         }
     }
 
-    /// Sign these refs with the given signer and return [`SignedRefs`].
-    pub fn signed<G>(self, signer: &G) -> Result<SignedRefs<Verified>, Error>
+    /// Sign these refs, along with the previous signed refs commit, if any, with the given
+    /// signer and return [`SignedRefs`].
+    pub fn signed<G>(self, parent: Option<Oid>, signer: &G) -> Result<SignedRefs<Verified>, Error>
     where
         G: Signer,
     {
         let refs = self;
-        let msg = refs.canonical();
+        let msg = canonical::payload(&refs, parent);
         let signature = signer.try_sign(&msg)?;
 
         Ok(SignedRefs {
             refs,
+            parent,
             signature,
             _verified: PhantomData,
         })
@@ -137,9 +182,11 @@ impl Refs {
 
     pub fn canonical(&self) -> Vec<u8> {
         let mut buf = String::new();
-        let refs = self
-            .iter()
-            .filter(|(name, oid)| name.as_refstr() != SIGREFS_BRANCH.as_ref() && !oid.is_zero());
+        let refs = self.iter().filter(|(name, oid)| {
+            name.as_refstr() != SIGREFS_BRANCH.as_ref()
+                && name.as_refstr() != SIGREFS_PARENT.as_ref()
+                && !oid.is_zero()
+        });
 
         for (name, oid) in refs {
             buf.push_str(&oid.to_string());
@@ -196,11 +243,17 @@ impl DerefMut for Refs {
 /// signature over the refs. This allows us to easily verify if a set of refs
 /// came from a particular key.
 ///
+/// The signature also covers the parent, ie. the previous signed refs commit, which
+/// chains signed refs together, and prevents older signed refs from being replayed.
+///
 /// The type parameter keeps track of whether the signature was [`Verified`] or
 /// [`Unverified`].
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct SignedRefs<V> {
     pub refs: Refs,
+    /// The previous signed refs commit, if any.
+    #[serde(skip)]
+    pub parent: Option<Oid>,
     #[serde(skip)]
     pub signature: Signature,
     #[serde(skip)]
@@ -208,9 +261,10 @@ pub struct SignedRefs<V> {
 }
 
 impl SignedRefs<Unverified> {
-    pub fn new(refs: Refs, signature: Signature) -> Self {
+    pub fn new(refs: Refs, parent: Option<Oid>, signature: Signature) -> Self {
         Self {
             refs,
+            parent,
             signature,
             _verified: PhantomData,
         }
@@ -220,6 +274,7 @@ impl SignedRefs<Unverified> {
         match self.verify(signer) {
             Ok(()) => Ok(SignedRefs {
                 refs: self.refs,
+                parent: self.parent,
                 signature: self.signature,
                 _verified: PhantomData,
             }),
@@ -228,7 +283,7 @@ impl SignedRefs<Unverified> {
     }
 
     pub fn verify(&self, signer: &PublicKey) -> Result<(), crypto::Error> {
-        let canonical = self.refs.canonical();
+        let canonical = canonical::payload(&self.refs, self.parent);
 
         match signer.verify(&canonical, &self.signature) {
             Ok(()) => Ok(()),
@@ -255,28 +310,37 @@ impl SignedRefs<Verified> {
         let signature = repo.blob_at(oid, Path::new(SIGNATURE_BLOB_PATH))?;
         let signature: crypto::Signature = signature.content().try_into()?;
 
-        match remote.verify(refs.content(), &signature) {
-            Ok(()) => {
-                let refs = Refs::from_canonical(refs.content())?;
+        remote.verify(refs.content(), &signature)?;
+
+        let (refs, parent) = canonical::from_payload(refs.content())?;
+        // The signed parent must be the actual parent of the commit, otherwise older signed
+        // refs could be replayed on top of newer ones. Signed refs created before parents were
+        // signed have no parent.
+        if parent.is_some() {
+            let actual = repo.commit(oid)?.parent_ids().next().map(Oid::from);
 
-                Ok(Self {
-                    refs,
-                    signature,
-                    _verified: PhantomData,
-                })
+            if actual != parent {
+                return Err(Error::InvalidParent {
+                    signed: parent,
+                    actual,
+                });
             }
-            Err(e) => Err(e.into()),
         }
+
+        Ok(Self {
+            refs,
+            parent,
+            signature,
+            _verified: PhantomData,
+        })
     }
 
     /// Save the signed refs to disk.
     /// This creates a new commit on the signed refs branch, and updates the branch pointer.
-    pub fn save<S: WriteRepository>(
-        &self,
-        // TODO: This should be part of the signed refs.
-        remote: &RemoteId,
-        repo: &S,
-    ) -> Result<Updated, Error> {
+    ///
+    /// The current head of the signed refs branch must be the signed parent, otherwise
+    /// [`Error::InvalidParent`] is returned.
+    pub fn save<S: WriteRepository>(&self, remote: &RemoteId, repo: &S) -> Result<Updated, Error> {
         let sigref = &SIGREFS_BRANCH;
         let parent = match repo.reference(remote, sigref) {
             Ok(r) => Some(r.peel_to_commit()?),
@@ -285,9 +349,34 @@ impl SignedRefs<Verified> {
             Err(e) => return Err(e.into()),
         };
 
+        if let Some(ref parent) = parent {
+            // Signing the same refs again only changes the parent, so we check whether the
+            // refs themselves changed.
+            let blob = parent
+                .tree()?
+                .get_path(Path::new(REFS_BLOB_PATH))?
+                .to_object(repo.raw())?
+                .peel_to_blob()?;
+            let (refs, _) = canonical::from_payload(blob.content())?;
+
+            if refs.canonical() == self.refs.canonical() {
+                return Ok(Updated::Unchanged {
+                    oid: parent.id().into(),
+                });
+            }
+        }
+
+        let actual: Option<Oid> = parent.as_ref().map(|c| c.id().into());
+        if actual != self.parent {
+            return Err(Error::InvalidParent {
+                signed: self.parent,
+                actual,
+            });
+        }
+
         let tree = {
             let raw = repo.raw();
-            let refs_blob_oid = raw.blob(&self.canonical())?;
+            let refs_blob_oid = raw.blob(&canonical::payload(&self.refs, self.parent))?;
             let sig_blob_oid = raw.blob(self.signature.as_ref())?;
 
             let mut builder = raw.treebuilder(None)?;
@@ -299,14 +388,6 @@ impl SignedRefs<Verified> {
             raw.find_tree(oid)
         }?;
 
-        if let Some(ref parent) = parent {
-            if parent.tree()?.id() == tree.id() {
-                return Ok(Updated::Unchanged {
-                    oid: parent.id().into(),
-                });
-            }
-        }
-
         let sigref = sigref.with_namespace(remote.into());
         let author = repo.raw().signature()?;
         let commit = repo.raw().commit(
@@ -334,6 +415,7 @@ impl SignedRefs<Verified> {
     pub fn unverified(self) -> SignedRefs<Unverified> {
         SignedRefs {
             refs: self.refs,
+            parent: self.parent,
             signature: self.signature,
             _verified: PhantomData,
         }
@@ -362,6 +444,24 @@ pub mod canonical {
         #[error(transparent)]
         Git(#[from] git2::Error),
     }
+
+    /// The signed payload: the canonical refs, followed by the parent, if any.
+    pub fn payload(refs: &Refs, parent: Option<Oid>) -> Vec<u8> {
+        let mut buf = refs.canonical();
+
+        if let Some(parent) = parent {
+            buf.extend_from_slice(format!("{} {}\n", parent, SIGREFS_PARENT.as_str()).as_bytes());
+        }
+        buf
+    }
+
+    /// Decode a signed payload into refs and parent.
+    pub fn from_payload(bytes: &[u8]) -> Result<(Refs, Option<Oid>), Error> {
+        let mut refs = Refs::from_canonical(bytes)?;
+        let parent = refs.remove(&SIGREFS_PARENT.to_ref_string());
+
+        Ok((refs, parent))
+    }
 }
 
 #[cfg(test)]
@@ -369,6 +469,48 @@ mod tests {
     use super::*;
     use qcheck_macros::quickcheck;
 
+    #[test]
+    fn test_check_category() {
+        let oid = "d96f425412c9f8ad5d9a9a05c9831d0728e2338d";
+
+        for name in [
+            "refs/heads/master",
+            "refs/heads/feature/1",
+            "refs/tags/v1.0",
+            "refs/notes/commits",
+            "refs/rad/id",
+            "refs/rad/sigrefs",
+        ] {
+            check_category(&git::RefString::try_from(name).unwrap()).unwrap();
+        }
+        check_category(
+            &git::RefString::try_from(format!("refs/cobs/xyz.radicle.issue/{oid}")).unwrap(),
+        )
+        .unwrap();
+
+        for name in [
+            "refs/remotes/origin/master",
+            "refs/junk",
+            "refs/heads",
+            "HEAD",
+        ] {
+            assert!(matches!(
+                check_category(&git::RefString::try_from(name).unwrap()),
+                Err(CategoryError::Unknown(_))
+            ));
+        }
+        for name in [
+            "refs/cobs/xyz.radicle.issue",
+            "refs/cobs/xyz.radicle.issue/master",
+            "refs/cobs/xyz.radicle.issue/{oid}/x",
+        ] {
+            assert!(matches!(
+                check_category(&git::RefString::try_from(name.replace("{oid}", oid)).unwrap()),
+                Err(CategoryError::InvalidCob(_))
+            ));
+        }
+    }
+
     #[quickcheck]
     fn prop_canonical_roundtrip(refs: Refs) {
         let encoded = refs.canonical();
@@ -376,4 +518,14 @@ mod tests {
 
         assert_eq!(refs, decoded);
     }
+
+    #[quickcheck]
+    fn prop_payload_roundtrip(refs: Refs, parent: Option<[u8; 20]>) {
+        let parent = parent.map(|bytes| Oid::try_from(bytes.as_slice()).unwrap());
+        let parent = parent.filter(|oid| !oid.is_zero());
+        let encoded = canonical::payload(&refs, parent);
+        let decoded = canonical::from_payload(&encoded).unwrap();
+
+        assert_eq!((refs, parent), decoded);
+    }
 }
@@ -114,8 +114,11 @@ impl Arbitrary for SignedRefs<Unverified> {
         let bytes: [u8; 64] = Arbitrary::arbitrary(g);
         let signature = crypto::Signature::from(bytes);
         let refs = Refs::arbitrary(g);
+        let parent = Option::<[u8; 20]>::arbitrary(g)
+            .map(|bytes| git::Oid::try_from(bytes.as_slice()).unwrap())
+            .filter(|oid| !oid.is_zero());
 
-        Self::new(refs, signature)
+        Self::new(refs, parent, signature)
     }
 }
 
@@ -160,7 +163,7 @@ impl Arbitrary for storage::Remote<crypto::Verified> {
     fn arbitrary(g: &mut qcheck::Gen) -> Self {
         let refs = Refs::arbitrary(g);
         let signer = MockSigner::arbitrary(g);
-        let signed = refs.signed(&signer).unwrap();
+        let signed = refs.signed(None, &signer).unwrap();
 
         storage::Remote::new(*signer.public_key(), signed)
     }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 
 use git_ref_format as fmt;
@@ -73,6 +73,17 @@ impl ReadRepository for MockRepository {
         todo!()
     }
 
+    fn canonical_branch_head(
+        &self,
+        _branch: &BranchName,
+    ) -> Result<(fmt::Qualified, Oid), ProjectError> {
+        todo!()
+    }
+
+    fn canonical_tags(&self) -> Result<BTreeMap<git::RefString, Oid>, ProjectError> {
+        todo!()
+    }
+
     fn path(&self) -> &std::path::Path {
         todo!()
     }
@@ -149,6 +160,10 @@ impl WriteRepository for MockRepository {
         todo!()
     }
 
+    fn set_canonical_tags(&self) -> Result<BTreeMap<git::RefString, Oid>, ProjectError> {
+        todo!()
+    }
+
     fn sign_refs<G: Signer>(
         &self,
         _signer: &G,